@@ -0,0 +1,15 @@
+-- Hourly aggregates of ratio snapshots older than `rollup_after_days`, which
+-- replace the raw rows they were condensed from. Prices and ratio are averages
+CREATE TABLE ratio_snapshots_hourly (
+    pair_name TEXT NOT NULL,
+    hour_start TEXT NOT NULL,
+    symbol_a TEXT NOT NULL,
+    symbol_b TEXT NOT NULL,
+    price_a DOUBLE PRECISION NOT NULL,
+    price_b DOUBLE PRECISION NOT NULL,
+    ratio DOUBLE PRECISION NOT NULL,
+    min_ratio DOUBLE PRECISION NOT NULL,
+    max_ratio DOUBLE PRECISION NOT NULL,
+    samples BIGINT NOT NULL,
+    PRIMARY KEY (pair_name, hour_start)
+);
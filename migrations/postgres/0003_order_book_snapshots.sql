@@ -0,0 +1,15 @@
+-- Top levels of order books fetched for volume and slippage analysis, when
+-- `order_book_levels` is set. `data` holds {"bids": [[price, qty], ...],
+-- "asks": [...]} as JSON, gzipped when `encoding` is "json+gzip"
+CREATE TABLE order_book_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    best_bid DOUBLE PRECISION NOT NULL,
+    best_ask DOUBLE PRECISION NOT NULL,
+    encoding TEXT NOT NULL,
+    data BYTEA NOT NULL,
+    timestamp TEXT NOT NULL
+);
+
+CREATE INDEX idx_order_book_snapshots_symbol_timestamp
+ON order_book_snapshots(symbol, timestamp DESC);
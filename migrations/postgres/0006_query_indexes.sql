@@ -0,0 +1,11 @@
+-- `alerts` without a pair reads the newest alerts across all pairs, which
+-- otherwise scans and sorts the whole table
+CREATE INDEX idx_alerts_timestamp
+ON alerts(timestamp);
+
+-- Replaces the pair and time index with one that also carries the ratio, so
+-- statistics over a range are answered from the index alone
+DROP INDEX IF EXISTS idx_ratio_snapshots_pair_timestamp;
+
+CREATE INDEX idx_ratio_snapshots_pair_timestamp_ratio
+ON ratio_snapshots(pair_name, timestamp, ratio);
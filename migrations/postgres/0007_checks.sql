@@ -0,0 +1,17 @@
+-- One row per pair and monitoring cycle: how long fetching its ratio took
+-- and, for failed checks, what went wrong. `error_class` is NULL when the
+-- check succeeded
+CREATE TABLE checks (
+    id BIGSERIAL PRIMARY KEY,
+    pair_name TEXT NOT NULL,
+    latency_ms BIGINT NOT NULL,
+    error_class TEXT,
+    error TEXT,
+    timestamp TEXT NOT NULL
+);
+
+CREATE INDEX idx_checks_pair_timestamp
+ON checks(pair_name, timestamp DESC);
+
+CREATE INDEX idx_checks_timestamp
+ON checks(timestamp);
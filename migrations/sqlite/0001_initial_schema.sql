@@ -0,0 +1,155 @@
+-- Tables as of the switch to migrations. IF NOT EXISTS lets databases created
+-- before it adopt this version without changes
+
+CREATE TABLE IF NOT EXISTS ratio_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pair_name TEXT NOT NULL,
+    symbol_a TEXT NOT NULL,
+    symbol_b TEXT NOT NULL,
+    price_a REAL NOT NULL,
+    price_b REAL NOT NULL,
+    ratio REAL NOT NULL,
+    timestamp TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ratio_snapshots_pair_timestamp
+ON ratio_snapshots(pair_name, timestamp DESC);
+
+CREATE TABLE IF NOT EXISTS alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pair_name TEXT NOT NULL,
+    ratio REAL NOT NULL,
+    change_percentage REAL NOT NULL,
+    threshold REAL NOT NULL,
+    timestamp TEXT NOT NULL,
+    direction TEXT,
+    price_a REAL,
+    price_b REAL,
+    window_secs INTEGER,
+    severity TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_alerts_pair_timestamp
+ON alerts(pair_name, timestamp DESC);
+
+CREATE TABLE IF NOT EXISTS volume_ratios (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pair_name TEXT NOT NULL,
+    symbol_a TEXT NOT NULL,
+    symbol_b TEXT NOT NULL,
+    volume REAL NOT NULL,
+    effective_price_a REAL NOT NULL,
+    effective_price_b REAL NOT NULL,
+    ratio REAL NOT NULL,
+    slippage_a REAL NOT NULL,
+    slippage_b REAL NOT NULL,
+    timestamp TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_volume_ratios_pair_timestamp
+ON volume_ratios(pair_name, timestamp DESC);
+
+-- Monitor alert state, survives restarts
+CREATE TABLE IF NOT EXISTS triggered_thresholds (
+    pair_name TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    threshold REAL NOT NULL,
+    triggered_at TEXT NOT NULL,
+    PRIMARY KEY (pair_name, metric, threshold)
+);
+
+-- Pairs skipped by the monitor until resumed
+CREATE TABLE IF NOT EXISTS paused_pairs (
+    pair_name TEXT PRIMARY KEY,
+    paused_at TEXT NOT NULL
+);
+
+-- Pairs whose alerts are held back until a time
+CREATE TABLE IF NOT EXISTS snoozed_pairs (
+    pair_name TEXT PRIMARY KEY,
+    until TEXT NOT NULL
+);
+
+-- Chats that receive a pair's alerts, managed through the bot
+CREATE TABLE IF NOT EXISTS subscriptions (
+    chat_id INTEGER NOT NULL,
+    pair_name TEXT NOT NULL,
+    subscribed_at TEXT NOT NULL,
+    PRIMARY KEY (chat_id, pair_name)
+);
+
+-- Pairs each bot user starred for the Favorites menu
+CREATE TABLE IF NOT EXISTS favorites (
+    user_id INTEGER NOT NULL,
+    pair_name TEXT NOT NULL,
+    added_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, pair_name)
+);
+
+-- Ratio pairs added through the bot, monitored alongside the config's
+CREATE TABLE IF NOT EXISTS bot_pairs (
+    name TEXT PRIMARY KEY,
+    symbol_a TEXT NOT NULL,
+    symbol_b TEXT NOT NULL,
+    created_by INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Change rules set through the bot, replacing the configured ones
+CREATE TABLE IF NOT EXISTS rule_overrides (
+    pair_name TEXT PRIMARY KEY,
+    window_secs INTEGER NOT NULL,
+    thresholds TEXT NOT NULL,
+    updated_by INTEGER NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Who acknowledged a pair's alert, and when
+CREATE TABLE IF NOT EXISTS acknowledgements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pair_name TEXT NOT NULL,
+    user_id INTEGER NOT NULL,
+    user_name TEXT NOT NULL,
+    chat_id INTEGER NOT NULL,
+    message_id INTEGER NOT NULL,
+    acknowledged_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_acknowledgements_pair_time
+ON acknowledgements(pair_name, acknowledged_at DESC);
+
+-- Audit log of the bot's commands and button presses
+CREATE TABLE IF NOT EXISTS bot_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    user_name TEXT NOT NULL,
+    chat_id INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    result TEXT NOT NULL,
+    timestamp TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_bot_log_user_time
+ON bot_log(user_id, timestamp DESC);
+
+-- Single row held by the running monitor
+CREATE TABLE IF NOT EXISTS monitor_lease (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    owner TEXT NOT NULL,
+    acquired_at TEXT NOT NULL,
+    heartbeat_at TEXT NOT NULL
+);
+
+-- Fingerprints of recently sent alerts
+CREATE TABLE IF NOT EXISTS sent_alerts (
+    fingerprint TEXT PRIMARY KEY,
+    sent_at TEXT NOT NULL
+);
+
+-- Notifications a channel failed to deliver
+CREATE TABLE IF NOT EXISTS notification_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    channel TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    queued_at TEXT NOT NULL
+);
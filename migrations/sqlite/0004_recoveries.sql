@@ -0,0 +1,14 @@
+-- Excursions past a pair's thresholds that returned to normal, for the time
+-- to recovery in `alerts --summary`
+CREATE TABLE recoveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pair_name TEXT NOT NULL,
+    window_secs INTEGER NOT NULL,
+    change_percentage REAL NOT NULL,
+    started_at TEXT NOT NULL,
+    duration_secs INTEGER NOT NULL,
+    timestamp TEXT NOT NULL
+);
+
+CREATE INDEX idx_recoveries_pair_timestamp
+ON recoveries(pair_name, timestamp DESC);
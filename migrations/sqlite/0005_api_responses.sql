@@ -0,0 +1,15 @@
+-- Raw Binance responses of the pairs in `database.archive`, for tracing an
+-- anomalous ratio back to the data received. `body` is the gzipped response
+-- text and `size` its length, which the size limit counts
+CREATE TABLE api_responses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    endpoint TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    body BLOB NOT NULL,
+    size INTEGER NOT NULL,
+    timestamp TEXT NOT NULL
+);
+
+CREATE INDEX idx_api_responses_symbol_timestamp
+ON api_responses(symbol, timestamp DESC);
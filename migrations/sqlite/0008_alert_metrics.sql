@@ -0,0 +1,5 @@
+-- Which check raised an alert: `window:<secs>` for a change rule on the mid
+-- ratio, `volume_window:<secs>` for one on the executable ratio, NULL for
+-- daily open thresholds. Older rows have no metric; those with a window
+-- came from change rules on the mid ratio
+ALTER TABLE alerts ADD COLUMN metric TEXT;
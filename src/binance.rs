@@ -1,12 +1,35 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::config::Config;
+use crate::database::Database;
 
 const BINANCE_API_BASE: &str = "https://api.binance.com/api/v3";
+/// Archived responses between checks of the archive's age and size limits
+const ARCHIVE_PRUNE_EVERY: u64 = 100;
+/// Most klines Binance returns for one request
+const MAX_KLINES: usize = 1000;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct BinanceClient {
     client: Client,
+    /// Where raw responses are kept, when `database.archive` is set
+    archive: Option<ResponseArchive>,
+}
+
+/// Keeps the raw ticker and order book responses of some symbols
+#[derive(Clone)]
+struct ResponseArchive {
+    database: Database,
+    symbols: Arc<HashSet<String>>,
+    max_age_days: i64,
+    max_bytes: u64,
+    writes: Arc<AtomicU64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -17,6 +40,7 @@ pub struct TickerPrice {
 
 #[derive(Debug, Deserialize)]
 pub struct OrderBook {
+    #[allow(dead_code)]
     #[serde(rename = "lastUpdateId")]
     pub last_update_id: u64,
     pub bids: Vec<(String, String)>, // price, quantity
@@ -29,6 +53,25 @@ pub struct PriceInfo {
     pub price: f64,
 }
 
+#[derive(Debug, Deserialize)]
+struct ExchangeInfo {
+    symbols: Vec<SymbolInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SymbolInfo {
+    status: String,
+}
+
+/// Closing price of a historical candle
+#[derive(Debug, Clone)]
+pub struct KlineClose {
+    /// Candle open time in milliseconds, identical across symbols
+    pub open_time: i64,
+    pub close_time: DateTime<Utc>,
+    pub close: f64,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct OrderBookInfo {
     pub symbol: String,
@@ -38,10 +81,119 @@ pub struct OrderBookInfo {
     pub asks: Vec<(f64, f64)>, // price, quantity
 }
 
+/// Binance answered with an error status, e.g. 429 when rate limited
+#[derive(Debug)]
+pub struct StatusError {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Error pages can be long; Binance's own errors are short JSON
+        let body: String = self.body.chars().take(200).collect();
+        write!(f, "Binance returned {}: {}", self.status, body.trim())
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+impl StatusError {
+    fn check(status: StatusCode, body: &str) -> std::result::Result<(), Self> {
+        if status.is_success() {
+            return Ok(());
+        }
+        Err(Self {
+            status,
+            body: body.to_string(),
+        })
+    }
+}
+
+/// Kind of a failed request for the `checks` table: `timeout`, `connect`,
+/// `rate_limit`, `server` (5xx), `http` (other statuses), `network`,
+/// `parse`, or `other` for failures outside the request
+pub fn error_class(error: &anyhow::Error) -> &'static str {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<StatusError>() {
+            return match e.status {
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::IM_A_TEAPOT => "rate_limit",
+                status if status.is_server_error() => "server",
+                _ => "http",
+            };
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return if e.is_timeout() {
+                "timeout"
+            } else if e.is_connect() {
+                "connect"
+            } else {
+                "network"
+            };
+        }
+        if cause.is::<serde_json::Error>() || cause.is::<std::num::ParseFloatError>() {
+            return "parse";
+        }
+    }
+    "other"
+}
+
 impl BinanceClient {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            archive: None,
+        }
+    }
+
+    /// Keep the raw ticker and order book responses of the pairs in
+    /// `database.archive` in the database
+    pub fn with_response_archive(mut self, config: &Config, database: &Database) -> Result<Self> {
+        let Some(archive) = &config.database.archive else {
+            return Ok(self);
+        };
+
+        let mut symbols = HashSet::new();
+        for name in &archive.pairs {
+            symbols.extend(config.pair_symbols(name)?);
+        }
+        log::info!("Archiving raw responses of {}", archive.pairs.join(", "));
+
+        self.archive = Some(ResponseArchive {
+            database: database.clone(),
+            symbols: Arc::new(symbols),
+            max_age_days: archive.max_age_days,
+            max_bytes: archive.max_size_mb * 1024 * 1024,
+            writes: Arc::new(AtomicU64::new(0)),
+        });
+        Ok(self)
+    }
+
+    /// Archive a response body if its symbol is archived. Failures are
+    /// logged, the response is used either way
+    async fn archive_response(&self, symbol: &str, endpoint: &str, status: StatusCode, body: &str) {
+        let Some(archive) = &self.archive else {
+            return;
+        };
+        if !archive.symbols.contains(symbol) {
+            return;
+        }
+
+        if let Err(e) = archive
+            .database
+            .insert_api_response(symbol, endpoint, status.as_u16(), body, Utc::now())
+            .await
+        {
+            log::warn!("Failed to archive {} response of {}: {}", endpoint, symbol, e);
+            return;
+        }
+
+        if archive.writes.fetch_add(1, Ordering::Relaxed) % ARCHIVE_PRUNE_EVERY == 0 {
+            match archive.database.prune_api_responses(archive.max_age_days, archive.max_bytes).await {
+                Ok(0) => {}
+                Ok(dropped) => log::debug!("Dropped {} archived responses", dropped),
+                Err(e) => log::warn!("Failed to prune archived responses: {}", e),
+            }
         }
     }
 
@@ -55,9 +207,16 @@ impl BinanceClient {
             .await
             .with_context(|| format!("Failed to fetch price for {}", symbol))?;
 
-        let ticker: TickerPrice = response
-            .json()
+        let status = response.status();
+        let body = response
+            .text()
             .await
+            .with_context(|| format!("Failed to read price response for {}", symbol))?;
+        self.archive_response(symbol, "ticker", status, &body).await;
+        StatusError::check(status, &body)
+            .with_context(|| format!("Failed to fetch price for {}", symbol))?;
+
+        let ticker: TickerPrice = serde_json::from_str(&body)
             .with_context(|| format!("Failed to parse price response for {}", symbol))?;
 
         let price = ticker.price.parse::<f64>()
@@ -69,6 +228,65 @@ impl BinanceClient {
         })
     }
 
+    /// Fetch current price for a symbol, returning `None` if Binance has no such market
+    pub async fn try_get_price(&self, symbol: &str) -> Result<Option<PriceInfo>> {
+        let url = format!("{}/ticker/price?symbol={}", BINANCE_API_BASE, symbol);
+
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch price for {}", symbol))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .with_context(|| format!("Failed to read price response for {}", symbol))?;
+        self.archive_response(symbol, "ticker", status, &body).await;
+
+        // Binance answers unknown symbols with 400 "Invalid symbol"
+        if status == StatusCode::BAD_REQUEST {
+            return Ok(None);
+        }
+        StatusError::check(status, &body)
+            .with_context(|| format!("Failed to fetch price for {}", symbol))?;
+
+        let ticker: TickerPrice = serde_json::from_str(&body)
+            .with_context(|| format!("Failed to parse price response for {}", symbol))?;
+
+        let price = ticker.price.parse::<f64>()
+            .with_context(|| format!("Failed to parse price value: {}", ticker.price))?;
+
+        Ok(Some(PriceInfo {
+            symbol: ticker.symbol,
+            price,
+        }))
+    }
+
+    /// Whether Binance lists a symbol and it is currently trading
+    pub async fn is_trading(&self, symbol: &str) -> Result<bool> {
+        let url = format!("{}/exchangeInfo?symbol={}", BINANCE_API_BASE, symbol);
+
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch exchange info for {}", symbol))?;
+
+        // Binance answers unknown symbols with 400 "Invalid symbol"
+        if response.status() == StatusCode::BAD_REQUEST {
+            return Ok(false);
+        }
+
+        let info: ExchangeInfo = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse exchange info for {}", symbol))?;
+
+        Ok(info.symbols.iter().any(|s| s.status == "TRADING"))
+    }
+
     /// Fetch order book for a symbol
     pub async fn get_order_book(&self, symbol: &str, limit: u32) -> Result<OrderBookInfo> {
         let url = format!(
@@ -82,9 +300,16 @@ impl BinanceClient {
             .await
             .with_context(|| format!("Failed to fetch order book for {}", symbol))?;
 
-        let order_book: OrderBook = response
-            .json()
+        let status = response.status();
+        let body = response
+            .text()
             .await
+            .with_context(|| format!("Failed to read order book response for {}", symbol))?;
+        self.archive_response(symbol, "depth", status, &body).await;
+        StatusError::check(status, &body)
+            .with_context(|| format!("Failed to fetch order book for {}", symbol))?;
+
+        let order_book: OrderBook = serde_json::from_str(&body)
             .with_context(|| format!("Failed to parse order book response for {}", symbol))?;
 
         // Parse bids and asks
@@ -121,6 +346,110 @@ impl BinanceClient {
         })
     }
 
+    /// Fetch the open price of the current UTC daily candle for a symbol
+    pub async fn get_daily_open(&self, symbol: &str) -> Result<f64> {
+        let url = format!(
+            "{}/klines?symbol={}&interval=1d&limit=1",
+            BINANCE_API_BASE, symbol
+        );
+
+        // Each kline is [open_time, open, high, low, close, volume, ...]
+        let klines = self.get_klines(symbol, &url).await?;
+
+        let open = klines
+            .first()
+            .and_then(|k| k.get(1))
+            .and_then(|v| v.as_str())
+            .with_context(|| format!("Empty kline response for {}", symbol))?;
+
+        open.parse::<f64>()
+            .with_context(|| format!("Failed to parse open price: {}", open))
+    }
+
+    /// Fetch the completed `interval` klines (e.g. "1m") opened in
+    /// `start..end`, keyed by open time so two symbols' candles can be
+    /// matched. Ranges over Binance's per-request limit take several requests.
+    pub async fn get_closes(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<KlineClose>> {
+        let mut closes = Vec::new();
+        let mut from = start.timestamp_millis();
+        loop {
+            let url = format!(
+                "{}/klines?symbol={}&interval={}&startTime={}&endTime={}&limit={}",
+                BINANCE_API_BASE,
+                symbol,
+                interval,
+                from,
+                end.timestamp_millis(),
+                MAX_KLINES
+            );
+
+            // Each kline is [open_time, open, high, low, close, volume, close_time, ...]
+            let klines = self.get_klines(symbol, &url).await?;
+            let full_page = klines.len() >= MAX_KLINES;
+
+            let mut last_open = None;
+            for kline in klines {
+                let (Some(open_time), Some(close), Some(close_time)) = (
+                    kline.first().and_then(|v| v.as_i64()),
+                    kline.get(4).and_then(|v| v.as_str()),
+                    kline.get(6).and_then(|v| v.as_i64()),
+                ) else {
+                    anyhow::bail!("Malformed kline for {}", symbol);
+                };
+                last_open = Some(open_time);
+
+                let close_time = DateTime::from_timestamp_millis(close_time)
+                    .with_context(|| format!("Invalid kline close time: {}", close_time))?;
+                // The current candle is still open; its close isn't a real price yet
+                if close_time >= end {
+                    continue;
+                }
+
+                closes.push(KlineClose {
+                    open_time,
+                    close_time,
+                    close: close
+                        .parse()
+                        .with_context(|| format!("Failed to parse close price: {}", close))?,
+                });
+            }
+
+            // A short page is the last one
+            match last_open {
+                Some(open_time) if full_page => from = open_time + 1,
+                _ => break,
+            }
+        }
+
+        Ok(closes)
+    }
+
+    /// Fetch klines from a `/klines` URL, checking the status like the ticker
+    async fn get_klines(&self, symbol: &str, url: &str) -> Result<Vec<Vec<serde_json::Value>>> {
+        let response = self.client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch klines for {}", symbol))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .with_context(|| format!("Failed to read kline response for {}", symbol))?;
+        StatusError::check(status, &body)
+            .with_context(|| format!("Failed to fetch klines for {}", symbol))?;
+
+        serde_json::from_str(&body)
+            .with_context(|| format!("Failed to parse kline response for {}", symbol))
+    }
+
     /// Fetch prices for multiple symbols in parallel
     pub async fn get_prices(&self, symbols: &[String]) -> Result<Vec<PriceInfo>> {
         let mut tasks = Vec::new();
@@ -143,6 +472,7 @@ impl BinanceClient {
     }
 
     /// Fetch order books for multiple symbols in parallel
+    #[allow(dead_code)]
     pub async fn get_order_books(&self, symbols: &[String], limit: u32) -> Result<Vec<OrderBookInfo>> {
         let mut tasks = Vec::new();
 
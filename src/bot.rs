@@ -1,17 +1,53 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use teloxide::{
     dispatching::dialogue::InMemStorage,
     prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
+    types::{
+        InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup, InputFile, MessageId, ParseMode,
+    },
     utils::command::BotCommands,
 };
 
 use crate::{
-    binance::BinanceClient,
-    config::{Config, RatioPair},
-    ratio::RatioCalculator,
+    chart,
+    config::{ChangeRule, Config, MAX_PAIR_NAME_LENGTH, RatioPair, SharedConfig},
+    database::Database,
+    export::csv_field,
+    i18n::{Texts, fill},
+    markdown::{self, Markdown},
+    monitor::format_duration,
+    rate_limit::RateLimiter,
+    ratio::{format_leg_value, OrderSide, RatioCalculator, SimpleRatio},
 };
 
+/// Percentage points the threshold buttons move every threshold by
+const THRESHOLD_STEP: f64 = 0.5;
+/// Change windows offered as buttons, in seconds
+const WINDOW_PRESETS: [u64; 4] = [900, 3600, 14400, 86400];
+/// Hours `/history` and `/stats` cover when none are given
+const DEFAULT_HISTORY_HOURS: i64 = 24;
+/// Snapshots listed under a `/history` chart
+const HISTORY_RECENT_VALUES: usize = 10;
+/// Alerts listed by `/alerts`
+const RECENT_ALERTS: i64 = 10;
+/// Pairs per page of the pair selection keyboard
+const PAIRS_PER_PAGE: usize = 8;
+/// Category filter that lists every pair; an empty filter lists the pairs without a category
+const ALL_CATEGORIES: &str = "*";
+/// Multiples of a pair's `analysis_volume` offered as volume analysis buttons
+const VOLUME_MULTIPLIERS: [f64; 4] = [0.5, 1.0, 2.0, 5.0];
+/// Largest document the Bot API accepts
+const MAX_DOCUMENT_BYTES: usize = 50 * 1024 * 1024;
+/// Most snapshots `/export` loads; at up to ~200 bytes a row the CSV stays
+/// under `MAX_DOCUMENT_BYTES`
+const MAX_EXPORT_ROWS: i64 = 250_000;
+/// Trade sizes offered by `/slippage <symbol>`, as notional in the quote currency
+const SLIPPAGE_NOTIONALS: [(f64, &str); 4] =
+    [(1_000.0, "1k"), (10_000.0, "10k"), (100_000.0, "100k"), (1_000_000.0, "1M")];
+
 #[derive(BotCommands, Clone)]
 #[command(rename_rule = "lowercase", description = "Available commands:")]
 enum Command {
@@ -23,43 +59,112 @@ enum Command {
     Pairs,
     #[command(description = "Get ratio for a specific pair")]
     Ratio,
+    #[command(description = "Get a pair's alerts in this chat")]
+    Subscribe(String),
+    #[command(description = "Stop getting a pair's alerts in this chat")]
+    Unsubscribe(String),
+    #[command(description = "Show the pairs this chat is subscribed to")]
+    Subscriptions,
+    #[command(description = "Add a ratio pair (admins)")]
+    AddPair,
+    #[command(description = "Remove a pair added with /addpair (admins)")]
+    RemovePair(String),
+    #[command(description = "Cancel adding a pair or entering a volume")]
+    Cancel,
+    #[command(description = "Show or set a pair's alert thresholds (admins)")]
+    SetThreshold(String),
+    #[command(description = "Analyze the slippage of a trade: <symbol> [volume] [buy|sell]")]
+    Slippage(String),
+    #[command(description = "Show a pair's recent ratios with a chart: <pair> [hours]")]
+    History(String),
+    #[command(description = "Show a pair's ratio statistics: <pair> [hours]")]
+    Stats(String),
+    #[command(description = "Show the most recent alerts: [pair]")]
+    Alerts(String),
+    #[command(description = "Show the ratios of your favorite pairs")]
+    Favorites,
+    #[command(description = "Export a pair's ratio history as CSV: <pair> [hours]")]
+    Export(String),
+}
+
+/// Where a chat is in the /addpair dialogue or entering a custom volume
+#[derive(Clone, Default)]
+pub enum State {
+    #[default]
+    Idle,
+    AddPairName,
+    AddPairSymbolA {
+        name: String,
+    },
+    AddPairSymbolB {
+        name: String,
+        symbol_a: String,
+    },
+    VolumeInput {
+        pair: String,
+    },
 }
 
+type PairDialogue = Dialogue<State, InMemStorage<State>>;
 type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
 pub struct BotHandler {
-    config: Config,
+    /// Updated when the monitor reloads the config; each update reads the latest
+    config: SharedConfig,
     calculator: RatioCalculator,
+    database: Database,
 }
 
 impl BotHandler {
-    pub fn new(config: Config, calculator: RatioCalculator) -> Self {
-        Self { config, calculator }
+    pub fn new(config: SharedConfig, calculator: RatioCalculator, database: Database) -> Self {
+        Self {
+            config,
+            calculator,
+            database,
+        }
     }
 
     pub async fn run(self) -> Result<()> {
         log::info!("Starting interactive Telegram bot...");
 
-        let bot = Bot::new(&self.config.telegram.token);
+        let config = current_config(&self.config);
+        let bot = Bot::new(&config.telegram.token);
 
         let handler = Update::filter_message()
+            .enter_dialogue::<Message, InMemStorage<State>, State>()
             .branch(
                 dptree::entry()
                     .filter_command::<Command>()
                     .endpoint(Self::handle_command),
             )
+            .branch(dptree::case![State::AddPairName].endpoint(Self::receive_pair_name))
+            .branch(dptree::case![State::AddPairSymbolA { name }].endpoint(Self::receive_symbol_a))
+            .branch(
+                dptree::case![State::AddPairSymbolB { name, symbol_a }]
+                    .endpoint(Self::receive_symbol_b),
+            )
+            .branch(
+                dptree::case![State::VolumeInput { pair }].endpoint(Self::receive_volume),
+            )
             .branch(Message::filter_text().endpoint(Self::handle_text));
 
         let callback_handler = Update::filter_callback_query().endpoint(Self::handle_callback);
 
-        let all_handlers = dptree::entry().branch(handler).branch(callback_handler);
+        // Handlers get a copy of the config as it is when the update arrives
+        let all_handlers = dptree::entry()
+            .map(|shared: SharedConfig| current_config(&shared))
+            .branch(handler)
+            .branch(callback_handler);
 
         // Store config and calculator in bot data
         let mut dispatcher = Dispatcher::builder(bot, all_handlers)
             .dependencies(dptree::deps![
                 self.config.clone(),
                 self.calculator.clone(),
-                InMemStorage::<()>::new()
+                self.database.clone(),
+                InMemStorage::<State>::new(),
+                RateLimiter::new(&config.telegram),
+                LiveSessions::default()
             ])
             .enable_ctrlc_handler()
             .build();
@@ -70,56 +175,270 @@ impl BotHandler {
         Ok(())
     }
 
+    /// Run a command and record it in the audit log
+    #[allow(clippy::too_many_arguments)]
     async fn handle_command(
         bot: Bot,
         msg: Message,
         cmd: Command,
+        dialogue: PairDialogue,
         config: Config,
-        _calculator: RatioCalculator,
+        calculator: RatioCalculator,
+        database: Database,
+        limiter: RateLimiter,
     ) -> HandlerResult {
-        match cmd {
-            Command::Start => {
-                let text = "👋 Welcome to Ratio\\-Noti Bot\\!\n\n\
-                    I can help you monitor cryptocurrency price ratios from Binance\\.\n\n\
-                    *Available Commands:*\n\
-                    /pairs \\- View all configured ratio pairs\n\
-                    /ratio \\- Get current ratios\n\
-                    /help \\- Show this help message\n\n\
-                    Click the buttons below or use commands to get started\\!";
+        let user = msg.from.clone();
+        let chat_id = msg.chat.id;
+        let action = msg.text().unwrap_or_default().to_string();
+        let result =
+            Self::run_command(bot, msg, cmd, dialogue, config, calculator, database.clone(), limiter).await;
+        log_interaction(&database, user.as_ref(), chat_id, &action, &result).await;
+        result
+    }
 
-                bot.send_message(msg.chat.id, text)
+    #[allow(clippy::too_many_arguments)]
+    async fn run_command(
+        bot: Bot,
+        msg: Message,
+        cmd: Command,
+        dialogue: PairDialogue,
+        config: Config,
+        calculator: RatioCalculator,
+        database: Database,
+        limiter: RateLimiter,
+    ) -> HandlerResult {
+        let config = with_bot_settings(config, &database).await?;
+        let t = config.language.texts();
+        match &cmd {
+            Command::Start => {
+                bot.send_message(msg.chat.id, t.bot_welcome)
                     .parse_mode(ParseMode::MarkdownV2)
-                    .reply_markup(create_main_keyboard())
+                    .reply_markup(create_main_keyboard(t))
                     .await?;
             }
             Command::Help => {
-                let text = format!(
-                    "🔍 *Ratio\\-Noti Bot Help*\n\n\
-                    *Commands:*\n\
-                    /start \\- Start the bot\n\
-                    /pairs \\- Show all configured pairs\n\
-                    /ratio \\- Get current ratios for a pair\n\
-                    /help \\- Show this message\n\n\
-                    *Features:*\n\
-                    ✅ Simple price ratios\n\
-                    ✅ Volume\\-based calculations\n\
-                    ✅ Real\\-time data from Binance\n\
-                    ✅ Interactive pair selection"
-                );
-
-                bot.send_message(msg.chat.id, text)
+                bot.send_message(msg.chat.id, t.bot_help)
                     .parse_mode(ParseMode::MarkdownV2)
                     .await?;
             }
             Command::Pairs => {
-                let text = create_pairs_list(&config);
+                let paused = database.get_paused_pairs().await?;
+                let text = create_pairs_list(&config, &paused);
                 bot.send_message(msg.chat.id, text)
                     .parse_mode(ParseMode::MarkdownV2)
                     .await?;
             }
             Command::Ratio => {
-                let keyboard = create_pair_selection_keyboard(&config.ratio_pairs);
-                bot.send_message(msg.chat.id, "📊 Select a ratio pair:")
+                let (text, keyboard) = create_pair_menu(t, &config.ratio_pairs);
+                bot.send_message(msg.chat.id, text)
+                    .reply_markup(keyboard)
+                    .await?;
+            }
+            Command::Subscribe(pair_name) | Command::Unsubscribe(pair_name) => {
+                let subscribe = matches!(cmd, Command::Subscribe(_));
+                let pair_name = pair_name.trim();
+                let text = if pair_name.is_empty() {
+                    let usage = if subscribe { "/subscribe <pair>" } else { "/unsubscribe <pair>" };
+                    markdown::fill(t.bot_command_usage, &[("usage", &usage)])
+                } else if config.find_pair(pair_name).is_none() {
+                    markdown::fill(t.bot_unknown_pair, &[("pair", &pair_name)])
+                } else if subscribe {
+                    let added = database.subscribe(msg.chat.id.0, pair_name).await?;
+                    let text = if added { t.bot_subscribed } else { t.bot_already_subscribed };
+                    markdown::fill(text, &[("pair", &pair_name)])
+                } else {
+                    let removed = database.unsubscribe(msg.chat.id.0, pair_name).await?;
+                    let text = if removed { t.bot_unsubscribed } else { t.bot_not_subscribed };
+                    markdown::fill(text, &[("pair", &pair_name)])
+                };
+                bot.send_message(msg.chat.id, text)
+                    .parse_mode(ParseMode::MarkdownV2)
+                    .await?;
+            }
+            Command::Subscriptions => {
+                let pairs = database.get_subscriptions(msg.chat.id.0).await?;
+                if pairs.is_empty() {
+                    bot.send_message(msg.chat.id, t.bot_no_subscriptions).await?;
+                } else {
+                    let text = pairs.iter().fold(
+                        Markdown::new().text("🔔 ").bold(t.bot_subscriptions).newline(),
+                        |text, pair| text.newline().text(&format!("• {}", pair)),
+                    );
+                    bot.send_message(msg.chat.id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .await?;
+                }
+            }
+            Command::AddPair => {
+                if !is_admin(&config, &msg) {
+                    bot.send_message(msg.chat.id, t.bot_not_admin).await?;
+                } else {
+                    dialogue.update(State::AddPairName).await?;
+                    bot.send_message(msg.chat.id, t.bot_add_pair_name).await?;
+                }
+            }
+            Command::RemovePair(pair_name) => {
+                let pair_name = pair_name.trim();
+                if !is_admin(&config, &msg) {
+                    bot.send_message(msg.chat.id, t.bot_not_admin).await?;
+                    return Ok(());
+                }
+                let text = if pair_name.is_empty() {
+                    markdown::fill(t.bot_command_usage, &[("usage", &"/removepair <pair>")])
+                } else if database.remove_bot_pair(pair_name).await? {
+                    log::info!("Pair {} removed through the bot", pair_name);
+                    database.delete_rule_override(pair_name).await?;
+                    markdown::fill(t.bot_pair_removed, &[("pair", &pair_name)])
+                } else if config.find_pair(pair_name).is_some() {
+                    markdown::fill(t.bot_pair_not_removable, &[("pair", &pair_name)])
+                } else {
+                    markdown::fill(t.bot_unknown_pair, &[("pair", &pair_name)])
+                };
+                bot.send_message(msg.chat.id, text)
+                    .parse_mode(ParseMode::MarkdownV2)
+                    .await?;
+            }
+            Command::Cancel => {
+                dialogue.exit().await?;
+                bot.send_message(msg.chat.id, t.bot_cancelled).await?;
+            }
+            Command::Slippage(args) => match parse_slippage_args(args) {
+                Some((symbol, Some(volume), side)) => {
+                    if let Some(user) = &msg.from
+                        && let Err(wait) = limiter.try_acquire(user.id.0)
+                    {
+                        bot.send_message(msg.chat.id, rate_limited_text(t, wait)).await?;
+                        return Ok(());
+                    }
+                    send_slippage(&bot, msg.chat.id, &config, &calculator, &symbol, volume, side).await?;
+                }
+                Some((symbol, None, _)) => {
+                    send_slippage_presets(&bot, msg.chat.id, &config, &calculator, &symbol).await?;
+                }
+                None => {
+                    let usage = markdown::fill(
+                        t.bot_command_usage,
+                        &[("usage", &"/slippage <symbol> [volume] [buy|sell]")],
+                    );
+                    bot.send_message(msg.chat.id, usage)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .await?;
+                }
+            },
+            Command::History(args) | Command::Stats(args) | Command::Export(args) => {
+                let usage = match cmd {
+                    Command::History(_) => "/history <pair> [hours]",
+                    Command::Stats(_) => "/stats <pair> [hours]",
+                    _ => "/export <pair> [hours]",
+                };
+                match parse_pair_hours(&config, args, usage) {
+                    Ok((pair, hours)) if matches!(cmd, Command::History(_)) => {
+                        send_history(&bot, msg.chat.id, &config, &database, &pair.name, hours).await?;
+                    }
+                    Ok((pair, hours)) if matches!(cmd, Command::Stats(_)) => {
+                        send_stats(&bot, msg.chat.id, &config, &database, &pair.name, hours).await?;
+                    }
+                    Ok((pair, hours)) => {
+                        send_export(&bot, msg.chat.id, &config, &database, &pair.name, hours).await?;
+                    }
+                    Err(text) => {
+                        bot.send_message(msg.chat.id, text)
+                            .parse_mode(ParseMode::MarkdownV2)
+                            .await?;
+                    }
+                }
+            }
+            Command::Alerts(pair_name) => {
+                let pair_name = pair_name.trim();
+                let pair = config.find_pair(pair_name);
+                if !pair_name.is_empty() && pair.is_none() {
+                    let text = markdown::fill(t.bot_unknown_pair, &[("pair", &pair_name)]);
+                    bot.send_message(msg.chat.id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .await?;
+                    return Ok(());
+                }
+
+                let records = match pair {
+                    Some(pair) => database.get_alert_history(&pair.name, RECENT_ALERTS).await?,
+                    None => database.get_all_alerts(RECENT_ALERTS).await?,
+                };
+                if records.is_empty() {
+                    bot.send_message(msg.chat.id, t.bot_no_alerts).await?;
+                } else {
+                    let title = match pair {
+                        Some(pair) => format!("{}: {}", t.bot_recent_alerts, pair.name),
+                        None => t.bot_recent_alerts.to_string(),
+                    };
+                    let text = records.iter().fold(
+                        Markdown::new().text("🔔 ").bold(&title),
+                        |text, alert| {
+                            let time = alert
+                                .timestamp
+                                .with_timezone(&config.timezone)
+                                .format("%Y-%m-%d %H:%M %Z");
+                            let text = text.paragraph().bold(&time.to_string());
+                            let text = match pair {
+                                Some(_) => text,
+                                None => text.text(&format!(" · {}", alert.pair_name)),
+                            };
+                            text.newline()
+                                .text(&format!("{} ", t.bot_ratio))
+                                .code(&format!("{:.8}", alert.ratio))
+                                .newline()
+                                .text(&alert.format_details(t))
+                        },
+                    );
+                    bot.send_message(msg.chat.id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .await?;
+                }
+            }
+            Command::Favorites => {
+                if let Some(user) = &msg.from {
+                    send_favorites(&bot, msg.chat.id, user.id.0, &config, &calculator, &database).await?;
+                }
+            }
+            Command::SetThreshold(args) => {
+                let mut args = args.split_whitespace();
+                let pair_name = args.next().unwrap_or_default();
+                let thresholds: Vec<&str> = args.collect();
+                let Some(pair) = config.find_pair(pair_name) else {
+                    let text = if pair_name.is_empty() {
+                        markdown::fill(t.bot_command_usage, &[("usage", &"/setthreshold <pair> [pct...]")])
+                    } else {
+                        markdown::fill(t.bot_unknown_pair, &[("pair", &pair_name)])
+                    };
+                    bot.send_message(msg.chat.id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .await?;
+                    return Ok(());
+                };
+
+                let mut config = config.clone();
+                if !thresholds.is_empty() {
+                    if !is_admin(&config, &msg) {
+                        bot.send_message(msg.chat.id, t.bot_not_admin).await?;
+                        return Ok(());
+                    }
+                    let Some(thresholds) = parse_thresholds(&thresholds) else {
+                        bot.send_message(msg.chat.id, t.bot_invalid_thresholds).await?;
+                        return Ok(());
+                    };
+                    let rule = ChangeRule {
+                        window_secs: config.primary_window_secs(pair),
+                        thresholds,
+                    };
+                    let user_id = msg.from.as_ref().map_or(0, |user| user.id.0);
+                    database
+                        .set_rule_override(&pair.name, rule.window_secs, &rule.thresholds, user_id)
+                        .await?;
+                    config.rule_overrides.insert(pair.name.clone(), rule);
+                }
+
+                let (text, keyboard) = create_rule_card(&config, pair_name);
+                bot.send_message(msg.chat.id, text)
+                    .parse_mode(ParseMode::MarkdownV2)
                     .reply_markup(keyboard)
                     .await?;
             }
@@ -128,194 +447,567 @@ impl BotHandler {
         Ok(())
     }
 
-    async fn handle_text(bot: Bot, msg: Message, _config: Config) -> HandlerResult {
-        let text = "Use /start to see available commands or click the buttons below:";
+    async fn receive_pair_name(
+        bot: Bot,
+        msg: Message,
+        dialogue: PairDialogue,
+        config: Config,
+        database: Database,
+    ) -> HandlerResult {
+        let config = with_bot_settings(config, &database).await?;
+        let t = config.language.texts();
+        if !is_admin(&config, &msg) {
+            return Ok(());
+        }
 
+        let name = msg.text().unwrap_or_default().trim();
+        if !is_valid_pair_name(name) {
+            bot.send_message(msg.chat.id, t.bot_add_pair_invalid_name).await?;
+            return Ok(());
+        }
+
+        let text = if config.find_pair(name).is_some() {
+            markdown::fill(t.bot_add_pair_exists, &[("pair", &name)])
+        } else {
+            dialogue
+                .update(State::AddPairSymbolA { name: name.to_string() })
+                .await?;
+            markdown::fill(t.bot_add_pair_symbol_a, &[("pair", &name)])
+        };
         bot.send_message(msg.chat.id, text)
-            .reply_markup(create_main_keyboard())
+            .parse_mode(ParseMode::MarkdownV2)
             .await?;
 
         Ok(())
     }
 
+    async fn receive_symbol_a(
+        bot: Bot,
+        msg: Message,
+        dialogue: PairDialogue,
+        name: String,
+        config: Config,
+        calculator: RatioCalculator,
+    ) -> HandlerResult {
+        let t = config.language.texts();
+        if !is_admin(&config, &msg) {
+            return Ok(());
+        }
+
+        if let Some(symbol_a) = check_symbol(&bot, &msg, t, &calculator).await? {
+            let text = markdown::fill(t.bot_add_pair_symbol_b, &[("pair", &name)]);
+            dialogue.update(State::AddPairSymbolB { name, symbol_a }).await?;
+            bot.send_message(msg.chat.id, text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn receive_symbol_b(
+        bot: Bot,
+        msg: Message,
+        dialogue: PairDialogue,
+        (name, symbol_a): (String, String),
+        config: Config,
+        calculator: RatioCalculator,
+        database: Database,
+    ) -> HandlerResult {
+        let config = with_bot_settings(config, &database).await?;
+        let t = config.language.texts();
+        if !is_admin(&config, &msg) {
+            return Ok(());
+        }
+
+        let Some(symbol_b) = check_symbol(&bot, &msg, t, &calculator).await? else {
+            return Ok(());
+        };
+        if symbol_b == symbol_a {
+            bot.send_message(msg.chat.id, t.bot_add_pair_same_symbol).await?;
+            return Ok(());
+        }
+
+        dialogue.exit().await?;
+        // The name may have been taken while the symbols were entered
+        let user_id = msg.from.as_ref().map_or(0, |user| user.id.0);
+        let text = if config.find_pair(&name).is_none()
+            && database.add_bot_pair(&name, &symbol_a, &symbol_b, user_id).await?
+        {
+            log::info!("Pair {} ({}/{}) added through the bot", name, symbol_a, symbol_b);
+            markdown::fill(
+                t.bot_pair_added,
+                &[("pair", &name), ("symbol_a", &symbol_a), ("symbol_b", &symbol_b)],
+            )
+        } else {
+            markdown::fill(t.bot_add_pair_exists, &[("pair", &name)])
+        };
+        bot.send_message(msg.chat.id, text)
+            .parse_mode(ParseMode::MarkdownV2)
+            .await?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn receive_volume(
+        bot: Bot,
+        msg: Message,
+        dialogue: PairDialogue,
+        pair: String,
+        config: Config,
+        calculator: RatioCalculator,
+        database: Database,
+        limiter: RateLimiter,
+    ) -> HandlerResult {
+        let config = with_bot_settings(config, &database).await?;
+        let t = config.language.texts();
+
+        let volume = msg
+            .text()
+            .and_then(|text| text.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite() && *v > 0.0);
+        let Some(volume) = volume else {
+            bot.send_message(msg.chat.id, t.bot_invalid_volume).await?;
+            return Ok(());
+        };
+        // Stay in the dialogue, so the volume can be sent again once allowed
+        if let Some(user) = &msg.from
+            && let Err(wait) = limiter.try_acquire(user.id.0)
+        {
+            bot.send_message(msg.chat.id, rate_limited_text(t, wait)).await?;
+            return Ok(());
+        }
+
+        dialogue.exit().await?;
+        // The pair may have been removed while the volume was entered
+        if let Some(pair) = config.find_pair(&pair) {
+            send_volume_analysis(&bot, msg.chat.id, &config, &calculator, pair, volume).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_text(bot: Bot, msg: Message, config: Config) -> HandlerResult {
+        let t = config.language.texts();
+        bot.send_message(msg.chat.id, t.bot_use_start)
+            .reply_markup(create_main_keyboard(t))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Handle a button press and record it in the audit log
+    #[allow(clippy::too_many_arguments)]
     async fn handle_callback(
         bot: Bot,
         q: CallbackQuery,
         config: Config,
         calculator: RatioCalculator,
+        database: Database,
+        storage: Arc<InMemStorage<State>>,
+        limiter: RateLimiter,
+        live: LiveSessions,
     ) -> HandlerResult {
+        let user = q.from.clone();
+        let chat_id = q.message.as_ref().map_or(ChatId(user.id.0 as i64), |msg| msg.chat().id);
+        let action = q.data.clone().unwrap_or_default();
+        let result =
+            Self::run_callback(bot, q, config, calculator, database.clone(), storage, limiter, live).await;
+        log_interaction(&database, Some(&user), chat_id, &action, &result).await;
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_callback(
+        bot: Bot,
+        q: CallbackQuery,
+        config: Config,
+        calculator: RatioCalculator,
+        database: Database,
+        storage: Arc<InMemStorage<State>>,
+        limiter: RateLimiter,
+        live: LiveSessions,
+    ) -> HandlerResult {
+        let config = with_bot_settings(config, &database).await?;
+        let t = config.language.texts();
         if let Some(data) = &q.data {
-            if data.starts_with("ratio:") {
-                let pair_name = data.strip_prefix("ratio:").unwrap();
-                let pair = config
-                    .ratio_pairs
-                    .iter()
-                    .find(|p| p.name == pair_name)
-                    .cloned();
-
-                if let Some(pair) = pair {
-                    // Answer the callback query first
+            if let Some(pair_name) = data.strip_prefix("ratio:") {
+                bot.answer_callback_query(&q.id).await?;
+
+                if let Some(pair) = config.find_pair(pair_name)
+                    && let Some(msg) = q.message
+                {
+                    let chat_id = msg.chat().id;
+                    bot.send_message(chat_id, t.bot_calculating)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .await?;
+
+                    match calculator
+                        .calculate_pair_ratio(&config, pair, &mut HashMap::new())
+                        .await
+                    {
+                        Ok(ratio) => {
+                            let text = create_ratio_card(&config, &database, pair, &ratio, None).await?;
+                            // Check if there's volume configured for detailed analysis
+                            // (composite pairs have no order book to analyze)
+                            let volume = pair.analysis_volume.filter(|_| !pair.is_composite());
+                            let paused = database.get_paused_pairs().await?.contains(&pair.name);
+                            let favorite = database.get_favorites(q.from.id.0).await?.contains(&pair.name);
+                            let keyboard = create_pair_keyboard(
+                                t,
+                                &pair.name,
+                                volume,
+                                paused,
+                                favorite,
+                                config.telegram.bot_live_duration_secs,
+                            );
+                            bot.send_message(chat_id, text)
+                                .parse_mode(ParseMode::MarkdownV2)
+                                .reply_markup(keyboard)
+                                .await?;
+                        }
+                        Err(e) => {
+                            let error_text = markdown::fill(t.bot_ratio_error, &[("error", &e)]);
+                            bot.send_message(chat_id, error_text)
+                                .parse_mode(ParseMode::MarkdownV2)
+                                .await?;
+                        }
+                    }
+                }
+            } else if let Some(pair_name) = data.strip_prefix("live:") {
+                let Some(pair) = config.find_pair(pair_name).cloned() else {
                     bot.answer_callback_query(&q.id).await?;
+                    return Ok(());
+                };
+                let Some(card) = q.message.as_ref().and_then(|msg| msg.regular_message()) else {
+                    bot.answer_callback_query(&q.id).await?;
+                    return Ok(());
+                };
+                if !live.start(card.chat.id, card.id) {
+                    bot.answer_callback_query(&q.id).text(t.bot_already_live).await?;
+                    return Ok(());
+                }
+                bot.answer_callback_query(&q.id).await?;
+
+                tokio::spawn(refresh_ratio_card(
+                    bot.clone(),
+                    card.chat.id,
+                    card.id,
+                    card.reply_markup().cloned(),
+                    config.clone(),
+                    calculator.clone(),
+                    database.clone(),
+                    pair,
+                    live.clone(),
+                ));
+            } else if let Some(rest) = data.strip_prefix("volume:") {
+                if !rest.ends_with(":custom")
+                    && let Err(wait) = limiter.try_acquire(q.from.id.0)
+                {
+                    bot.answer_callback_query(&q.id).text(rate_limited_text(t, wait)).await?;
+                    return Ok(());
+                }
+                bot.answer_callback_query(&q.id).await?;
 
-                    // Send "calculating" message
-                    if let Some(msg) = q.message {
-                        let chat = msg.chat();
-                        let chat_id = chat.id;
-                        bot.send_message(chat_id, "⏳ Calculating ratio\\.\\.\\.")
+                if let Some((pair_name, volume)) = rest.rsplit_once(':')
+                    && let Some(pair) = config.find_pair(pair_name)
+                    && let Some(msg) = q.message
+                {
+                    let chat_id = msg.chat().id;
+                    if volume == "custom" {
+                        PairDialogue::new(storage, chat_id)
+                            .update(State::VolumeInput { pair: pair.name.clone() })
+                            .await?;
+                        let text = markdown::fill(t.bot_volume_prompt, &[("pair", &pair.name)]);
+                        bot.send_message(chat_id, text)
                             .parse_mode(ParseMode::MarkdownV2)
                             .await?;
+                    } else if let Ok(volume) = volume.parse::<f64>() {
+                        send_volume_analysis(&bot, chat_id, &config, &calculator, pair, volume).await?;
+                    }
+                }
+            } else if let Some(pair_name) = data.strip_prefix("pause:") {
+                if !config.telegram.is_admin(q.from.id.0) {
+                    bot.answer_callback_query(&q.id).text(t.bot_not_admin).await?;
+                    return Ok(());
+                }
+                bot.answer_callback_query(&q.id).await?;
 
-                        // Calculate ratio
-                        match calculator
-                            .calculate_simple_ratio(&pair.name, &pair.symbol_a, &pair.symbol_b)
-                            .await
-                        {
-                            Ok(ratio) => {
-                                let text = format!(
-                                    "📈 *{}*\n\n\
-                                    *Ratio:* `{:.8}`\n\n\
-                                    {} \\- `${:.2}`\n\
-                                    {} \\- `${:.2}`\n\n\
-                                    _Time: {}_",
-                                    escape_markdown(&pair.name),
-                                    ratio.ratio,
-                                    escape_markdown(&pair.symbol_a),
-                                    ratio.price_a,
-                                    escape_markdown(&pair.symbol_b),
-                                    ratio.price_b,
-                                    escape_markdown(
-                                        &ratio
-                                            .timestamp
-                                            .format("%Y-%m-%d %H:%M:%S UTC")
-                                            .to_string()
-                                    )
-                                );
-
-                                // Check if there's volume configured for detailed analysis
-                                if let Some(volume) = pair.analysis_volume {
-                                    bot.send_message(chat_id, text.clone())
-                                        .parse_mode(ParseMode::MarkdownV2)
-                                        .reply_markup(create_volume_analysis_keyboard(
-                                            &pair.name, volume,
-                                        ))
-                                        .await?;
-                                } else {
-                                    bot.send_message(chat_id, text)
-                                        .parse_mode(ParseMode::MarkdownV2)
-                                        .reply_markup(create_back_keyboard())
-                                        .await?;
-                                }
-                            }
-                            Err(e) => {
-                                let error_text = format!(
-                                    "❌ Error calculating ratio: {}",
-                                    escape_markdown(&e.to_string())
-                                );
-                                bot.send_message(chat_id, error_text)
-                                    .parse_mode(ParseMode::MarkdownV2)
-                                    .await?;
-                            }
-                        }
+                if config.find_pair(pair_name).is_some()
+                    && let Some(msg) = q.message
+                {
+                    database.pause_pair(pair_name).await?;
+                    let text = markdown::fill(t.bot_paused, &[("pair", &pair_name)]);
+                    bot.send_message(msg.chat().id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .reply_markup(create_back_keyboard(t))
+                        .await?;
+                }
+            } else if let Some(pair_name) = data.strip_prefix("resume:") {
+                if !config.telegram.is_admin(q.from.id.0) {
+                    bot.answer_callback_query(&q.id).text(t.bot_not_admin).await?;
+                    return Ok(());
+                }
+                bot.answer_callback_query(&q.id).await?;
+
+                if config.find_pair(pair_name).is_some()
+                    && let Some(msg) = q.message
+                {
+                    database.resume_pair(pair_name).await?;
+                    let text = markdown::fill(t.bot_resumed, &[("pair", &pair_name)]);
+                    bot.send_message(msg.chat().id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .reply_markup(create_back_keyboard(t))
+                        .await?;
+                }
+            } else if let Some(pair_name) = data.strip_prefix("fav:") {
+                let Some(pair) = config.find_pair(pair_name) else {
+                    bot.answer_callback_query(&q.id).await?;
+                    return Ok(());
+                };
+
+                let user_id = q.from.id.0;
+                let favorite = !database.remove_favorite(user_id, pair_name).await?;
+                let text = if favorite {
+                    database.add_favorite(user_id, pair_name).await?;
+                    fill(t.bot_favorite_added, &[("pair", &pair_name)])
+                } else {
+                    fill(t.bot_favorite_removed, &[("pair", &pair_name)])
+                };
+                bot.answer_callback_query(&q.id).text(text).await?;
+
+                // Flip the star on the ratio card the button belongs to
+                if let Some(msg) = q.message {
+                    let volume = pair.analysis_volume.filter(|_| !pair.is_composite());
+                    let paused = database.get_paused_pairs().await?.contains(pair_name);
+                    let keyboard = create_pair_keyboard(
+                        t,
+                        pair_name,
+                        volume,
+                        paused,
+                        favorite,
+                        config.telegram.bot_live_duration_secs,
+                    );
+                    if let Err(e) = bot
+                        .edit_message_reply_markup(msg.chat().id, msg.id())
+                        .reply_markup(keyboard)
+                        .await
+                    {
+                        log::warn!("Failed to update the favorite button of {}: {}", pair_name, e);
                     }
                 }
-            } else if data.starts_with("volume:") {
-                let parts: Vec<&str> = data.strip_prefix("volume:").unwrap().split(':').collect();
-                if parts.len() == 2 {
-                    let pair_name = parts[0];
-                    let volume: f64 = parts[1].parse().unwrap_or(1.0);
-
-                    let pair = config
-                        .ratio_pairs
-                        .iter()
-                        .find(|p| p.name == pair_name)
-                        .cloned();
-
-                    if let Some(pair) = pair {
-                        bot.answer_callback_query(&q.id).await?;
-
-                        if let Some(msg) = q.message {
-                            let chat = msg.chat();
-                            let chat_id = chat.id;
-                            bot.send_message(chat_id, "⏳ Analyzing order book\\.\\.\\.")
-                                .parse_mode(ParseMode::MarkdownV2)
-                                .await?;
+            } else if data == "favorites" {
+                bot.answer_callback_query(&q.id).await?;
 
-                            match calculator
-                                .calculate_volume_based_ratio(
-                                    &pair.name,
-                                    &pair.symbol_a,
-                                    &pair.symbol_b,
-                                    volume,
-                                )
-                                .await
-                            {
-                                Ok(ratio) => {
-                                    let text = format!(
-                                        "📊 *Volume\\-Based Analysis*\n\n\
-                                        *Pair:* {}\n\
-                                        *Volume:* `{}`\n\
-                                        *Ratio:* `{:.8}`\n\n\
-                                        *{}*\n\
-                                        Effective Price: `${:.2}`\n\
-                                        Slippage: `{:.3}%`\n\n\
-                                        *{}*\n\
-                                        Effective Price: `${:.2}`\n\
-                                        Slippage: `{:.3}%`\n\n\
-                                        _Time: {}_",
-                                        escape_markdown(&pair.name),
-                                        volume,
-                                        ratio.ratio,
-                                        escape_markdown(&pair.symbol_a),
-                                        ratio.effective_price_a,
-                                        ratio.slippage_a,
-                                        escape_markdown(&pair.symbol_b),
-                                        ratio.effective_price_b,
-                                        ratio.slippage_b,
-                                        escape_markdown(
-                                            &ratio
-                                                .timestamp
-                                                .format("%Y-%m-%d %H:%M:%S UTC")
-                                                .to_string()
-                                        )
-                                    );
-
-                                    bot.send_message(chat_id, text)
-                                        .parse_mode(ParseMode::MarkdownV2)
-                                        .reply_markup(create_back_keyboard())
-                                        .await?;
-                                }
-                                Err(e) => {
-                                    let error_text = format!(
-                                        "❌ Error analyzing volume: {}",
-                                        escape_markdown(&e.to_string())
-                                    );
-                                    bot.send_message(chat_id, error_text)
-                                        .parse_mode(ParseMode::MarkdownV2)
-                                        .await?;
-                                }
-                            }
+                if let Some(msg) = q.message {
+                    send_favorites(&bot, msg.chat().id, q.from.id.0, &config, &calculator, &database).await?;
+                }
+            } else if let Some(rest) = data.strip_prefix("snooze:") {
+                if !config.telegram.is_admin(q.from.id.0) {
+                    bot.answer_callback_query(&q.id).text(t.bot_not_admin).await?;
+                    return Ok(());
+                }
+                bot.answer_callback_query(&q.id).await?;
+
+                if let Some((hours, pair_name)) = rest.split_once(':')
+                    && let Ok(hours) = hours.parse::<i64>()
+                    && config.find_pair(pair_name).is_some()
+                    && let Some(msg) = q.message
+                {
+                    let until = Utc::now() + chrono::Duration::hours(hours);
+                    database.snooze_pair(pair_name, until).await?;
+                    let until = until
+                        .with_timezone(&config.timezone)
+                        .format("%Y-%m-%d %H:%M %Z")
+                        .to_string();
+                    let text = markdown::fill(t.bot_snoozed, &[("pair", &pair_name), ("until", &until)]);
+                    bot.send_message(msg.chat().id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .await?;
+                }
+            } else if let Some(pair_name) = data.strip_prefix("ack:") {
+                bot.answer_callback_query(&q.id).await?;
+
+                if config.find_pair(pair_name).is_some()
+                    && let Some(msg) = q.message
+                {
+                    let now = Utc::now();
+                    let user = q.from.mention().unwrap_or_else(|| q.from.full_name());
+                    database
+                        .insert_acknowledgement(
+                            pair_name,
+                            q.from.id.0,
+                            &user,
+                            msg.chat().id.0,
+                            msg.id().0,
+                            now,
+                        )
+                        .await?;
+
+                    // Mark the alert itself, keeping its formatting and the other buttons
+                    if let Some(alert) = msg.regular_message()
+                        && let Some(text) = alert.text()
+                    {
+                        let time = now
+                            .with_timezone(&config.timezone)
+                            .format("%Y-%m-%d %H:%M %Z")
+                            .to_string();
+                        let note = fill(t.bot_acknowledged, &[("user", &user), ("time", &time)]);
+                        let mut request =
+                            bot.edit_message_text(alert.chat.id, alert.id, format!("{}\n\n{}", text, note));
+                        request.entities = alert.entities().map(<[_]>::to_vec);
+                        request.reply_markup = alert.reply_markup().and_then(without_ack_button);
+                        if let Err(e) = request.await {
+                            log::warn!("Failed to mark alert for {} as acknowledged: {}", pair_name, e);
                         }
                     }
                 }
+            } else if let Some(rest) = data.strip_prefix("slip:") {
+                if let Err(wait) = limiter.try_acquire(q.from.id.0) {
+                    bot.answer_callback_query(&q.id).text(rate_limited_text(t, wait)).await?;
+                    return Ok(());
+                }
+                bot.answer_callback_query(&q.id).await?;
+
+                let mut parts = rest.splitn(3, ':');
+                if let (Some(side), Some(volume), Some(symbol)) = (parts.next(), parts.next(), parts.next())
+                    && let Ok(volume) = volume.parse::<f64>()
+                    && let Some(msg) = q.message
+                {
+                    let side = if side == "sell" { OrderSide::Sell } else { OrderSide::Buy };
+                    send_slippage(&bot, msg.chat().id, &config, &calculator, symbol, volume, side).await?;
+                }
+            } else if let Some(pair_name) = data.strip_prefix("stats:") {
+                bot.answer_callback_query(&q.id).await?;
+
+                if config.find_pair(pair_name).is_some()
+                    && let Some(msg) = q.message
+                {
+                    send_stats(&bot, msg.chat().id, &config, &database, pair_name, DEFAULT_HISTORY_HOURS).await?;
+                }
+            } else if let Some(pair_name) = data.strip_prefix("rules:") {
+                bot.answer_callback_query(&q.id).await?;
+
+                if config.find_pair(pair_name).is_some()
+                    && let Some(msg) = q.message
+                {
+                    let (text, keyboard) = create_rule_card(&config, pair_name);
+                    bot.send_message(msg.chat().id, text)
+                        .parse_mode(ParseMode::MarkdownV2)
+                        .reply_markup(keyboard)
+                        .await?;
+                }
+            } else if data.starts_with("thr:") || data.starts_with("win:") || data.starts_with("thr_reset:") {
+                if !config.telegram.is_admin(q.from.id.0) {
+                    bot.answer_callback_query(&q.id).text(t.bot_not_admin).await?;
+                    return Ok(());
+                }
+                bot.answer_callback_query(&q.id).await?;
+
+                let Some(msg) = q.message else {
+                    return Ok(());
+                };
+                let mut config = config;
+                let (action, rest) = data.split_once(':').unwrap_or_default();
+                let (value, pair_name) = match action {
+                    "thr_reset" => ("", rest),
+                    _ => rest.split_once(':').unwrap_or_default(),
+                };
+                let Some(pair) = config.find_pair(pair_name) else {
+                    return Ok(());
+                };
+
+                let current = config.change_rules(pair).swap_remove(0);
+                let rule = match action {
+                    "thr" => value.parse::<f64>().ok().and_then(|step| {
+                        let thresholds = current
+                            .thresholds
+                            .iter()
+                            .map(|t| ((t + step) * 100.0).round() / 100.0)
+                            .collect::<Vec<_>>();
+                        thresholds.iter().all(|t| *t > 0.0).then_some(ChangeRule {
+                            thresholds,
+                            ..current.clone()
+                        })
+                    }),
+                    "win" => value.parse::<u64>().ok().map(|window_secs| ChangeRule {
+                        window_secs,
+                        ..current.clone()
+                    }),
+                    _ => None,
+                };
+
+                match rule {
+                    Some(rule) if rule != current => {
+                        database
+                            .set_rule_override(pair_name, rule.window_secs, &rule.thresholds, q.from.id.0)
+                            .await?;
+                        config.rule_overrides.insert(pair_name.to_string(), rule);
+                    }
+                    None if action == "thr_reset" && database.delete_rule_override(pair_name).await? => {
+                        config.rule_overrides.remove(pair_name);
+                    }
+                    _ => return Ok(()),
+                }
+
+                let (text, keyboard) = create_rule_card(&config, pair_name);
+                if let Err(e) = bot
+                    .edit_message_text(msg.chat().id, msg.id(), text)
+                    .parse_mode(ParseMode::MarkdownV2)
+                    .reply_markup(keyboard)
+                    .await
+                {
+                    log::warn!("Failed to update the thresholds of {}: {}", pair_name, e);
+                }
             } else if data == "back_to_pairs" {
                 bot.answer_callback_query(&q.id).await?;
 
                 if let Some(msg) = q.message {
                     let chat = msg.chat();
                     let chat_id = chat.id;
-                    let keyboard = create_pair_selection_keyboard(&config.ratio_pairs);
-                    bot.send_message(chat_id, "📊 Select a ratio pair:")
+                    let (text, keyboard) = create_pair_menu(t, &config.ratio_pairs);
+                    bot.send_message(chat_id, text)
                         .reply_markup(keyboard)
                         .await?;
                 }
+            } else if data == "categories" || data.starts_with("pairs:") {
+                bot.answer_callback_query(&q.id).await?;
+
+                // Browse in place rather than sending a new message per page
+                if let Some(msg) = q.message {
+                    let (text, keyboard) = match data.strip_prefix("pairs:").and_then(|rest| rest.split_once(':')) {
+                        Some((page, category)) => (
+                            t.bot_select_pair,
+                            create_pair_selection_keyboard(
+                                t,
+                                &config.ratio_pairs,
+                                category,
+                                page.parse().unwrap_or(0),
+                            ),
+                        ),
+                        None => create_pair_menu(t, &config.ratio_pairs),
+                    };
+                    if let Err(e) = bot
+                        .edit_message_text(msg.chat().id, msg.id(), text)
+                        .reply_markup(keyboard)
+                        .await
+                    {
+                        log::warn!("Failed to show pair page: {}", e);
+                    }
+                }
+            } else if data == "page" {
+                // The page indicator between the arrows
+                bot.answer_callback_query(&q.id).await?;
             } else if data == "main_menu" {
                 bot.answer_callback_query(&q.id).await?;
 
                 if let Some(msg) = q.message {
                     let chat = msg.chat();
                     let chat_id = chat.id;
-                    bot.send_message(chat_id, "Main menu:")
-                        .reply_markup(create_main_keyboard())
+                    bot.send_message(chat_id, t.bot_main_menu)
+                        .reply_markup(create_main_keyboard(t))
                         .await?;
                 }
             }
@@ -325,24 +1017,896 @@ impl BotHandler {
     }
 }
 
-fn create_main_keyboard() -> InlineKeyboardMarkup {
+/// Ratio cards currently being refreshed by a Live button
+#[derive(Clone, Default)]
+struct LiveSessions(Arc<Mutex<HashSet<(ChatId, MessageId)>>>);
+
+impl LiveSessions {
+    /// Claim a card for refreshing. Returns false if it already is.
+    fn start(&self, chat_id: ChatId, message_id: MessageId) -> bool {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).insert((chat_id, message_id))
+    }
+
+    fn finish(&self, chat_id: ChatId, message_id: MessageId) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).remove(&(chat_id, message_id));
+    }
+}
+
+/// A pair's ratio card: ratio, legs, time and the most recent alert. While
+/// refreshed live, it says until when.
+async fn create_ratio_card(
+    config: &Config,
+    database: &Database,
+    pair: &RatioPair,
+    ratio: &SimpleRatio,
+    live_until: Option<DateTime<Utc>>,
+) -> Result<String> {
+    let t = config.language.texts();
+    let time = ratio
+        .timestamp
+        .with_timezone(&config.timezone)
+        .format("%Y-%m-%d %H:%M:%S %Z");
+    let mut text = Markdown::new()
+        .text("📈 ")
+        .bold(&pair.name)
+        .paragraph()
+        .bold(t.bot_ratio)
+        .text(" ")
+        .code(&format!("{:.8}", ratio.ratio))
+        .paragraph()
+        .text(&format!("{} - ", ratio.symbol_a))
+        .code(&format_leg_value(pair.leg_a(), ratio.price_a))
+        .newline()
+        .text(&format!("{} - ", ratio.symbol_b))
+        .code(&format_leg_value(pair.leg_b(), ratio.price_b))
+        .paragraph()
+        .italic(&format!("{}: {}", t.time, time));
+
+    // Most recent alert, with the details recorded when it fired
+    if let Some(alert) = database.get_alert_history(&pair.name, 1).await?.first() {
+        let time = alert
+            .timestamp
+            .with_timezone(&config.timezone)
+            .format("%Y-%m-%d %H:%M:%S %Z");
+        text = text
+            .paragraph()
+            .bold(t.bot_last_alert)
+            .text(&format!(" {}", time))
+            .newline()
+            .text(&alert.format_details(t));
+    }
+
+    if let Some(until) = live_until {
+        let until = until.with_timezone(&config.timezone).format("%H:%M:%S").to_string();
+        text = text.paragraph().text(&fill(t.bot_live_until, &[("time", &until)]));
+    }
+
+    Ok(text.into())
+}
+
+/// Edit a ratio card with fresh values every `bot_live_refresh_secs` until
+/// `bot_live_duration_secs` have passed, then once more without the live note.
+/// Stops early if the card can't be edited anymore, e.g. when it was deleted.
+#[allow(clippy::too_many_arguments)]
+async fn refresh_ratio_card(
+    bot: Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    keyboard: Option<InlineKeyboardMarkup>,
+    config: Config,
+    calculator: RatioCalculator,
+    database: Database,
+    pair: RatioPair,
+    live: LiveSessions,
+) {
+    let interval = std::time::Duration::from_secs(config.telegram.bot_live_refresh_secs);
+    let until = Utc::now() + chrono::Duration::seconds(config.telegram.bot_live_duration_secs as i64);
+    log::debug!("Refreshing the ratio card of {} until {}", pair.name, until);
+
+    loop {
+        let live_until = (Utc::now() < until).then_some(until);
+        let text = match calculator
+            .calculate_pair_ratio(&config, &pair, &mut HashMap::new())
+            .await
+        {
+            Ok(ratio) => create_ratio_card(&config, &database, &pair, &ratio, live_until).await,
+            Err(e) => Err(e),
+        };
+
+        match text {
+            Ok(text) => {
+                let mut request = bot
+                    .edit_message_text(chat_id, message_id, text)
+                    .parse_mode(ParseMode::MarkdownV2);
+                request.reply_markup = keyboard.clone();
+                if let Err(e) = request.await {
+                    log::warn!("Stopped refreshing the ratio card of {}: {}", pair.name, e);
+                    break;
+                }
+            }
+            // Keep the last values and try again next time
+            Err(e) => log::warn!("Failed to refresh the ratio card of {}: {}", pair.name, e),
+        }
+
+        if live_until.is_none() {
+            break;
+        }
+        tokio::time::sleep(interval).await;
+    }
+
+    live.finish(chat_id, message_id);
+}
+
+/// Reply to a user who ran out of order book lookups, rounding the wait up
+fn rate_limited_text(t: &Texts, wait: std::time::Duration) -> String {
+    let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    fill(t.bot_rate_limited, &[("secs", &secs)])
+}
+
+/// Store who did what in the bot and whether it worked. Failing to do so
+/// is logged rather than failing the interaction.
+async fn log_interaction(
+    database: &Database,
+    user: Option<&teloxide::types::User>,
+    chat_id: ChatId,
+    action: &str,
+    result: &HandlerResult,
+) {
+    let (user_id, user_name) = match user {
+        Some(user) => (user.id.0, user.mention().unwrap_or_else(|| user.full_name())),
+        None => (0, String::new()),
+    };
+    let result = match result {
+        Ok(()) => "ok".to_string(),
+        Err(e) => format!("error: {}", e),
+    };
+    if let Err(e) = database
+        .insert_bot_log(user_id, &user_name, chat_id.0, action, &result)
+        .await
+    {
+        log::warn!("Failed to record bot action {:?}: {}", action, e);
+    }
+}
+
+/// The config as last loaded by the monitor
+fn current_config(shared: &SharedConfig) -> Config {
+    shared.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The config with the pairs and change rules set through the bot
+async fn with_bot_settings(mut config: Config, database: &Database) -> Result<Config> {
+    let pairs = database.get_bot_pairs().await?;
+    config.add_pairs(
+        pairs
+            .iter()
+            .map(|p| RatioPair::new(&p.name, &p.symbol_a, &p.symbol_b)),
+    );
+    config.rule_overrides = database
+        .get_rule_overrides()
+        .await?
+        .into_iter()
+        .map(|r| {
+            let rule = ChangeRule {
+                window_secs: r.window_secs,
+                thresholds: r.thresholds,
+            };
+            (r.pair_name, rule)
+        })
+        .collect();
+    Ok(config)
+}
+
+/// `<pair> [hours]` command arguments, or the MarkdownV2 reply explaining what's wrong
+fn parse_pair_hours<'a>(
+    config: &'a Config,
+    args: &str,
+    usage: &str,
+) -> std::result::Result<(&'a RatioPair, i64), String> {
+    let t = config.language.texts();
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let hours = match args.get(1) {
+        Some(hours) => hours.parse::<i64>().ok().filter(|h| (1..=8760).contains(h)),
+        None => Some(DEFAULT_HISTORY_HOURS),
+    };
+
+    match (args.first(), hours) {
+        (Some(name), Some(hours)) if args.len() <= 2 => config
+            .find_pair(name)
+            .map(|pair| (pair, hours))
+            .ok_or_else(|| markdown::fill(t.bot_unknown_pair, &[("pair", name)])),
+        _ => Err(markdown::fill(t.bot_command_usage, &[("usage", &usage)])),
+    }
+}
+
+/// Sample count, min, max, average, spread and change of a pair's ratio over the last `hours`
+async fn send_stats(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    database: &Database,
+    pair_name: &str,
+    hours: i64,
+) -> Result<()> {
+    let t = config.language.texts();
+    let stats = database.get_pair_statistics(pair_name, hours).await?;
+
+    let text = if stats.count == 0 {
+        markdown::fill(t.bot_no_history, &[("pair", &pair_name), ("hours", &hours)])
+    } else {
+        let range = (stats.max_ratio - stats.min_ratio) / stats.min_ratio * 100.0;
+        Markdown::new()
+            .text("📈 ")
+            .bold(&fill(t.bot_statistics, &[("pair", &pair_name), ("hours", &hours)]))
+            .paragraph()
+            .bold(t.bot_samples)
+            .text(" ")
+            .code(&stats.count.to_string())
+            .newline()
+            .bold(t.bot_min)
+            .text(" ")
+            .code(&format!("{:.8}", stats.min_ratio))
+            .newline()
+            .bold(t.bot_max)
+            .text(" ")
+            .code(&format!("{:.8}", stats.max_ratio))
+            .newline()
+            .bold(t.bot_avg)
+            .text(" ")
+            .code(&format!("{:.8}", stats.avg_ratio))
+            .newline()
+            .bold(t.bot_stddev)
+            .text(" ")
+            .code(&format!("{:.8}", stats.stddev))
+            .newline()
+            .bold(t.bot_range)
+            .text(" ")
+            .code(&format!("{:.2}%", range))
+            .newline()
+            .bold(t.bot_net_change)
+            .text(" ")
+            .code(&format!("{:+.2}%", stats.change_pct()))
+            .into()
+    };
+    bot.send_message(chat_id, text)
+        .parse_mode(ParseMode::MarkdownV2)
+        .await?;
+
+    Ok(())
+}
+
+/// A pair's ratios over the last `hours`: a chart captioned with the range and
+/// the latest values, or just the text when there are too few snapshots to chart
+async fn send_history(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    database: &Database,
+    pair_name: &str,
+    hours: i64,
+) -> Result<()> {
+    let t = config.language.texts();
+    let now = Utc::now();
+    let mut records = database
+        .get_ratio_history_range(pair_name, now - chrono::Duration::hours(hours), now)
+        .await?;
+    // Oldest first, as the chart expects
+    records.reverse();
+
+    let (Some(first), Some(last)) = (records.first(), records.last()) else {
+        let text = markdown::fill(t.bot_no_history, &[("pair", &pair_name), ("hours", &hours)]);
+        bot.send_message(chat_id, text)
+            .parse_mode(ParseMode::MarkdownV2)
+            .await?;
+        return Ok(());
+    };
+
+    let low = records.iter().map(|r| r.ratio).fold(f64::INFINITY, f64::min);
+    let high = records.iter().map(|r| r.ratio).fold(f64::NEG_INFINITY, f64::max);
+    let change = format!("{:+.2}", (last.ratio - first.ratio) / first.ratio * 100.0);
+    let recent = records
+        .iter()
+        .rev()
+        .take(HISTORY_RECENT_VALUES)
+        .map(|r| {
+            let time = r.timestamp.with_timezone(&config.timezone).format("%m-%d %H:%M");
+            format!("{}  {:.8}", time, r.ratio)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text: String = Markdown::new()
+        .text("📜 ")
+        .bold(&fill(t.bot_history, &[("pair", &pair_name), ("hours", &hours)]))
+        .paragraph()
+        .text(&fill(
+            t.bot_history_range,
+            &[
+                ("change", &change),
+                ("low", &format!("{:.8}", low)),
+                ("high", &format!("{:.8}", high)),
+            ],
+        ))
+        .paragraph()
+        .bold(t.bot_history_recent)
+        .newline()
+        .pre(&recent)
+        .into();
+
+    match chart::ratio_chart(&records) {
+        Ok(png) => {
+            bot.send_photo(chat_id, InputFile::memory(png).file_name("chart.png"))
+                .caption(text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
+        }
+        Err(e) => {
+            log::debug!("No history chart for {}: {}", pair_name, e);
+            bot.send_message(chat_id, text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A pair's snapshots over the last `hours` as a CSV document, oldest first
+async fn send_export(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    database: &Database,
+    pair_name: &str,
+    hours: i64,
+) -> Result<()> {
+    let t = config.language.texts();
+    let now = Utc::now();
+    let start = now - chrono::Duration::hours(hours);
+
+    // Count first, so a long window isn't loaded only to be refused
+    let stats = database.get_pair_statistics_range(pair_name, start, now).await?;
+    if stats.count == 0 {
+        let text = markdown::fill(t.bot_no_history, &[("pair", &pair_name), ("hours", &hours)]);
+        bot.send_message(chat_id, text)
+            .parse_mode(ParseMode::MarkdownV2)
+            .await?;
+        return Ok(());
+    }
+    if stats.count > MAX_EXPORT_ROWS {
+        let text = fill(t.bot_export_too_large, &[("hours", &hours)]);
+        bot.send_message(chat_id, text).await?;
+        return Ok(());
+    }
+
+    let records = database.get_ratio_history_range(pair_name, start, now).await?;
+
+    let mut csv = String::from("timestamp,pair,symbol_a,symbol_b,price_a,price_b,ratio\n");
+    for record in records.iter().rev() {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            record.timestamp.to_rfc3339(),
+            csv_field(&record.pair_name),
+            csv_field(&record.symbol_a),
+            csv_field(&record.symbol_b),
+            record.price_a,
+            record.price_b,
+            record.ratio
+        ));
+    }
+    if csv.len() > MAX_DOCUMENT_BYTES {
+        let text = fill(t.bot_export_too_large, &[("hours", &hours)]);
+        bot.send_message(chat_id, text).await?;
+        return Ok(());
+    }
+
+    let file_name = format!("{}_{}h.csv", pair_name.replace(['/', ' '], "-"), hours);
+    let caption = fill(
+        t.bot_export,
+        &[("pair", &pair_name), ("hours", &hours), ("count", &records.len())],
+    );
+    bot.send_document(chat_id, InputFile::memory(csv.into_bytes()).file_name(file_name))
+        .caption(caption)
+        .await?;
+
+    Ok(())
+}
+
+/// Current ratios of a user's favorite pairs in one message
+async fn send_favorites(
+    bot: &Bot,
+    chat_id: ChatId,
+    user_id: u64,
+    config: &Config,
+    calculator: &RatioCalculator,
+    database: &Database,
+) -> Result<()> {
+    let t = config.language.texts();
+    let favorites = database.get_favorites(user_id).await?;
+    // Favorites of removed pairs are skipped
+    let pairs: Vec<&RatioPair> = favorites.iter().filter_map(|name| config.find_pair(name)).collect();
+    if pairs.is_empty() {
+        bot.send_message(chat_id, t.bot_no_favorites).await?;
+        return Ok(());
+    }
+
+    bot.send_message(chat_id, t.bot_calculating)
+        .parse_mode(ParseMode::MarkdownV2)
+        .await?;
+
+    // Shared by all pairs, so legs used by several of them are fetched once
+    let mut resolved = HashMap::new();
+    let mut text = Markdown::new().text("⭐ ").bold(t.bot_favorites).newline();
+    for pair in &pairs {
+        text = text.newline().bold(&pair.name).text(" ");
+        text = match calculator.calculate_pair_ratio(config, pair, &mut resolved).await {
+            Ok(ratio) => text.code(&format!("{:.8}", ratio.ratio)),
+            Err(e) => {
+                log::warn!("Failed to calculate ratio for favorite {}: {}", pair.name, e);
+                text.text("❌")
+            }
+        };
+    }
+    let time = Utc::now().with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S %Z");
+    text = text.paragraph().italic(&format!("{}: {}", t.time, time));
+
+    // Open a favorite's full ratio card from here
+    let mut buttons: Vec<Vec<InlineKeyboardButton>> = pairs
+        .chunks(2)
+        .map(|row| {
+            row.iter()
+                .map(|pair| InlineKeyboardButton::callback(pair.name.clone(), format!("ratio:{}", pair.name)))
+                .collect()
+        })
+        .collect();
+    buttons.push(vec![InlineKeyboardButton::callback(t.button_main_menu, "main_menu")]);
+
+    bot.send_message(chat_id, text)
+        .parse_mode(ParseMode::MarkdownV2)
+        .reply_markup(InlineKeyboardMarkup::new(buttons))
+        .await?;
+
+    Ok(())
+}
+
+/// Effective prices, ratio and slippage of trading `volume` of a pair's legs
+async fn send_volume_analysis(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    calculator: &RatioCalculator,
+    pair: &RatioPair,
+    volume: f64,
+) -> Result<()> {
+    let t = config.language.texts();
+    bot.send_message(chat_id, t.bot_analyzing)
+        .parse_mode(ParseMode::MarkdownV2)
+        .await?;
+
+    match calculator
+        .calculate_volume_based_ratio(&pair.name, &pair.symbol_a, &pair.symbol_b, volume)
+        .await
+    {
+        Ok(ratio) => {
+            let time = ratio
+                .timestamp
+                .with_timezone(&config.timezone)
+                .format("%Y-%m-%d %H:%M:%S %Z");
+            let text = Markdown::new()
+                .text("📊 ")
+                .bold(t.bot_volume_analysis)
+                .paragraph()
+                .bold(t.bot_pair)
+                .text(&format!(" {}", pair.name))
+                .newline()
+                .bold(t.bot_volume)
+                .text(" ")
+                .code(&volume.to_string())
+                .newline()
+                .bold(t.bot_ratio)
+                .text(" ")
+                .code(&format!("{:.8}", ratio.ratio))
+                .paragraph()
+                .bold(&pair.symbol_a)
+                .newline()
+                .text(&format!("{} ", t.bot_effective_price))
+                .code(&format!("${:.2}", ratio.effective_price_a))
+                .newline()
+                .text(&format!("{} ", t.bot_slippage))
+                .code(&format!("{:.3}%", ratio.slippage_a))
+                .paragraph()
+                .bold(&pair.symbol_b)
+                .newline()
+                .text(&format!("{} ", t.bot_effective_price))
+                .code(&format!("${:.2}", ratio.effective_price_b))
+                .newline()
+                .text(&format!("{} ", t.bot_slippage))
+                .code(&format!("{:.3}%", ratio.slippage_b))
+                .paragraph()
+                .italic(&format!("{}: {}", t.time, time));
+
+            bot.send_message(chat_id, text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .reply_markup(create_back_keyboard(t))
+                .await?;
+        }
+        Err(e) => {
+            let error_text = markdown::fill(t.bot_volume_error, &[("error", &e)]);
+            bot.send_message(chat_id, error_text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `<symbol> [volume] [buy|sell]`, `None` if malformed; buying is the default side
+fn parse_slippage_args(args: &str) -> Option<(String, Option<f64>, OrderSide)> {
+    let parse_volume = |volume: &str| volume.parse::<f64>().ok().filter(|v| v.is_finite() && *v > 0.0);
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let (symbol, volume, side) = match args.as_slice() {
+        [symbol] => (symbol, None, OrderSide::Buy),
+        [symbol, volume] => (symbol, Some(parse_volume(volume)?), OrderSide::Buy),
+        [symbol, volume, side] => {
+            let side = match side.to_lowercase().as_str() {
+                "buy" => OrderSide::Buy,
+                "sell" => OrderSide::Sell,
+                _ => return None,
+            };
+            (symbol, Some(parse_volume(volume)?), side)
+        }
+        _ => return None,
+    };
+
+    symbol
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric())
+        .then(|| (symbol.to_uppercase(), volume, side))
+}
+
+/// Run a slippage analysis and reply with the result
+async fn send_slippage(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    calculator: &RatioCalculator,
+    symbol: &str,
+    volume: f64,
+    side: OrderSide,
+) -> Result<()> {
+    let t = config.language.texts();
+    bot.send_message(chat_id, t.bot_analyzing)
+        .parse_mode(ParseMode::MarkdownV2)
+        .await?;
+
+    let side_label = match side {
+        OrderSide::Buy => t.bot_buy,
+        OrderSide::Sell => t.bot_sell,
+    };
+    let text = match calculator.analyze_slippage(symbol, volume, side).await {
+        Ok(analysis) => {
+            let time = Utc::now()
+                .with_timezone(&config.timezone)
+                .format("%Y-%m-%d %H:%M:%S %Z");
+            Markdown::new()
+                .text("💧 ")
+                .bold(&fill(t.bot_slippage_analysis, &[("side", &side_label), ("symbol", &symbol)]))
+                .paragraph()
+                .bold(t.bot_volume)
+                .text(" ")
+                .code(&volume.to_string())
+                .newline()
+                .bold(t.bot_mid_price)
+                .text(" ")
+                .code(&format!("${:.2}", analysis.mid_price))
+                .newline()
+                .bold(t.bot_effective_price)
+                .text(" ")
+                .code(&format!("${:.2}", analysis.effective_price))
+                .newline()
+                .bold(t.bot_slippage)
+                .text(" ")
+                .code(&format!("{:.3}%", analysis.slippage_percentage))
+                .newline()
+                .bold(t.bot_depth_consumed)
+                .text(" ")
+                .code(&analysis.depth_consumed.to_string())
+                .newline()
+                .bold(t.bot_total_cost)
+                .text(" ")
+                .code(&format!("${:.2}", analysis.total_cost))
+                .paragraph()
+                .italic(&format!("{}: {}", t.time, time))
+                .into()
+        }
+        Err(e) => markdown::fill(t.bot_slippage_error, &[("error", &e)]),
+    };
+    bot.send_message(chat_id, text)
+        .parse_mode(ParseMode::MarkdownV2)
+        .await?;
+
+    Ok(())
+}
+
+/// Offer buy and sell buttons for a symbol at a few trade sizes
+async fn send_slippage_presets(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    calculator: &RatioCalculator,
+    symbol: &str,
+) -> Result<()> {
+    let t = config.language.texts();
+    let price = match calculator.is_trading(symbol).await {
+        Ok(true) => calculator.price(symbol).await,
+        Ok(false) => {
+            let text = markdown::fill(t.bot_unknown_symbol, &[("symbol", &symbol)]);
+            bot.send_message(chat_id, text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
+            return Ok(());
+        }
+        Err(e) => Err(e),
+    };
+    let price = match price {
+        Ok(price) => price,
+        Err(e) => {
+            let text = markdown::fill(t.bot_slippage_error, &[("error", &e)]);
+            bot.send_message(chat_id, text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let buttons: Vec<Vec<InlineKeyboardButton>> = SLIPPAGE_NOTIONALS
+        .iter()
+        .map(|(notional, label)| {
+            let volume = round_significant(notional / price, 4);
+            vec![
+                InlineKeyboardButton::callback(
+                    format!("{} {} (≈{})", t.bot_buy, volume, label),
+                    format!("slip:buy:{}:{}", volume, symbol),
+                ),
+                InlineKeyboardButton::callback(
+                    format!("{} {} (≈{})", t.bot_sell, volume, label),
+                    format!("slip:sell:{}:{}", volume, symbol),
+                ),
+            ]
+        })
+        .collect();
+
+    let text = fill(t.bot_slippage_pick_volume, &[("symbol", &symbol), ("price", &price)]);
+    bot.send_message(chat_id, text)
+        .reply_markup(InlineKeyboardMarkup::new(buttons))
+        .await?;
+
+    Ok(())
+}
+
+/// Round to a number of significant digits, for readable preset volumes
+fn round_significant(value: f64, digits: i32) -> f64 {
+    if value <= 0.0 {
+        return value;
+    }
+    // Dividing by a negative power of ten would leave float noise on large values
+    let exponent = digits - 1 - value.log10().floor() as i32;
+    if exponent >= 0 {
+        let scale = 10f64.powi(exponent);
+        (value * scale).round() / scale
+    } else {
+        let scale = 10f64.powi(-exponent);
+        (value / scale).round() * scale
+    }
+}
+
+/// Thresholds given as command arguments, `None` unless all are positive numbers
+fn parse_thresholds(args: &[&str]) -> Option<Vec<f64>> {
+    let mut thresholds = args
+        .iter()
+        .map(|arg| arg.trim_end_matches('%').parse::<f64>().ok().filter(|t| t.is_finite() && *t > 0.0))
+        .collect::<Option<Vec<_>>>()?;
+    thresholds.sort_by(f64::total_cmp);
+    thresholds.dedup();
+    Some(thresholds)
+}
+
+/// A pair's change rules with buttons to move the thresholds and pick a window
+fn create_rule_card(config: &Config, pair_name: &str) -> (String, InlineKeyboardMarkup) {
+    let t = config.language.texts();
+    let mut text = Markdown::new().text("⚙️ ").bold(pair_name).paragraph();
+    let rules = config.find_pair(pair_name).map(|pair| config.change_rules(pair)).unwrap_or_default();
+    for rule in &rules {
+        let thresholds = rule
+            .thresholds
+            .iter()
+            .map(|t| format!("{}%", t))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let window = format_duration(rule.window_secs);
+        text = text
+            .text(&fill(t.bot_rule, &[("thresholds", &thresholds), ("window", &window)]))
+            .newline();
+    }
+    if config.rule_overrides.contains_key(pair_name) {
+        text = text.newline().italic(t.bot_rule_overridden);
+    }
+
+    let mut buttons = vec![vec![
+        InlineKeyboardButton::callback(
+            format!("➖ {}%", THRESHOLD_STEP),
+            format!("thr:-{}:{}", THRESHOLD_STEP, pair_name),
+        ),
+        InlineKeyboardButton::callback(
+            format!("➕ {}%", THRESHOLD_STEP),
+            format!("thr:{}:{}", THRESHOLD_STEP, pair_name),
+        ),
+    ]];
+    buttons.push(
+        WINDOW_PRESETS
+            .iter()
+            .map(|secs| {
+                let selected = rules.first().is_some_and(|rule| rule.window_secs == *secs);
+                let label = format_duration(*secs);
+                let label = if selected { format!("• {}", label) } else { label };
+                InlineKeyboardButton::callback(label, format!("win:{}:{}", secs, pair_name))
+            })
+            .collect(),
+    );
+    if config.rule_overrides.contains_key(pair_name) {
+        buttons.push(vec![InlineKeyboardButton::callback(
+            t.button_reset_rule,
+            format!("thr_reset:{}", pair_name),
+        )]);
+    }
+
+    (text.into(), InlineKeyboardMarkup::new(buttons))
+}
+
+/// Whether the sender of a message may add and remove pairs
+fn is_admin(config: &Config, msg: &Message) -> bool {
+    msg.from
+        .as_ref()
+        .is_some_and(|user| config.telegram.is_admin(user.id.0))
+}
+
+/// Pair names end up in callback data, so they are kept short and free of `:`
+fn is_valid_pair_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_PAIR_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '/'))
+}
+
+/// The symbol in a dialogue reply if Binance trades it; otherwise tells the
+/// user why not and returns `None` so the dialogue asks again
+async fn check_symbol(
+    bot: &Bot,
+    msg: &Message,
+    t: &Texts,
+    calculator: &RatioCalculator,
+) -> Result<Option<String>> {
+    let symbol = msg.text().unwrap_or_default().trim().to_uppercase();
+    let check = if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(false)
+    } else {
+        calculator.is_trading(&symbol).await
+    };
+
+    let text = match check {
+        Ok(true) => return Ok(Some(symbol)),
+        Ok(false) => markdown::fill(t.bot_add_pair_invalid_symbol, &[("symbol", &symbol)]),
+        Err(e) => markdown::fill(t.bot_add_pair_check_failed, &[("symbol", &symbol), ("error", &e)]),
+    };
+    bot.send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::MarkdownV2)
+        .await?;
+
+    Ok(None)
+}
+
+/// An alert's buttons once it is acknowledged, `None` if none are left
+fn without_ack_button(markup: &InlineKeyboardMarkup) -> Option<InlineKeyboardMarkup> {
+    let rows: Vec<Vec<InlineKeyboardButton>> = markup
+        .inline_keyboard
+        .iter()
+        .map(|row| {
+            row.iter()
+                .filter(|button| {
+                    !matches!(&button.kind, InlineKeyboardButtonKind::CallbackData(data) if data.starts_with("ack:"))
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .filter(|row| !row.is_empty())
+        .collect();
+
+    (!rows.is_empty()).then(|| InlineKeyboardMarkup::new(rows))
+}
+
+fn create_main_keyboard(t: &Texts) -> InlineKeyboardMarkup {
     let buttons = vec![
         vec![InlineKeyboardButton::callback(
-            "📊 Get Ratios",
+            t.button_get_ratios,
             "main:ratios",
         )],
         vec![InlineKeyboardButton::callback(
-            "📋 View Pairs",
+            t.button_view_pairs,
             "main:pairs",
         )],
+        vec![InlineKeyboardButton::callback(t.button_favorites, "favorites")],
     ];
 
     InlineKeyboardMarkup::new(buttons)
 }
 
-fn create_pair_selection_keyboard(pairs: &[RatioPair]) -> InlineKeyboardMarkup {
-    let mut buttons: Vec<Vec<InlineKeyboardButton>> = pairs
+/// Categories in the order they first appear in the config
+fn pair_categories(pairs: &[RatioPair]) -> Vec<&str> {
+    let mut categories = Vec::new();
+    for category in pairs.iter().filter_map(|p| p.category.as_deref()) {
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    categories
+}
+
+/// Where picking a pair starts: the categories when pairs have them, else the
+/// first page of pairs
+fn create_pair_menu(t: &Texts, pairs: &[RatioPair]) -> (&'static str, InlineKeyboardMarkup) {
+    let categories = pair_categories(pairs);
+    if categories.is_empty() {
+        return (t.bot_select_pair, create_pair_selection_keyboard(t, pairs, ALL_CATEGORIES, 0));
+    }
+
+    let count = |category: Option<&str>| pairs.iter().filter(|p| p.category.as_deref() == category).count();
+    let mut buttons: Vec<Vec<InlineKeyboardButton>> = categories
         .iter()
+        .map(|category| {
+            vec![InlineKeyboardButton::callback(
+                format!("{} ({})", category, count(Some(category))),
+                format!("pairs:0:{}", category),
+            )]
+        })
+        .collect();
+    let uncategorized = count(None);
+    if uncategorized > 0 {
+        buttons.push(vec![InlineKeyboardButton::callback(
+            format!("{} ({})", t.bot_other_category, uncategorized),
+            "pairs:0:",
+        )]);
+    }
+    buttons.push(vec![InlineKeyboardButton::callback(
+        format!("{} ({})", t.button_all_pairs, pairs.len()),
+        format!("pairs:0:{}", ALL_CATEGORIES),
+    )]);
+    buttons.push(vec![InlineKeyboardButton::callback(t.button_back, "main_menu")]);
+
+    (t.bot_select_category, InlineKeyboardMarkup::new(buttons))
+}
+
+/// One page of the pairs in a category, with arrows when there are several pages
+fn create_pair_selection_keyboard(
+    t: &Texts,
+    pairs: &[RatioPair],
+    category: &str,
+    page: usize,
+) -> InlineKeyboardMarkup {
+    let listed: Vec<&RatioPair> = pairs
+        .iter()
+        .filter(|p| match category {
+            ALL_CATEGORIES => true,
+            "" => p.category.is_none(),
+            category => p.category.as_deref() == Some(category),
+        })
+        .collect();
+    let pages = listed.len().div_ceil(PAIRS_PER_PAGE).max(1);
+    let page = page.min(pages - 1);
+
+    let mut buttons: Vec<Vec<InlineKeyboardButton>> = listed
+        .iter()
+        .skip(page * PAIRS_PER_PAGE)
+        .take(PAIRS_PER_PAGE)
         .map(|pair| {
             vec![InlineKeyboardButton::callback(
                 &pair.name,
@@ -351,73 +1915,118 @@ fn create_pair_selection_keyboard(pairs: &[RatioPair]) -> InlineKeyboardMarkup {
         })
         .collect();
 
-    buttons.push(vec![InlineKeyboardButton::callback("« Back", "main_menu")]);
+    if pages > 1 {
+        let mut nav = Vec::new();
+        if page > 0 {
+            nav.push(InlineKeyboardButton::callback(
+                t.button_previous_page,
+                format!("pairs:{}:{}", page - 1, category),
+            ));
+        }
+        nav.push(InlineKeyboardButton::callback(format!("{}/{}", page + 1, pages), "page"));
+        if page + 1 < pages {
+            nav.push(InlineKeyboardButton::callback(
+                t.button_next_page,
+                format!("pairs:{}:{}", page + 1, category),
+            ));
+        }
+        buttons.push(nav);
+    }
+
+    let back = if pair_categories(pairs).is_empty() { "main_menu" } else { "categories" };
+    buttons.push(vec![InlineKeyboardButton::callback(t.button_back, back)]);
 
     InlineKeyboardMarkup::new(buttons)
 }
 
-fn create_volume_analysis_keyboard(pair_name: &str, volume: f64) -> InlineKeyboardMarkup {
-    let buttons = vec![
-        vec![InlineKeyboardButton::callback(
-            format!("📊 Volume Analysis ({})", volume),
-            format!("volume:{}:{}", pair_name, volume),
-        )],
-        vec![InlineKeyboardButton::callback(
-            "« Back to Pairs",
-            "back_to_pairs",
-        )],
-    ];
+fn create_pair_keyboard(
+    t: &Texts,
+    pair_name: &str,
+    volume: Option<f64>,
+    paused: bool,
+    favorite: bool,
+    live_duration_secs: u64,
+) -> InlineKeyboardMarkup {
+    let mut buttons = Vec::new();
+
+    if let Some(volume) = volume {
+        buttons.push(
+            VOLUME_MULTIPLIERS
+                .iter()
+                .map(|multiplier| {
+                    let volume = round_significant(volume * multiplier, 4);
+                    InlineKeyboardButton::callback(
+                        fill(t.button_volume_analysis, &[("volume", &volume)]),
+                        format!("volume:{}:{}", pair_name, volume),
+                    )
+                })
+                .collect(),
+        );
+        buttons.push(vec![InlineKeyboardButton::callback(
+            t.button_custom_volume,
+            format!("volume:{}:custom", pair_name),
+        )]);
+    }
+
+    let pause_button = if paused {
+        InlineKeyboardButton::callback(t.button_resume, format!("resume:{}", pair_name))
+    } else {
+        InlineKeyboardButton::callback(t.button_pause, format!("pause:{}", pair_name))
+    };
+    let favorite_button = if favorite {
+        InlineKeyboardButton::callback(t.button_remove_favorite, format!("fav:{}", pair_name))
+    } else {
+        InlineKeyboardButton::callback(t.button_add_favorite, format!("fav:{}", pair_name))
+    };
+    buttons.push(vec![pause_button, favorite_button]);
+    buttons.push(vec![InlineKeyboardButton::callback(
+        fill(t.button_live, &[("duration", &format_duration(live_duration_secs))]),
+        format!("live:{}", pair_name),
+    )]);
+    buttons.push(vec![
+        InlineKeyboardButton::callback(t.button_stats, format!("stats:{}", pair_name)),
+        InlineKeyboardButton::callback(t.button_thresholds, format!("rules:{}", pair_name)),
+    ]);
+
+    buttons.push(vec![InlineKeyboardButton::callback(
+        t.button_back_to_pairs,
+        "back_to_pairs",
+    )]);
 
     InlineKeyboardMarkup::new(buttons)
 }
 
-fn create_back_keyboard() -> InlineKeyboardMarkup {
+fn create_back_keyboard(t: &Texts) -> InlineKeyboardMarkup {
     let buttons = vec![
         vec![InlineKeyboardButton::callback(
-            "« Back to Pairs",
+            t.button_back_to_pairs,
             "back_to_pairs",
         )],
-        vec![InlineKeyboardButton::callback("« Main Menu", "main_menu")],
+        vec![InlineKeyboardButton::callback(t.button_main_menu, "main_menu")],
     ];
 
     InlineKeyboardMarkup::new(buttons)
 }
 
-fn create_pairs_list(config: &Config) -> String {
-    let mut text = String::from("📋 *Configured Ratio Pairs*\n\n");
+fn create_pairs_list(config: &Config, paused: &HashSet<String>) -> String {
+    let t = config.language.texts();
+    let mut text = Markdown::new().text("📋 ").bold(t.bot_configured_pairs).paragraph();
 
     for (i, pair) in config.ratio_pairs.iter().enumerate() {
-        text.push_str(&format!(
-            "{}\\. *{}*\n   {} / {}\n",
-            i + 1,
-            escape_markdown(&pair.name),
-            escape_markdown(&pair.symbol_a),
-            escape_markdown(&pair.symbol_b)
-        ));
+        let status = if paused.contains(&pair.name) { " ⏸" } else { "" };
+        text = text
+            .text(&format!("{}. ", i + 1))
+            .bold(&pair.name)
+            .text(status)
+            .newline()
+            .text(&format!("   {} / {}", pair.leg_a().label(), pair.leg_b().label()))
+            .newline();
 
         if let Some(vol) = pair.analysis_volume {
-            text.push_str(&format!("   Volume: {}\n", vol));
+            text = text.text(&format!("   {} {}", t.bot_volume, vol)).newline();
         }
-        text.push('\n');
+        text = text.newline();
     }
 
-    text
-}
-
-fn escape_markdown(text: &str) -> String {
-    text.chars()
-        .map(|c| match c {
-            '_' | '*' | '[' | ']' | '(' | ')' | '~' | '`' | '>' | '#' | '+' | '-' | '=' | '|'
-            | '{' | '}' | '.' | '!' => {
-                format!("\\{}", c)
-            }
-            _ => c.to_string(),
-        })
-        .collect()
-}
-
-impl Clone for RatioCalculator {
-    fn clone(&self) -> Self {
-        Self::new(BinanceClient::new())
-    }
+    text.into()
 }
@@ -0,0 +1,95 @@
+use anyhow::{Context, Result, anyhow};
+use image::{ImageFormat, RgbImage};
+use plotters::prelude::*;
+use std::io::Cursor;
+
+use crate::database::RatioRecord;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 320;
+
+/// PNG line chart of a pair's ratio, from snapshots ordered oldest first.
+/// Green when the ratio ended higher than it started, red when lower. The
+/// chart has no text: the alert it goes with carries the numbers.
+pub fn ratio_chart(records: &[RatioRecord]) -> Result<Vec<u8>> {
+    let (Some(first), Some(last)) = (records.first(), records.last()) else {
+        return Err(anyhow!("No snapshots to chart"));
+    };
+    if records.len() < 2 {
+        return Err(anyhow!("Not enough snapshots to chart"));
+    }
+
+    let start = first.timestamp.timestamp();
+    let end = last.timestamp.timestamp().max(start + 1);
+    let min = records
+        .iter()
+        .map(|r| r.ratio)
+        .fold(f64::INFINITY, f64::min);
+    let max = records
+        .iter()
+        .map(|r| r.ratio)
+        .fold(f64::NEG_INFINITY, f64::max);
+    // Keep a flat line off the edges
+    let padding = ((max - min) * 0.1).max(max.abs() * 1e-6).max(f64::EPSILON);
+
+    let color = if last.ratio >= first.ratio {
+        RGBColor(22, 163, 74)
+    } else {
+        RGBColor(220, 38, 38)
+    };
+
+    let mut pixels = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)
+            .map_err(|e| anyhow!("Failed to draw chart: {}", e))?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(12)
+            .build_cartesian_2d(start..end, (min - padding)..(max + padding))
+            .map_err(|e| anyhow!("Failed to draw chart: {}", e))?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .y_labels(5)
+            .light_line_style(WHITE)
+            .bold_line_style(RGBColor(229, 231, 235))
+            .axis_style(RGBColor(209, 213, 219))
+            .y_label_formatter(&|_| String::new())
+            .x_label_formatter(&|_| String::new())
+            .draw()
+            .map_err(|e| anyhow!("Failed to draw chart: {}", e))?;
+
+        let points = records.iter().map(|r| (r.timestamp.timestamp(), r.ratio));
+        chart
+            .draw_series(AreaSeries::new(
+                points.clone(),
+                min - padding,
+                color.mix(0.12),
+            ))
+            .map_err(|e| anyhow!("Failed to draw chart: {}", e))?;
+        chart
+            .draw_series(LineSeries::new(points, color.stroke_width(2)))
+            .map_err(|e| anyhow!("Failed to draw chart: {}", e))?;
+        chart
+            .draw_series(std::iter::once(Circle::new(
+                (last.timestamp.timestamp(), last.ratio),
+                4,
+                color.filled(),
+            )))
+            .map_err(|e| anyhow!("Failed to draw chart: {}", e))?;
+
+        root.present()
+            .map_err(|e| anyhow!("Failed to draw chart: {}", e))?;
+    }
+
+    let image =
+        RgbImage::from_raw(WIDTH, HEIGHT, pixels).context("Chart buffer has the wrong size")?;
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .context("Failed to encode chart")?;
+
+    Ok(png.into_inner())
+}
@@ -1,39 +1,696 @@
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use croner::Cron;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use anyhow::{Context, Result};
 
+use crate::i18n::{Language, fill};
+use crate::templates::Templates;
+
+/// Longest pair name, in bytes. Names go into the bot's button callback
+/// data, which Telegram caps at 64 bytes.
+pub const MAX_PAIR_NAME_LENGTH: usize = 32;
+
+/// The config as last loaded from the file, shared so a reload reaches the bot too
+pub type SharedConfig = Arc<RwLock<Config>>;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
+    /// IANA timezone for message timestamps, schedules and maintenance windows
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
+    /// Language of alerts, reports and bot replies
+    #[serde(default)]
+    pub language: Language,
+    /// Needed for the Telegram channel and the interactive bot
+    #[serde(default)]
     pub telegram: TelegramConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
     pub monitoring: MonitoringConfig,
     pub database: DatabaseConfig,
+    /// Publish ratios to an MQTT broker, e.g. for Home Assistant (optional)
+    pub mqtt: Option<MqttConfig>,
+    #[serde(default)]
+    pub templates: TemplatesConfig,
     pub ratio_pairs: Vec<RatioPair>,
+    /// Change rules set through the bot, replacing a pair's configured rules
+    #[serde(skip)]
+    pub rule_overrides: BTreeMap<String, ChangeRule>,
+}
+
+/// Handlebars templates replacing the built-in text of some messages
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct TemplatesConfig {
+    /// Threshold alerts over a change window or from the daily open
+    pub alert: Option<String>,
+    /// The periodic ratio update
+    pub periodic: Option<String>,
+    /// The daily summary report
+    pub daily_summary: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DatabaseConfig {
-    /// Path to SQLite database file
+    /// Path to SQLite database file, or a `postgres://` URL
     pub path: String,
     /// Days to keep historical data (older data will be cleaned up)
     pub retention_days: Option<i64>,
+    /// Days to keep each kind of history, overriding `retention_days`
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    /// Days after which ratio snapshots are condensed into hourly aggregates
+    pub rollup_after_days: Option<i64>,
+    /// Store this many levels of each side of the order books fetched for
+    /// volume and slippage analysis
+    pub order_book_levels: Option<usize>,
+    /// Gzip the stored order books
+    #[serde(default)]
+    pub compress_order_books: bool,
+    /// Send a Telegram summary when the retention cleanup purges rows
+    #[serde(default)]
+    pub notify_on_cleanup: bool,
+    /// Key of a SQLCipher encrypted database; `RATIO_NOTI_DB_KEY` overrides it
+    pub encryption_key: Option<String>,
+    /// Keep the raw Binance responses of some pairs for debugging
+    pub archive: Option<ResponseArchiveConfig>,
+}
+
+/// Days to keep each kind of history; unset ones fall back to `retention_days`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct RetentionConfig {
+    /// Raw snapshots and the hourly aggregates condensed from them
+    pub snapshots: Option<i64>,
+    /// Alerts and recoveries
+    pub alerts: Option<i64>,
+    pub volume_ratios: Option<i64>,
+    pub order_books: Option<i64>,
+    /// The monitor's log of checks
+    pub checks: Option<i64>,
+}
+
+impl RetentionConfig {
+    /// The same number of days for everything
+    pub fn uniform(days: i64) -> Self {
+        Self {
+            snapshots: Some(days),
+            alerts: Some(days),
+            volume_ratios: Some(days),
+            order_books: Some(days),
+            checks: Some(days),
+        }
+    }
+
+    /// Periods by table name, as in `[database.retention]`
+    pub fn periods(&self) -> [(&'static str, Option<i64>); 5] {
+        [
+            ("snapshots", self.snapshots),
+            ("alerts", self.alerts),
+            ("volume_ratios", self.volume_ratios),
+            ("order_books", self.order_books),
+            ("checks", self.checks),
+        ]
+    }
+
+    /// Whether nothing is ever purged
+    pub fn is_empty(&self) -> bool {
+        self.periods().iter().all(|(_, days)| days.is_none())
+    }
 }
 
+/// Raw ticker and order book responses kept to trace an anomalous ratio
+/// back to the data received
 #[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ResponseArchiveConfig {
+    /// Pairs whose legs' responses are archived
+    pub pairs: Vec<String>,
+    /// Days to keep responses (default: 7)
+    #[serde(default = "default_archive_max_age_days")]
+    pub max_age_days: i64,
+    /// Megabytes of compressed responses to keep, dropping the oldest first (default: 100)
+    #[serde(default = "default_archive_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+fn default_archive_max_age_days() -> i64 {
+    7
+}
+
+fn default_archive_max_size_mb() -> u64 {
+    100
+}
+
+impl DatabaseConfig {
+    /// Connection URL: `path` itself for PostgreSQL, otherwise the SQLite file,
+    /// created if missing
+    pub fn url(&self) -> String {
+        if self.path.starts_with("postgres://") || self.path.starts_with("postgresql://") {
+            self.path.clone()
+        } else {
+            format!("sqlite:{}?mode=rwc", self.path)
+        }
+    }
+
+    /// Days to keep each kind of history: its `retention` entry, or else
+    /// `retention_days`. Kinds with neither are kept forever
+    pub fn retention_periods(&self) -> RetentionConfig {
+        RetentionConfig {
+            snapshots: self.retention.snapshots.or(self.retention_days),
+            alerts: self.retention.alerts.or(self.retention_days),
+            volume_ratios: self.retention.volume_ratios.or(self.retention_days),
+            order_books: self.retention.order_books.or(self.retention_days),
+            checks: self.retention.checks.or(self.retention_days),
+        }
+    }
+
+    /// SQLCipher key, from `RATIO_NOTI_DB_KEY` or else `encryption_key`
+    pub fn encryption_key(&self) -> Option<String> {
+        std::env::var("RATIO_NOTI_DB_KEY")
+            .ok()
+            .or_else(|| self.encryption_key.clone())
+            .filter(|key| !key.is_empty())
+    }
+
+    /// `path` for logs and messages, without a password in a PostgreSQL URL
+    pub fn location(&self) -> String {
+        if let Some((scheme, rest)) = self.path.split_once("://")
+            && let Some((credentials, host)) = rest.rsplit_once('@')
+            && let Some((user, _)) = credentials.split_once(':')
+        {
+            return format!("{}://{}@{}", scheme, user, host);
+        }
+        self.path.clone()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
 pub struct TelegramConfig {
     pub token: String,
+    /// Chat that receives notifications when no `recipients` are listed
     pub user_id: i64,
+    /// Chat that receives escalated reminders (defaults to the recipients)
+    pub escalation_chat_id: Option<i64>,
+    /// Forum topic of the escalation chat that receives escalated reminders
+    pub escalation_thread_id: Option<i32>,
+    /// Chats (users, groups, channels) that receive notifications, each with
+    /// optional filters; replaces `user_id` when set
+    #[serde(default)]
+    pub recipients: Vec<TelegramRecipient>,
+    /// Keep one pinned message with the latest ratios, edited every cycle,
+    /// instead of sending periodic updates
+    #[serde(default)]
+    pub live_status: bool,
+    /// Send notifications as plain text, without MarkdownV2 formatting
+    #[serde(default)]
+    pub plain_text: bool,
+    /// Send each pair's alerts to the chats subscribed to it through the bot
+    /// instead of to the recipients
+    #[serde(default)]
+    pub subscriptions: bool,
+    /// Users besides `user_id` who may add and remove pairs through the bot
+    #[serde(default)]
+    pub admins: Vec<i64>,
+    /// Order book lookups (volume and slippage analysis) each bot user may
+    /// make in a row before being throttled
+    #[serde(default = "default_rate_limit_burst")]
+    pub rate_limit_burst: u32,
+    /// Seconds until a throttled user gets another lookup (0 disables the limit)
+    #[serde(default = "default_rate_limit_refill_secs")]
+    pub rate_limit_refill_secs: u64,
+    /// Seconds between refreshes of a ratio card after its Live button is pressed
+    #[serde(default = "default_bot_live_refresh_secs")]
+    pub bot_live_refresh_secs: u64,
+    /// How long a ratio card keeps refreshing after its Live button is pressed
+    #[serde(default = "default_bot_live_duration_secs")]
+    pub bot_live_duration_secs: u64,
+}
+
+fn default_rate_limit_burst() -> u32 {
+    5
+}
+
+fn default_rate_limit_refill_secs() -> u64 {
+    12
+}
+
+fn default_bot_live_refresh_secs() -> u64 {
+    15
+}
+
+fn default_bot_live_duration_secs() -> u64 {
+    300
+}
+
+impl TelegramConfig {
+    /// Whether a Telegram user may change the monitored pairs through the bot
+    pub fn is_admin(&self, user_id: u64) -> bool {
+        let user_id = user_id as i64;
+        user_id == self.user_id || self.admins.contains(&user_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TelegramRecipient {
+    pub chat_id: i64,
+    /// Only alerts for these pairs (default: all pairs)
+    #[serde(default)]
+    pub pairs: Vec<String>,
+    /// Only alerts with these severities (default: all severities)
+    #[serde(default)]
+    pub severities: Vec<Severity>,
+    /// Forum topic that receives messages when the chat is a supergroup with topics
+    pub message_thread_id: Option<i32>,
+    /// Forum topic per pair for that pair's alerts, overriding `message_thread_id`
+    #[serde(default)]
+    pub pair_topics: BTreeMap<String, i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Client id, also used to keep Home Assistant entity ids unique (default: ratio-noti)
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    /// Prefix of the state and status topics (default: ratio-noti)
+    #[serde(default = "default_mqtt_client_id")]
+    pub topic_prefix: String,
+    /// Announce each pair as Home Assistant sensors (default: true)
+    #[serde(default = "default_true")]
+    pub discovery: bool,
+    /// Home Assistant's discovery prefix (default: homeassistant)
+    #[serde(default = "default_discovery_prefix")]
+    pub discovery_prefix: String,
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "ratio-noti".to_string()
+}
+
+fn default_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NotificationsConfig {
+    /// Channels that receive alerts, updates and reports (default: telegram)
+    #[serde(default = "default_channels")]
+    pub channels: Vec<Channel>,
+    pub discord: Option<DiscordConfig>,
+    pub webhook: Option<WebhookConfig>,
+    pub ntfy: Option<NtfyConfig>,
+    pub gotify: Option<GotifyConfig>,
+    pub matrix: Option<MatrixConfig>,
+    /// Which messages arrive without a notification sound
+    #[serde(default)]
+    pub silent: SilentConfig,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            channels: default_channels(),
+            discord: None,
+            webhook: None,
+            ntfy: None,
+            gotify: None,
+            matrix: None,
+            silent: SilentConfig::default(),
+        }
+    }
+}
+
+/// Message types and alert severities Telegram delivers with notifications disabled
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SilentConfig {
+    /// Periodic updates (default: true)
+    #[serde(default = "default_true")]
+    pub periodic: bool,
+    /// Daily and weekly reports (default: false)
+    #[serde(default)]
+    pub reports: bool,
+    /// Startup, shutdown, no-data and cleanup notices (default: false)
+    #[serde(default)]
+    pub notices: bool,
+    /// Alerts with these severities (default: info)
+    #[serde(default = "default_silent_severities")]
+    pub severities: Vec<Severity>,
+}
+
+impl Default for SilentConfig {
+    fn default() -> Self {
+        Self {
+            periodic: true,
+            reports: false,
+            notices: false,
+            severities: default_silent_severities(),
+        }
+    }
+}
+
+impl SilentConfig {
+    pub fn is_silent(&self, severity: Severity) -> bool {
+        self.severities.contains(&severity)
+    }
+}
+
+fn default_silent_severities() -> Vec<Severity> {
+    vec![Severity::Info]
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DiscordConfig {
+    /// Channel webhook URL from the Discord channel's integration settings
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WebhookConfig {
+    /// URL that receives a JSON POST for every notification
+    pub url: String,
+    /// Key for the HMAC-SHA256 signature header (optional, unsigned without it)
+    pub secret: Option<String>,
+    /// Delivery attempts before giving up on a message (default: 3)
+    #[serde(default = "default_webhook_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_webhook_attempts() -> u32 {
+    3
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NtfyConfig {
+    /// ntfy server (default: https://ntfy.sh)
+    #[serde(default = "default_ntfy_server")]
+    pub server: String,
+    pub topic: String,
+    /// Access token for a protected topic (optional)
+    pub token: Option<String>,
+    /// Username and password for a protected topic (optional)
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GotifyConfig {
+    /// Base URL of the Gotify server
+    pub url: String,
+    /// Token of the Gotify application the messages are posted as
+    pub app_token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MatrixConfig {
+    /// Homeserver base URL, e.g. https://matrix.org
+    pub homeserver: String,
+    /// Access token of the account that posts the messages
+    pub access_token: String,
+    /// Room the account has joined, e.g. !abcdef:matrix.org
+    pub room_id: String,
+}
+
+/// A notification channel selectable in `notifications.channels`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Telegram,
+    Discord,
+    Webhook,
+    Ntfy,
+    Gotify,
+    Matrix,
+    Desktop,
+}
+
+impl Channel {
+    /// Name as written in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Telegram => "telegram",
+            Channel::Discord => "discord",
+            Channel::Webhook => "webhook",
+            Channel::Ntfy => "ntfy",
+            Channel::Gotify => "gotify",
+            Channel::Matrix => "matrix",
+            Channel::Desktop => "desktop",
+        }
+    }
+}
+
+fn default_channels() -> Vec<Channel> {
+    vec![Channel::Telegram]
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MonitoringConfig {
     /// Interval in seconds to check for ratio changes
     pub check_interval_secs: u64,
+    /// Spread each cycle's API requests over up to this many seconds instead of a burst
+    pub request_spread_secs: Option<u64>,
+    /// Compute the executable (order book) ratio of pairs with `analysis_volume`
+    /// every this many seconds and alert on its changes
+    pub volume_check_interval_secs: Option<u64>,
     /// Interval in seconds for periodic notifications (default: 3600 = 1 hour)
+    #[serde(default = "default_periodic_notification_secs")]
     pub periodic_notification_secs: u64,
+    /// Cron expression for periodic notifications, e.g. "0 8,20 * * *" (replaces the interval)
+    pub periodic_notification_cron: Option<String>,
+    /// Time (in `timezone`) at which to send a report of each pair's last 24 hours, e.g. "21:00"
+    pub daily_summary_time: Option<NaiveTime>,
+    /// When to send the week-over-week report
+    pub weekly_report: Option<WeeklyReportSchedule>,
     /// Thresholds for ratio change alerts (e.g., [5.0, 10.0, 15.0, 20.0] for 5%, 10%, 15%, 20%)
     pub change_thresholds: Vec<f64>,
     /// Time window in seconds to detect sudden changes (default: 300 = 5 minutes)
     pub change_window_secs: u64,
+    /// What the current ratio is compared against within the change window
+    #[serde(default)]
+    pub baseline: BaselineMode,
+    /// Thresholds for change since the UTC daily open (e.g., [3.0, 5.0]); empty disables
+    #[serde(default)]
+    pub daily_open_thresholds: Vec<f64>,
+    /// Which moves trigger alerts: "both" (default), "up" or "down"
+    #[serde(default)]
+    pub direction: AlertDirection,
+    /// A triggered threshold re-arms once the change falls below (threshold - margin)
+    #[serde(default = "default_rearm_margin_pct")]
+    pub rearm_margin_pct: f64,
+    /// ...and stays there for this many seconds
+    #[serde(default = "default_rearm_after_secs")]
+    pub rearm_after_secs: u64,
+    /// Minimum seconds between two alerts for the same pair and threshold (0 disables)
+    #[serde(default)]
+    pub alert_cooldown_secs: u64,
+    /// Never repeat an identical alert (pair, window, threshold, direction) within
+    /// this many seconds, including across restarts (0 disables)
+    #[serde(default = "default_dedup_window_secs")]
+    pub dedup_window_secs: u64,
+    /// Global cap on alerts sent per rolling hour; the overflow is reported as a count
+    pub max_alerts_per_hour: Option<u32>,
+    /// Send an escalated reminder every this many seconds while a breach persists
+    pub escalation_after_secs: Option<u64>,
+    /// Coalesce alerts raised within this many seconds into a single digest message
+    pub digest_window_secs: Option<u64>,
+    /// Attach a chart of the pair's ratio over the last this many hours to alerts
+    pub alert_chart_hours: Option<u64>,
+    /// Severity assigned to thresholds, by the smallest threshold each tier covers
+    #[serde(default)]
+    pub severity_tiers: Vec<SeverityTier>,
+    /// Send a "monitor started" message when monitoring begins
+    #[serde(default = "default_true")]
+    pub notify_on_startup: bool,
+    /// Send a "monitor stopping" message on graceful shutdown
+    #[serde(default = "default_true")]
+    pub notify_on_shutdown: bool,
+    /// Custom text for the startup announcement
+    pub startup_message: Option<String>,
+    /// Custom text for the shutdown announcement
+    pub shutdown_message: Option<String>,
+    /// Append a summary of the monitored pairs and thresholds to the startup announcement
+    #[serde(default)]
+    pub startup_config_summary: bool,
+    /// Periods during which snapshots are still recorded but alerts are suppressed
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+    /// Warn once a pair has produced no data for this many consecutive checks (0 disables)
+    #[serde(default = "default_stale_after_failures")]
+    pub stale_after_failures: u32,
+    /// On startup, fill a gap since the last stored snapshot from Binance klines
+    #[serde(default = "default_true")]
+    pub backfill_gaps: bool,
+    /// URL pinged after each cycle that got data (e.g. a healthchecks.io check)
+    pub heartbeat_url: Option<String>,
+}
+
+/// Weekday and time (in `timezone`) of the weekly report
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WeeklyReportSchedule {
+    pub day: Weekday,
+    pub time: NaiveTime,
+}
+
+/// A maintenance period: either an explicit `start`..`end` range, or a daily
+/// `from`..`to` time range in `timezone`, optionally limited to some weekdays
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MaintenanceWindow {
+    /// Shown in logs, e.g. "Binance maintenance"
+    pub name: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub from: Option<NaiveTime>,
+    pub to: Option<NaiveTime>,
+    /// Weekdays on which the daily range applies (empty means every day)
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+}
+
+impl MaintenanceWindow {
+    /// Whether `at` falls inside this window, reading daily ranges in `tz`
+    pub fn contains(&self, at: DateTime<Utc>, tz: Tz) -> bool {
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            return start <= at && at < end;
+        }
+
+        let (Some(from), Some(to)) = (self.from, self.to) else {
+            return false;
+        };
+
+        let at = at.with_timezone(&tz);
+        let time = at.time();
+        // A range past midnight (e.g. 23:00-01:00) belongs to the day it started on
+        let (inside, day) = if from <= to {
+            (from <= time && time < to, at.weekday())
+        } else if time >= from {
+            (true, at.weekday())
+        } else {
+            (time < to, at.weekday().pred())
+        };
+
+        inside && (self.days.is_empty() || self.days.contains(&day))
+    }
+
+    /// Name for logs, falling back to the configured range
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        match (self.start, self.end, self.from, self.to) {
+            (Some(start), Some(end), _, _) => format!("{} - {}", start, end),
+            (_, _, Some(from), Some(to)) => format!("{}-{}", from.format("%H:%M"), to.format("%H:%M")),
+            _ => "maintenance window".to_string(),
+        }
+    }
+}
+
+fn default_timezone() -> Tz {
+    Tz::UTC
+}
+
+fn default_periodic_notification_secs() -> u64 {
+    3600
+}
+
+fn default_dedup_window_secs() -> u64 {
+    600
+}
+
+fn default_stale_after_failures() -> u32 {
+    5
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SeverityTier {
+    /// Thresholds at or above this percentage get this tier's severity
+    pub min_threshold: f64,
+    pub severity: Severity,
+    /// Chat that receives alerts of this severity (defaults to the Telegram recipients)
+    pub chat_id: Option<i64>,
+    /// Forum topic of `chat_id` that receives alerts of this severity
+    pub message_thread_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Severity::Info => "ℹ️",
+            Severity::Warning => "⚠️",
+            Severity::Critical => "🚨",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+fn default_rearm_margin_pct() -> f64 {
+    1.0
+}
+
+fn default_rearm_after_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertDirection {
+    #[default]
+    Both,
+    Up,
+    Down,
+}
+
+impl AlertDirection {
+    /// Whether a percentage change moves in an alerting direction
+    pub fn allows(&self, change_pct: f64) -> bool {
+        match self {
+            AlertDirection::Both => true,
+            AlertDirection::Up => change_pct > 0.0,
+            AlertDirection::Down => change_pct < 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BaselineMode {
+    /// Oldest snapshot in the change window
+    #[default]
+    Oldest,
+    /// Exponential moving average of the snapshots in the change window
+    Ema,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -41,11 +698,103 @@ pub struct RatioPair {
     /// Name/identifier for this ratio pair
     pub name: String,
     /// First symbol (e.g., "BTCUSDT")
+    #[serde(default)]
     pub symbol_a: String,
     /// Second symbol (e.g., "ETHUSDT")
+    #[serde(default)]
     pub symbol_b: String,
+    /// Name of another configured pair to use as the first leg instead of `symbol_a`
+    pub pair_a: Option<String>,
+    /// Name of another configured pair to use as the second leg instead of `symbol_b`
+    pub pair_b: Option<String>,
     /// Volume in base currency for slippage analysis (optional)
     pub analysis_volume: Option<f64>,
+    /// Check interval in seconds for this pair (defaults to `monitoring.check_interval_secs`)
+    pub check_interval_secs: Option<u64>,
+    /// Alert direction for this pair (defaults to `monitoring.direction`)
+    pub direction: Option<AlertDirection>,
+    /// Alert cooldown in seconds for this pair (defaults to `monitoring.alert_cooldown_secs`)
+    pub alert_cooldown_secs: Option<u64>,
+    /// Change detection rules for this pair (defaults to the global window and thresholds)
+    pub change_rules: Option<Vec<ChangeRule>>,
+    /// Where this pair's alerts go (defaults to every channel and Telegram recipient)
+    pub route: Option<PairRoute>,
+    /// Group this pair is listed under in the bot's pair selection
+    pub category: Option<String>,
+}
+
+/// Destinations of one pair's alerts, replacing the defaults
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PairRoute {
+    /// Only these of the enabled notification channels (default: all of them)
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+    /// Telegram chat that receives the alerts instead of `telegram.recipients`
+    pub chat_id: Option<i64>,
+    /// Forum topic in `chat_id` when the chat is a supergroup with topics
+    pub message_thread_id: Option<i32>,
+}
+
+/// A change window with the thresholds evaluated over it
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ChangeRule {
+    pub window_secs: u64,
+    pub thresholds: Vec<f64>,
+}
+
+/// One side of a ratio pair: either a market symbol or another configured pair
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Leg<'a> {
+    Symbol(&'a str),
+    Pair(&'a str),
+}
+
+impl Leg<'_> {
+    /// Symbol or pair name, for display
+    pub fn label(&self) -> &str {
+        match self {
+            Leg::Symbol(s) | Leg::Pair(s) => s,
+        }
+    }
+}
+
+impl RatioPair {
+    /// A symbol pair with every per-pair setting left at its default
+    pub fn new(name: &str, symbol_a: &str, symbol_b: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            symbol_a: symbol_a.to_string(),
+            symbol_b: symbol_b.to_string(),
+            pair_a: None,
+            pair_b: None,
+            analysis_volume: None,
+            check_interval_secs: None,
+            direction: None,
+            alert_cooldown_secs: None,
+            change_rules: None,
+            route: None,
+            category: None,
+        }
+    }
+
+    pub fn leg_a(&self) -> Leg<'_> {
+        match &self.pair_a {
+            Some(name) => Leg::Pair(name),
+            None => Leg::Symbol(&self.symbol_a),
+        }
+    }
+
+    pub fn leg_b(&self) -> Leg<'_> {
+        match &self.pair_b {
+            Some(name) => Leg::Pair(name),
+            None => Leg::Symbol(&self.symbol_b),
+        }
+    }
+
+    /// Whether either leg references another pair
+    pub fn is_composite(&self) -> bool {
+        self.pair_a.is_some() || self.pair_b.is_some()
+    }
 }
 
 impl Config {
@@ -60,20 +809,499 @@ impl Config {
     }
 
     pub fn validate(&self) -> Result<()> {
-        if self.telegram.token.is_empty() {
-            anyhow::bail!("Telegram token cannot be empty");
+        if self.notifications.channels.contains(&Channel::Telegram) {
+            self.require_telegram()?;
         }
 
         if self.ratio_pairs.is_empty() {
             anyhow::bail!("At least one ratio pair must be configured");
         }
 
+        if self.database.retention_days.is_some_and(|days| days <= 0) {
+            anyhow::bail!("retention_days must be greater than zero");
+        }
+
+        for (name, days) in self.database.retention.periods() {
+            if days.is_some_and(|days| days <= 0) {
+                anyhow::bail!("database.retention.{} must be greater than zero", name);
+            }
+        }
+
+        if self.database.rollup_after_days.is_some_and(|days| days <= 0) {
+            anyhow::bail!("rollup_after_days must be greater than zero");
+        }
+
+        if self.database.order_book_levels == Some(0) {
+            anyhow::bail!("order_book_levels must be greater than zero");
+        }
+
+        if let Some(archive) = &self.database.archive {
+            if archive.max_age_days <= 0 || archive.max_size_mb == 0 {
+                anyhow::bail!("archive max_age_days and max_size_mb must be greater than zero");
+            }
+            if let Some(name) = archive.pairs.iter().find(|name| self.find_pair(name).is_none()) {
+                anyhow::bail!("Archived pair {} is not configured", name);
+            }
+        }
+
+        if self.notifications.channels.is_empty() {
+            anyhow::bail!("At least one notification channel must be configured");
+        }
+
+        for channel in &self.notifications.channels {
+            let configured = match channel {
+                Channel::Telegram => true,
+                Channel::Discord => self.notifications.discord.is_some(),
+                Channel::Webhook => self.notifications.webhook.is_some(),
+                Channel::Ntfy => self.notifications.ntfy.is_some(),
+                Channel::Gotify => self.notifications.gotify.is_some(),
+                Channel::Matrix => self.notifications.matrix.is_some(),
+                Channel::Desktop => true,
+            };
+            if !configured {
+                anyhow::bail!(
+                    "Notification channel {:?} is enabled but [notifications.{}] is missing",
+                    channel,
+                    channel.name()
+                );
+            }
+        }
+
+        self.periodic_schedule()?;
+        Templates::new(&self.templates)?;
+
+        if self.notifications.webhook.as_ref().is_some_and(|w| w.max_attempts == 0) {
+            anyhow::bail!("webhook max_attempts must be greater than zero");
+        }
+
+        if self.telegram.rate_limit_refill_secs > 0 && self.telegram.rate_limit_burst == 0 {
+            anyhow::bail!("rate_limit_burst must be greater than zero");
+        }
+        if self.telegram.bot_live_refresh_secs == 0 || self.telegram.bot_live_duration_secs == 0 {
+            anyhow::bail!("bot_live_refresh_secs and bot_live_duration_secs must be greater than zero");
+        }
+
+        if self.telegram.escalation_thread_id.is_some() && self.telegram.escalation_chat_id.is_none() {
+            anyhow::bail!("escalation_thread_id requires escalation_chat_id");
+        }
+        if self
+            .monitoring
+            .severity_tiers
+            .iter()
+            .any(|t| t.message_thread_id.is_some() && t.chat_id.is_none())
+        {
+            anyhow::bail!("Severity tier message_thread_id requires its chat_id");
+        }
+
+        for recipient in &self.telegram.recipients {
+            for pair in recipient.pairs.iter().chain(recipient.pair_topics.keys()) {
+                if self.find_pair(pair).is_none() {
+                    anyhow::bail!(
+                        "Telegram recipient {} refers to unknown pair: {}",
+                        recipient.chat_id,
+                        pair
+                    );
+                }
+            }
+        }
+
+        if let Some(mqtt) = &self.mqtt {
+            if mqtt.host.is_empty() || mqtt.client_id.is_empty() || mqtt.topic_prefix.is_empty() {
+                anyhow::bail!("mqtt host, client_id and topic_prefix cannot be empty");
+            }
+            if mqtt.username.is_some() != mqtt.password.is_some() {
+                anyhow::bail!("mqtt username and password must be set together");
+            }
+        }
+
+        if let Some(ntfy) = &self.notifications.ntfy {
+            if ntfy.topic.is_empty() {
+                anyhow::bail!("ntfy topic cannot be empty");
+            }
+            if ntfy.username.is_some() != ntfy.password.is_some() {
+                anyhow::bail!("ntfy username and password must be set together");
+            }
+        }
+
+        if self.monitoring.max_alerts_per_hour == Some(0) {
+            anyhow::bail!("max_alerts_per_hour must be greater than zero");
+        }
+
+        if self.monitoring.check_interval_secs == 0 {
+            anyhow::bail!("check_interval_secs must be greater than zero");
+        }
+
+        if self.monitoring.alert_chart_hours == Some(0) {
+            anyhow::bail!("alert_chart_hours must be greater than zero");
+        }
+
+        if self.monitoring.volume_check_interval_secs == Some(0) {
+            anyhow::bail!("volume_check_interval_secs must be greater than zero");
+        }
+
+        if let Some(spread) = self.monitoring.request_spread_secs {
+            let shortest = self
+                .ratio_pairs
+                .iter()
+                .map(|p| self.check_interval_secs(p))
+                .min()
+                .unwrap_or(self.monitoring.check_interval_secs);
+            if spread >= shortest {
+                anyhow::bail!(
+                    "request_spread_secs must be shorter than the shortest check interval ({}s)",
+                    shortest
+                );
+            }
+        }
+
+        for window in &self.monitoring.maintenance_windows {
+            let valid = match (window.start, window.end, window.from, window.to) {
+                (Some(start), Some(end), None, None) => start < end && window.days.is_empty(),
+                (None, None, Some(from), Some(to)) => from != to,
+                _ => false,
+            };
+            if !valid {
+                anyhow::bail!(
+                    "Maintenance window {} needs either start < end, or from/to (with optional days)",
+                    window.label()
+                );
+            }
+        }
+
+        for pair in &self.ratio_pairs {
+            if pair.name.trim().is_empty() || pair.name.len() > MAX_PAIR_NAME_LENGTH {
+                anyhow::bail!(
+                    "Ratio pair name must be 1 to {} characters: {}",
+                    MAX_PAIR_NAME_LENGTH,
+                    pair.name
+                );
+            }
+
+            for rule in pair.change_rules.iter().flatten() {
+                if rule.window_secs == 0 || rule.thresholds.is_empty() {
+                    anyhow::bail!(
+                        "Change rules need a non-zero window and at least one threshold in ratio pair: {}",
+                        pair.name
+                    );
+                }
+            }
+
+            if pair
+                .category
+                .as_ref()
+                .is_some_and(|c| c.trim().is_empty() || c.len() > 32)
+            {
+                anyhow::bail!("Category must be 1 to 32 characters in ratio pair: {}", pair.name);
+            }
+
+            if let Some(route) = &pair.route {
+                if let Some(channel) = route
+                    .channels
+                    .iter()
+                    .find(|c| !self.notifications.channels.contains(c))
+                {
+                    anyhow::bail!(
+                        "Ratio pair {} routes alerts to {}, which is not in notifications.channels",
+                        pair.name,
+                        channel.name()
+                    );
+                }
+                if route.message_thread_id.is_some() && route.chat_id.is_none() {
+                    anyhow::bail!("Route message_thread_id requires its chat_id in ratio pair: {}", pair.name);
+                }
+            }
+
+            if pair.check_interval_secs == Some(0) {
+                anyhow::bail!(
+                    "check_interval_secs must be greater than zero in ratio pair: {}",
+                    pair.name
+                );
+            }
+
+            for leg in [pair.leg_a(), pair.leg_b()] {
+                match leg {
+                    Leg::Symbol("") => {
+                        anyhow::bail!("Symbols cannot be empty in ratio pair: {}", pair.name);
+                    }
+                    Leg::Pair(name) if self.find_pair(name).is_none() => {
+                        anyhow::bail!(
+                            "Ratio pair {} references unknown pair: {}",
+                            pair.name,
+                            name
+                        );
+                    }
+                    _ => {}
+                }
+            }
+
+            self.dependency_order(&pair.name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that Telegram is configured, for the Telegram channel and the bot
+    pub fn require_telegram(&self) -> Result<()> {
+        if self.telegram.token.is_empty() {
+            anyhow::bail!("Telegram token cannot be empty");
+        }
+        Ok(())
+    }
+
+    /// Check interval for a pair, falling back to the global interval
+    pub fn check_interval_secs(&self, pair: &RatioPair) -> u64 {
+        pair.check_interval_secs.unwrap_or(self.monitoring.check_interval_secs)
+    }
+
+    /// Alert direction for a pair, falling back to the global direction
+    pub fn alert_direction(&self, pair: &RatioPair) -> AlertDirection {
+        pair.direction.unwrap_or(self.monitoring.direction)
+    }
+
+    /// Alert cooldown for a pair, falling back to the global cooldown
+    pub fn alert_cooldown_secs(&self, pair: &RatioPair) -> u64 {
+        pair.alert_cooldown_secs.unwrap_or(self.monitoring.alert_cooldown_secs)
+    }
+
+    /// Severity tier for a threshold: the tier with the highest `min_threshold`
+    /// not above it, or `None` if no tier applies
+    pub fn severity_for(&self, threshold: f64) -> Option<&SeverityTier> {
+        self.monitoring
+            .severity_tiers
+            .iter()
+            .filter(|t| threshold >= t.min_threshold)
+            .max_by(|a, b| a.min_threshold.total_cmp(&b.min_threshold))
+    }
+
+    /// Change detection rules for a pair: the one set through the bot, else the
+    /// pair's own, else the global window and thresholds
+    pub fn change_rules(&self, pair: &RatioPair) -> Vec<ChangeRule> {
+        if let Some(rule) = self.rule_overrides.get(&pair.name) {
+            return vec![rule.clone()];
+        }
+        pair.change_rules.clone().unwrap_or_else(|| {
+            vec![ChangeRule {
+                window_secs: self.monitoring.change_window_secs,
+                thresholds: self.monitoring.change_thresholds.clone(),
+            }]
+        })
+    }
+
+    /// Window of a pair's first change rule, used where one change figure is shown
+    pub fn primary_window_secs(&self, pair: &RatioPair) -> u64 {
+        self.change_rules(pair)
+            .first()
+            .map(|rule| rule.window_secs)
+            .unwrap_or(self.monitoring.change_window_secs)
+    }
+
+    /// Short description of what is being monitored, for announcements
+    pub fn summary(&self) -> String {
+        let t = self.language.texts();
+        let mut lines = vec![fill(
+            t.summary_pairs,
+            &[
+                ("count", &self.ratio_pairs.len()),
+                ("secs", &self.monitoring.check_interval_secs),
+            ],
+        )];
+
+        for pair in &self.ratio_pairs {
+            let rules = self
+                .change_rules(pair)
+                .iter()
+                .map(|rule| {
+                    let thresholds = rule
+                        .thresholds
+                        .iter()
+                        .map(|t| format!("{}%", t))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    fill(t.summary_rule, &[("thresholds", &thresholds), ("secs", &rule.window_secs)])
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            lines.push(format!("- {}: {}", pair.name, rules));
+        }
+
+        if !self.monitoring.daily_open_thresholds.is_empty() {
+            let thresholds = self
+                .monitoring
+                .daily_open_thresholds
+                .iter()
+                .map(|t| format!("{}%", t))
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(fill(t.summary_daily_open, &[("thresholds", &thresholds)]));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Parsed `periodic_notification_cron`, if periodic updates follow a schedule
+    pub fn periodic_schedule(&self) -> Result<Option<Cron>> {
+        self.monitoring
+            .periodic_notification_cron
+            .as_deref()
+            .map(|expr| {
+                Cron::from_str(expr)
+                    .with_context(|| format!("Invalid periodic_notification_cron: {}", expr))
+            })
+            .transpose()
+    }
+
+    /// The maintenance window `at` falls into, if any
+    pub fn maintenance_window(&self, at: DateTime<Utc>) -> Option<&MaintenanceWindow> {
+        self.monitoring
+            .maintenance_windows
+            .iter()
+            .find(|w| w.contains(at, self.timezone))
+    }
+
+    /// Add pairs defined outside the config file, skipping names it already has
+    pub fn add_pairs(&mut self, pairs: impl IntoIterator<Item = RatioPair>) {
+        for pair in pairs {
+            if self.find_pair(&pair.name).is_none() {
+                self.ratio_pairs.push(pair);
+            }
+        }
+    }
+
+    /// Look up a configured ratio pair by name
+    pub fn find_pair(&self, name: &str) -> Option<&RatioPair> {
+        self.ratio_pairs.iter().find(|p| p.name == name)
+    }
+
+    /// Binance symbols a pair's ratio is computed from, including those of
+    /// the pairs it references
+    pub fn pair_symbols(&self, name: &str) -> Result<Vec<String>> {
+        let mut symbols = Vec::new();
+        for pair in self.dependency_order(name)? {
+            for leg in [pair.leg_a(), pair.leg_b()] {
+                if let Leg::Symbol(symbol) = leg
+                    && !symbols.iter().any(|s| s == symbol)
+                {
+                    symbols.push(symbol.to_string());
+                }
+            }
+        }
+        Ok(symbols)
+    }
+
+    /// Return the named pair and every pair it depends on, ordered so that each
+    /// pair comes after the pairs it references
+    pub fn dependency_order(&self, name: &str) -> Result<Vec<&RatioPair>> {
+        let mut order = Vec::new();
+        let mut visiting = Vec::new();
+        self.visit_pair(name, &mut visiting, &mut order)?;
+        Ok(order)
+    }
+
+    /// Settings that differ between this config and `other`, one line per key,
+    /// with tokens, secrets, passwords and webhook URLs redacted
+    pub fn diff(&self, other: &Config) -> Vec<String> {
+        let old = self.flatten();
+        let new = other.flatten();
+
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter_map(|key| {
+                let before = old.get(key);
+                let after = new.get(key);
+                if before == after {
+                    return None;
+                }
+                if key.ends_with("token")
+                    || key.ends_with("webhook_url")
+                    || key.ends_with("secret")
+                    || key.ends_with("password") {
+                    return Some(format!("{}: changed", key));
+                }
+                let show = |v: Option<&String>| v.cloned().unwrap_or_else(|| "(unset)".to_string());
+                Some(format!("{}: {} -> {}", key, show(before), show(after)))
+            })
+            .collect()
+    }
+
+    /// Every setting as a dotted key; ratio pairs are keyed by name so
+    /// reordering them is not reported as a change
+    fn flatten(&self) -> BTreeMap<String, String> {
+        let mut settings = BTreeMap::new();
+        settings.insert("timezone".to_string(), self.timezone.to_string());
+        if let Ok(toml::Value::String(language)) = toml::Value::try_from(self.language) {
+            settings.insert("language".to_string(), language);
+        }
+
+        let sections = [
+            ("telegram", toml::Value::try_from(&self.telegram)),
+            ("notifications", toml::Value::try_from(&self.notifications)),
+            ("monitoring", toml::Value::try_from(&self.monitoring)),
+            ("database", toml::Value::try_from(&self.database)),
+            ("mqtt", toml::Value::try_from(&self.mqtt)),
+            ("templates", toml::Value::try_from(&self.templates)),
+        ];
+        for (name, value) in sections {
+            if let Ok(value) = value {
+                flatten_value(name, &value, &mut settings);
+            }
+        }
+
         for pair in &self.ratio_pairs {
-            if pair.symbol_a.is_empty() || pair.symbol_b.is_empty() {
-                anyhow::bail!("Symbols cannot be empty in ratio pair: {}", pair.name);
+            if let Ok(value) = toml::Value::try_from(pair) {
+                flatten_value(&format!("ratio_pairs.{}", pair.name), &value, &mut settings);
             }
         }
 
+        settings
+    }
+
+    fn visit_pair<'a>(
+        &'a self,
+        name: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<&'a RatioPair>,
+    ) -> Result<()> {
+        if order.iter().any(|p| p.name == name) {
+            return Ok(());
+        }
+
+        if visiting.iter().any(|n| n == name) {
+            anyhow::bail!(
+                "Circular pair reference: {} -> {}",
+                visiting.join(" -> "),
+                name
+            );
+        }
+
+        let pair = self
+            .find_pair(name)
+            .with_context(|| format!("Unknown ratio pair: {}", name))?;
+
+        visiting.push(name.to_string());
+        for leg in [pair.leg_a(), pair.leg_b()] {
+            if let Leg::Pair(dep) = leg {
+                self.visit_pair(dep, visiting, order)?;
+            }
+        }
+        visiting.pop();
+
+        order.push(pair);
         Ok(())
     }
 }
+
+fn flatten_value(prefix: &str, value: &toml::Value, settings: &mut BTreeMap<String, String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                flatten_value(&format!("{}.{}", prefix, key), value, settings);
+            }
+        }
+        other => {
+            settings.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
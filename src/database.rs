@@ -1,11 +1,194 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use sqlx::{Row, sqlite::SqlitePool};
+use chrono::{DateTime, DurationRound, Utc};
+use sqlx::any::AnyPoolOptions;
+use sqlx::{AnyPool, Executor, Row};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
 
+use crate::binance::OrderBookInfo;
+use crate::config::{DatabaseConfig, RetentionConfig};
+use crate::i18n::{Texts, fill};
+
+#[derive(Clone)]
 pub struct Database {
-    pool: SqlitePool,
+    pool: AnyPool,
+    backend: Backend,
+}
+
+/// The database server behind the pool. Queries are written to run on both;
+/// only the schema differs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+/// Raw snapshots together with the hourly aggregates condensed from older
+/// ones, which read as a snapshot taken at the start of their hour. Queries
+/// of a single pair use `pair_snapshots` instead
+const ALL_SNAPSHOTS: &str = r#"(
+    SELECT id, pair_name, symbol_a, symbol_b, price_a, price_b, ratio, timestamp
+    FROM ratio_snapshots
+    UNION ALL
+    SELECT 0, pair_name, symbol_a, symbol_b, price_a, price_b, ratio, hour_start
+    FROM ratio_snapshots_hourly
+) AS snapshots"#;
+
+/// A pair's snapshots as in `ALL_SNAPSHOTS`, bound as `$1`, with `condition`
+/// applied to each side and `{time}` standing for its time column. Unlike a
+/// subquery over both tables, the database can then read each side in index
+/// order and merge them, so an `ORDER BY timestamp ... LIMIT` stops early
+/// instead of sorting the pair's whole history
+fn pair_snapshots(condition: &str) -> String {
+    format!(
+        r#"
+        SELECT id, pair_name, symbol_a, symbol_b, price_a, price_b, ratio, timestamp
+        FROM ratio_snapshots
+        WHERE pair_name = $1 {}
+        UNION ALL
+        SELECT 0, pair_name, symbol_a, symbol_b, price_a, price_b, ratio, hour_start
+        FROM ratio_snapshots_hourly
+        WHERE pair_name = $1 {}
+        "#,
+        condition.replace("{time}", "timestamp"),
+        condition.replace("{time}", "hour_start")
+    )
+}
+
+/// Newest snapshots of a pair; `$2` is the limit
+fn ratio_history_sql() -> String {
+    format!("{} ORDER BY timestamp DESC LIMIT $2", pair_snapshots(""))
+}
+
+/// Oldest snapshot of a pair at or after `$2`
+fn first_snapshot_since_sql() -> String {
+    format!("{} ORDER BY timestamp ASC LIMIT 1", pair_snapshots("AND {time} >= $2"))
+}
+
+/// Snapshots of a pair from `$2` to `$3`, newest first
+fn ratio_history_range_sql() -> String {
+    format!(
+        "{} ORDER BY timestamp DESC",
+        pair_snapshots("AND {time} >= $2 AND {time} <= $3")
+    )
+}
+
+/// Statistics of a pair's snapshots from `$2` to `$3`. Hourly aggregates count
+/// with the number of snapshots they condense, each at the hour's average, so
+/// their spread within the hour is lost
+fn pair_statistics_range_sql() -> String {
+    let range = pair_snapshots("AND {time} >= $2 AND {time} <= $3");
+    format!(
+        r#"
+        WITH range_samples AS (
+            SELECT ratio, ratio AS min_ratio, ratio AS max_ratio, 1 AS samples
+            FROM ratio_snapshots
+            WHERE pair_name = $1 AND timestamp >= $2 AND timestamp <= $3
+            UNION ALL
+            SELECT ratio, min_ratio, max_ratio, samples
+            FROM ratio_snapshots_hourly
+            WHERE pair_name = $1 AND hour_start >= $2 AND hour_start <= $3
+        ),
+        mean AS (
+            SELECT SUM(ratio * samples) / CAST(SUM(samples) AS DOUBLE PRECISION) as avg_ratio
+            FROM range_samples
+        )
+        SELECT
+            CAST(SUM(samples) AS BIGINT) as count,
+            MIN(min_ratio) as min_ratio,
+            MAX(max_ratio) as max_ratio,
+            MAX(mean.avg_ratio) as avg_ratio,
+            SUM((ratio - mean.avg_ratio) * (ratio - mean.avg_ratio) * samples) as squared_deviations,
+            (SELECT ratio FROM ({range} ORDER BY timestamp ASC LIMIT 1) AS first) as first_ratio,
+            (SELECT ratio FROM ({range} ORDER BY timestamp DESC LIMIT 1) AS last) as last_ratio
+        FROM range_samples CROSS JOIN mean
+        "#
+    )
 }
 
+/// Newest alerts of a pair; `$2` is the limit
+const ALERT_HISTORY_SQL: &str = r#"
+    SELECT id, pair_name, ratio, change_percentage, threshold, timestamp,
+           direction, price_a, price_b, window_secs, severity, metric
+    FROM alerts
+    WHERE pair_name = $1
+    ORDER BY timestamp DESC
+    LIMIT $2
+"#;
+
+/// Newest alerts of all pairs; `$1` is the limit
+const ALL_ALERTS_SQL: &str = r#"
+    SELECT id, pair_name, ratio, change_percentage, threshold, timestamp,
+           direction, price_a, price_b, window_secs, severity, metric
+    FROM alerts
+    ORDER BY timestamp DESC
+    LIMIT $1
+"#;
+
+/// Tables holding a pair's history under its name in `pair_name`
+const PAIR_HISTORY_TABLES: [&str; 7] = [
+    "ratio_snapshots",
+    "ratio_snapshots_hourly",
+    "alerts",
+    "recoveries",
+    "volume_ratios",
+    "acknowledgements",
+    "checks",
+];
+
+/// Append-only tables and the column their rows are timed by, for the
+/// growth estimate of `db info`
+const GROWTH_TABLES: [(&str, &str); 9] = [
+    ("ratio_snapshots", "timestamp"),
+    ("alerts", "timestamp"),
+    ("volume_ratios", "timestamp"),
+    ("checks", "timestamp"),
+    ("order_book_snapshots", "timestamp"),
+    ("recoveries", "timestamp"),
+    ("api_responses", "timestamp"),
+    ("acknowledgements", "acknowledged_at"),
+    ("bot_log", "timestamp"),
+];
+
+/// Days of recent rows the growth estimate of `db info` averages over
+const GROWTH_DAYS: i64 = 7;
+
+/// Tables holding a pair's monitor and bot state under its name in `pair_name`,
+/// with the other columns of their primary key
+const PAIR_STATE_TABLES: [(&str, &[&str]); 6] = [
+    ("triggered_thresholds", &["metric", "threshold"]),
+    ("paused_pairs", &[]),
+    ("snoozed_pairs", &[]),
+    ("subscriptions", &["chat_id"]),
+    ("favorites", &["user_id"]),
+    ("rule_overrides", &[]),
+];
+
+/// Combines an hourly row inserted into `ratio_snapshots_hourly` with the
+/// one already there for its pair and hour, weighted by their samples
+const MERGE_HOURLY: &str = r#"
+    ON CONFLICT(pair_name, hour_start) DO UPDATE SET
+        price_a = (ratio_snapshots_hourly.price_a * ratio_snapshots_hourly.samples
+                   + excluded.price_a * excluded.samples)
+                  / (ratio_snapshots_hourly.samples + excluded.samples),
+        price_b = (ratio_snapshots_hourly.price_b * ratio_snapshots_hourly.samples
+                   + excluded.price_b * excluded.samples)
+                  / (ratio_snapshots_hourly.samples + excluded.samples),
+        ratio = (ratio_snapshots_hourly.ratio * ratio_snapshots_hourly.samples
+                 + excluded.ratio * excluded.samples)
+                / (ratio_snapshots_hourly.samples + excluded.samples),
+        min_ratio = CASE WHEN excluded.min_ratio < ratio_snapshots_hourly.min_ratio
+                        THEN excluded.min_ratio ELSE ratio_snapshots_hourly.min_ratio END,
+        max_ratio = CASE WHEN excluded.max_ratio > ratio_snapshots_hourly.max_ratio
+                        THEN excluded.max_ratio ELSE ratio_snapshots_hourly.max_ratio END,
+        samples = ratio_snapshots_hourly.samples + excluded.samples
+"#;
+
+/// Periods `stats --volume` splits its window into
+const VOLUME_STATS_PERIODS: i64 = 8;
+
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct RatioRecord {
     pub id: i64,
@@ -18,6 +201,7 @@ pub struct RatioRecord {
     pub timestamp: DateTime<Utc>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct AlertRecord {
     pub id: i64,
@@ -26,8 +210,81 @@ pub struct AlertRecord {
     pub change_percentage: f64,
     pub threshold: f64,
     pub timestamp: DateTime<Utc>,
+    /// "up" or "down"; `None` for alerts recorded before it was stored
+    pub direction: Option<String>,
+    pub price_a: Option<f64>,
+    pub price_b: Option<f64>,
+    /// Lookback window, `None` for daily open alerts and older rows
+    pub window_secs: Option<i64>,
+    pub severity: Option<String>,
+    /// Check that raised it, see `insert_alert`; `None` for daily open alerts and older rows
+    pub metric: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TriggeredThresholdRecord {
+    pub pair_name: String,
+    pub threshold: f64,
+    pub triggered_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaseRecord {
+    pub owner: String,
+    pub heartbeat_at: DateTime<Utc>,
+}
+
+/// A notification waiting for its channel to become reachable again
+#[derive(Debug, Clone)]
+pub struct QueuedNotificationRecord {
+    pub id: i64,
+    /// The serialized notification
+    pub payload: String,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// A ratio pair added through the bot rather than the config file
+#[derive(Debug, Clone)]
+pub struct BotPairRecord {
+    pub name: String,
+    pub symbol_a: String,
+    pub symbol_b: String,
+}
+
+/// A pair's change rule set through the bot
+#[derive(Debug, Clone)]
+pub struct RuleOverrideRecord {
+    pub pair_name: String,
+    pub window_secs: u64,
+    pub thresholds: Vec<f64>,
+}
+
+/// A command or button press handled by the bot
+#[derive(Debug, Clone)]
+pub struct BotLogRecord {
+    pub timestamp: DateTime<Utc>,
+    pub user_id: i64,
+    pub user_name: String,
+    pub chat_id: i64,
+    /// The command text or callback data
+    pub action: String,
+    /// "ok", or the error the handler failed with
+    pub result: String,
+}
+
+/// A raw Binance response kept by the response archive
+#[derive(Debug, Clone)]
+pub struct ApiResponseRecord {
+    pub symbol: String,
+    /// "ticker" or "depth"
+    pub endpoint: String,
+    /// HTTP status code
+    pub status: i64,
+    pub body: String,
+    pub timestamp: DateTime<Utc>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct VolumeRatioRecord {
     pub id: i64,
@@ -44,106 +301,186 @@ pub struct VolumeRatioRecord {
 }
 
 impl Database {
-    /// Create a new database connection
-    pub async fn new(database_url: &str) -> Result<Self> {
-        let pool = SqlitePool::connect(database_url)
+    /// Create a new database connection, to SQLite (`sqlite:` URLs) or,
+    /// when built with the `postgres` feature, PostgreSQL (`postgres://` URLs).
+    /// SQLite databases with an encryption key need the `sqlcipher` feature
+    pub async fn new(config: &DatabaseConfig) -> Result<Self> {
+        let database_url = config.url();
+        let backend = if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+            if !cfg!(feature = "postgres") {
+                anyhow::bail!("PostgreSQL databases need a build with the `postgres` feature");
+            }
+            Backend::Postgres
+        } else {
+            Backend::Sqlite
+        };
+
+        let key = config.encryption_key();
+        let mut options = AnyPoolOptions::new();
+        if let Some(key) = &key {
+            if backend == Backend::Postgres {
+                anyhow::bail!("encryption_key only applies to SQLite databases");
+            }
+            if !cfg!(feature = "sqlcipher") {
+                anyhow::bail!("Encrypted databases need a build with the `sqlcipher` feature");
+            }
+            // The key has to be the first statement to touch the file
+            let pragma = format!("PRAGMA key = '{}'", key.replace('\'', "''"));
+            options = options.after_connect(move |conn, _| {
+                let pragma = pragma.clone();
+                Box::pin(async move {
+                    conn.execute(pragma.as_str()).await?;
+                    Ok(())
+                })
+            });
+        }
+
+        sqlx::any::install_default_drivers();
+        let pool = options
+            .connect(&database_url)
             .await
             .context("Failed to connect to database")?;
 
-        let db = Self { pool };
+        // A wrong key only shows once a page is read
+        if key.is_some() {
+            sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+                .fetch_one(&pool)
+                .await
+                .context("Failed to decrypt the database, check the encryption key")?;
+        }
+
+        let db = Self { pool, backend };
         db.init_schema().await?;
 
         Ok(db)
     }
 
-    /// Initialize database schema
+    /// Close the connection pool, waiting for in-flight queries to finish
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// Bring the schema up to date by applying pending migrations from
+    /// `migrations/`, recorded with their versions in `_sqlx_migrations`
     async fn init_schema(&self) -> Result<()> {
-        // Create ratio_snapshots table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS ratio_snapshots (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                pair_name TEXT NOT NULL,
-                symbol_a TEXT NOT NULL,
-                symbol_b TEXT NOT NULL,
-                price_a REAL NOT NULL,
-                price_b REAL NOT NULL,
-                ratio REAL NOT NULL,
-                timestamp TEXT NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create ratio_snapshots table")?;
+        let migrator = match self.backend {
+            Backend::Sqlite => sqlx::migrate!("migrations/sqlite"),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres => sqlx::migrate!("migrations/postgres"),
+            #[cfg(not(feature = "postgres"))]
+            Backend::Postgres => unreachable!("PostgreSQL needs the postgres feature"),
+        };
+        // A binary rolled back past an upgrade doesn't know the newer tables
+        let latest = migrator.iter().map(|migration| migration.version).max().unwrap_or(0);
+        match self.schema_version().await? {
+            Some(version) if version > latest => anyhow::bail!(
+                "The database schema is at version {}, newer than version {} of this build. \
+                 Run the ratio-noti version that upgraded it, or restore a backup from before the upgrade",
+                version,
+                latest
+            ),
+            Some(version) if version < latest => {
+                log::info!("Upgrading database schema from version {} to {}", version, latest);
+            }
+            _ => {}
+        }
 
-        // Create index on pair_name and timestamp
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_ratio_snapshots_pair_timestamp
-            ON ratio_snapshots(pair_name, timestamp DESC)
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create index")?;
+        migrator
+            .run(&self.pool)
+            .await
+            .context("Failed to apply database migrations")?;
 
-        // Create alerts table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS alerts (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                pair_name TEXT NOT NULL,
-                ratio REAL NOT NULL,
-                change_percentage REAL NOT NULL,
-                threshold REAL NOT NULL,
-                timestamp TEXT NOT NULL
-            )
-            "#,
+        // Databases created before migrations may predate these alerts columns,
+        // which the initial migration's CREATE TABLE IF NOT EXISTS leaves alone
+        self.add_missing_columns(
+            "alerts",
+            &[
+                ("direction", "TEXT"),
+                ("price_a", "REAL"),
+                ("price_b", "REAL"),
+                ("window_secs", "INTEGER"),
+                ("severity", "TEXT"),
+            ],
         )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create alerts table")?;
+        .await?;
 
-        // Create index on alerts
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_alerts_pair_timestamp
-            ON alerts(pair_name, timestamp DESC)
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create alerts index")?;
+        log::info!("Database schema at version {}", self.schema_version().await?.unwrap_or(0));
 
-        // Create volume_ratios table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS volume_ratios (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                pair_name TEXT NOT NULL,
-                symbol_a TEXT NOT NULL,
-                symbol_b TEXT NOT NULL,
-                volume REAL NOT NULL,
-                effective_price_a REAL NOT NULL,
-                effective_price_b REAL NOT NULL,
-                ratio REAL NOT NULL,
-                slippage_a REAL NOT NULL,
-                slippage_b REAL NOT NULL,
-                timestamp TEXT NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create volume_ratios table")?;
+        Ok(())
+    }
+
+    /// Latest migration applied to the database, `None` before the first
+    pub async fn schema_version(&self) -> Result<Option<i64>> {
+        let query = match self.backend {
+            Backend::Sqlite => "SELECT COUNT(*) AS count FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+            Backend::Postgres => {
+                "SELECT COUNT(*) AS count FROM pg_tables
+                 WHERE schemaname = current_schema() AND tablename = '_sqlx_migrations'"
+            }
+        };
+        let row = sqlx::query(query)
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to look up the migrations table")?;
+        if row.get::<i64, _>("count") == 0 {
+            return Ok(None);
+        }
+
+        let row = sqlx::query("SELECT MAX(version) AS version FROM _sqlx_migrations WHERE success = $1")
+            .bind(true)
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to read schema version")?;
+
+        Ok(row.get::<Option<i64>, _>("version"))
+    }
 
-        log::info!("Database schema initialized");
+    /// Migrations write column types for SQLite; PostgreSQL needs 64-bit
+    /// types where SQLite's are 64-bit anyway
+    fn schema(&self, sql: &str) -> String {
+        match self.backend {
+            Backend::Sqlite => sql.to_string(),
+            Backend::Postgres => sql
+                .replace("INTEGER", "BIGINT")
+                .replace("REAL", "DOUBLE PRECISION"),
+        }
+    }
+
+    /// Add columns missing from a table created by an older version
+    async fn add_missing_columns(&self, table: &str, columns: &[(&str, &str)]) -> Result<()> {
+        let query = match self.backend {
+            Backend::Sqlite => format!("PRAGMA table_info({})", table),
+            Backend::Postgres => format!(
+                "SELECT column_name::text AS name FROM information_schema.columns \
+                 WHERE table_schema = current_schema() AND table_name = '{}'",
+                table
+            ),
+        };
+        let existing: HashSet<String> = sqlx::query(&query)
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| format!("Failed to read {} columns", table))?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect();
+
+        for (name, kind) in columns {
+            if existing.contains(*name) {
+                continue;
+            }
+
+            sqlx::query(&self.schema(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, name, kind)))
+                .execute(&self.pool)
+                .await
+                .with_context(|| format!("Failed to add {}.{} column", table, name))?;
+            log::info!("Added {}.{} column", table, name);
+        }
 
         Ok(())
     }
 
     /// Insert a ratio snapshot
+    #[allow(clippy::too_many_arguments)]
     pub async fn insert_ratio_snapshot(
         &self,
         pair_name: &str,
@@ -154,10 +491,11 @@ impl Database {
         ratio: f64,
         timestamp: DateTime<Utc>,
     ) -> Result<i64> {
-        let result = sqlx::query(
+        let row = sqlx::query(
             r#"
             INSERT INTO ratio_snapshots (pair_name, symbol_a, symbol_b, price_a, price_b, ratio, timestamp)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
             "#,
         )
         .bind(pair_name)
@@ -167,26 +505,77 @@ impl Database {
         .bind(price_b)
         .bind(ratio)
         .bind(timestamp.to_rfc3339())
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await
         .context("Failed to insert ratio snapshot")?;
 
-        Ok(result.last_insert_rowid())
+        Ok(row.get("id"))
+    }
+
+    /// Store imported snapshots in one transaction, skipping any whose
+    /// timestamp is already stored for its pair or whose hour was condensed.
+    /// Returns how many were stored
+    pub async fn import_ratio_snapshots(&self, records: &[RatioRecord]) -> Result<u64> {
+        let mut tx = self.pool.begin().await.context("Failed to start import")?;
+
+        let mut imported = 0;
+        for record in records {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO ratio_snapshots (pair_name, symbol_a, symbol_b, price_a, price_b, ratio, timestamp)
+                SELECT $1, $2, $3, $4, $5, $6, $7
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM ratio_snapshots WHERE pair_name = $1 AND timestamp = $7
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM ratio_snapshots_hourly
+                    WHERE pair_name = $1 AND hour_start = SUBSTR($7, 1, 13) || ':00:00+00:00'
+                )
+                "#,
+            )
+            .bind(&record.pair_name)
+            .bind(&record.symbol_a)
+            .bind(&record.symbol_b)
+            .bind(record.price_a)
+            .bind(record.price_b)
+            .bind(record.ratio)
+            .bind(record.timestamp.to_rfc3339())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to import ratio snapshot")?;
+            imported += result.rows_affected();
+        }
+
+        tx.commit().await.context("Failed to commit import")?;
+
+        Ok(imported)
     }
 
-    /// Insert an alert record
+    /// Insert an alert record; `window_secs` and `metric` are `None` for daily
+    /// open alerts. `metric` tells mid-ratio change rules (`window:<secs>`)
+    /// from executable-ratio ones (`volume_window:<secs>`).
+    #[allow(clippy::too_many_arguments)]
     pub async fn insert_alert(
         &self,
         pair_name: &str,
         ratio: f64,
         change_percentage: f64,
         threshold: f64,
+        price_a: f64,
+        price_b: f64,
+        window_secs: Option<u64>,
+        severity: Option<&str>,
+        metric: Option<&str>,
         timestamp: DateTime<Utc>,
     ) -> Result<i64> {
-        let result = sqlx::query(
+        let direction = if change_percentage >= 0.0 { "up" } else { "down" };
+
+        let row = sqlx::query(
             r#"
-            INSERT INTO alerts (pair_name, ratio, change_percentage, threshold, timestamp)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO alerts (pair_name, ratio, change_percentage, threshold, timestamp,
+                                direction, price_a, price_b, window_secs, severity, metric)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id
             "#,
         )
         .bind(pair_name)
@@ -194,14 +583,51 @@ impl Database {
         .bind(change_percentage)
         .bind(threshold)
         .bind(timestamp.to_rfc3339())
-        .execute(&self.pool)
+        .bind(direction)
+        .bind(price_a)
+        .bind(price_b)
+        .bind(window_secs.map(|secs| secs as i64))
+        .bind(severity)
+        .bind(metric)
+        .fetch_one(&self.pool)
         .await
         .context("Failed to insert alert")?;
 
-        Ok(result.last_insert_rowid())
+        Ok(row.get("id"))
+    }
+
+    /// Record that a pair's excursion past its thresholds ended
+    pub async fn insert_recovery(
+        &self,
+        pair_name: &str,
+        window_secs: u64,
+        change_percentage: f64,
+        started_at: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO recoveries (pair_name, window_secs, change_percentage, started_at,
+                                    duration_secs, timestamp)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+        )
+        .bind(pair_name)
+        .bind(window_secs as i64)
+        .bind(change_percentage)
+        .bind(started_at.to_rfc3339())
+        .bind((timestamp - started_at).num_seconds().max(0))
+        .bind(timestamp.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to insert recovery")?;
+
+        Ok(row.get("id"))
     }
 
     /// Insert a volume-based ratio record
+    #[allow(clippy::too_many_arguments)]
     pub async fn insert_volume_ratio(
         &self,
         pair_name: &str,
@@ -215,12 +641,13 @@ impl Database {
         slippage_b: f64,
         timestamp: DateTime<Utc>,
     ) -> Result<i64> {
-        let result = sqlx::query(
+        let row = sqlx::query(
             r#"
             INSERT INTO volume_ratios (pair_name, symbol_a, symbol_b, volume,
                                       effective_price_a, effective_price_b, ratio,
                                       slippage_a, slippage_b, timestamp)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id
             "#,
         )
         .bind(pair_name)
@@ -233,235 +660,2200 @@ impl Database {
         .bind(slippage_a)
         .bind(slippage_b)
         .bind(timestamp.to_rfc3339())
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await
         .context("Failed to insert volume ratio")?;
 
-        Ok(result.last_insert_rowid())
+        Ok(row.get("id"))
     }
 
-    /// Get ratio history for a specific pair
-    pub async fn get_ratio_history(&self, pair_name: &str, limit: i64) -> Result<Vec<RatioRecord>> {
-        let rows = sqlx::query(
+    /// Store the top `levels` of each side of an order book, gzipped if `compress`
+    pub async fn insert_order_book(
+        &self,
+        order_book: &OrderBookInfo,
+        levels: usize,
+        compress: bool,
+        timestamp: DateTime<Utc>,
+    ) -> Result<i64> {
+        let json = serde_json::to_vec(&serde_json::json!({
+            "bids": &order_book.bids[..levels.min(order_book.bids.len())],
+            "asks": &order_book.asks[..levels.min(order_book.asks.len())],
+        }))
+        .context("Failed to serialize order book")?;
+
+        let (encoding, data) = if compress {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&json).context("Failed to compress order book")?;
+            ("json+gzip", encoder.finish().context("Failed to compress order book")?)
+        } else {
+            ("json", json)
+        };
+
+        let row = sqlx::query(
             r#"
-            SELECT id, pair_name, symbol_a, symbol_b, price_a, price_b, ratio, timestamp
-            FROM ratio_snapshots
-            WHERE pair_name = ?
-            ORDER BY timestamp DESC
-            LIMIT ?
+            INSERT INTO order_book_snapshots (symbol, best_bid, best_ask, encoding, data, timestamp)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
             "#,
         )
-        .bind(pair_name)
-        .bind(limit)
-        .fetch_all(&self.pool)
+        .bind(&order_book.symbol)
+        .bind(order_book.best_bid)
+        .bind(order_book.best_ask)
+        .bind(encoding)
+        .bind(data)
+        .bind(timestamp.to_rfc3339())
+        .fetch_one(&self.pool)
         .await
-        .context("Failed to fetch ratio history")?;
+        .context("Failed to insert order book snapshot")?;
 
-        let mut records = Vec::new();
-        for row in rows {
-            let timestamp_str: String = row.get("timestamp");
-            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
-                .context("Failed to parse timestamp")?
-                .with_timezone(&Utc);
+        Ok(row.get("id"))
+    }
 
-            records.push(RatioRecord {
-                id: row.get("id"),
-                pair_name: row.get("pair_name"),
-                symbol_a: row.get("symbol_a"),
-                symbol_b: row.get("symbol_b"),
-                price_a: row.get("price_a"),
-                price_b: row.get("price_b"),
-                ratio: row.get("ratio"),
-                timestamp,
-            });
-        }
+    /// Archive a raw Binance response, gzipped
+    pub async fn insert_api_response(
+        &self,
+        symbol: &str,
+        endpoint: &str,
+        status: u16,
+        body: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.as_bytes()).context("Failed to compress response")?;
+        let data = encoder.finish().context("Failed to compress response")?;
 
-        Ok(records)
+        sqlx::query(
+            r#"
+            INSERT INTO api_responses (symbol, endpoint, status, body, size, timestamp)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(symbol)
+        .bind(endpoint)
+        .bind(status as i64)
+        .bind(&data)
+        .bind(data.len() as i64)
+        .bind(timestamp.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to archive response")?;
+
+        Ok(())
     }
 
-    /// Get ratio history within a time range
-    pub async fn get_ratio_history_range(
-        &self,
-        pair_name: &str,
-        start: DateTime<Utc>,
-        end: DateTime<Utc>,
-    ) -> Result<Vec<RatioRecord>> {
-        let rows = sqlx::query(
+    /// Drop archived responses older than `days`, then the oldest ones until
+    /// the rest fit in `max_bytes`. Returns how many were dropped
+    pub async fn prune_api_responses(&self, days: i64, max_bytes: u64) -> Result<u64> {
+        let cutoff = Utc::now() - chrono::Duration::days(days);
+        let expired = sqlx::query("DELETE FROM api_responses WHERE timestamp < $1")
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete expired responses")?
+            .rows_affected();
+
+        let oversize = sqlx::query(
             r#"
-            SELECT id, pair_name, symbol_a, symbol_b, price_a, price_b, ratio, timestamp
-            FROM ratio_snapshots
-            WHERE pair_name = ? AND timestamp >= ? AND timestamp <= ?
-            ORDER BY timestamp DESC
+            DELETE FROM api_responses WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, SUM(size) OVER (ORDER BY id DESC) AS newer_size
+                    FROM api_responses
+                ) AS sized
+                WHERE newer_size > $1
+            )
             "#,
         )
-        .bind(pair_name)
-        .bind(start.to_rfc3339())
-        .bind(end.to_rfc3339())
-        .fetch_all(&self.pool)
+        .bind(max_bytes as i64)
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch ratio history range")?;
+        .context("Failed to delete responses over the size limit")?
+        .rows_affected();
+
+        Ok(expired + oversize)
+    }
 
+    /// Archived responses of some symbols at or before `at`, newest first
+    pub async fn get_api_responses(
+        &self,
+        symbols: &[String],
+        at: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ApiResponseRecord>> {
         let mut records = Vec::new();
-        for row in rows {
-            let timestamp_str: String = row.get("timestamp");
-            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
-                .context("Failed to parse timestamp")?
-                .with_timezone(&Utc);
+        for symbol in symbols {
+            let rows = sqlx::query(
+                r#"
+                SELECT symbol, endpoint, status, body, timestamp
+                FROM api_responses
+                WHERE symbol = $1 AND timestamp <= $2
+                ORDER BY timestamp DESC
+                LIMIT $3
+                "#,
+            )
+            .bind(symbol)
+            .bind(at.to_rfc3339())
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch archived responses")?;
 
-            records.push(RatioRecord {
-                id: row.get("id"),
-                pair_name: row.get("pair_name"),
-                symbol_a: row.get("symbol_a"),
-                symbol_b: row.get("symbol_b"),
-                price_a: row.get("price_a"),
-                price_b: row.get("price_b"),
-                ratio: row.get("ratio"),
-                timestamp,
-            });
+            for row in rows {
+                let timestamp_str: String = row.get("timestamp");
+                let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                    .context("Failed to parse timestamp")?
+                    .with_timezone(&Utc);
+
+                let data: Vec<u8> = row.get("body");
+                let mut body = String::new();
+                GzDecoder::new(&data[..])
+                    .read_to_string(&mut body)
+                    .context("Failed to decompress archived response")?;
+
+                records.push(ApiResponseRecord {
+                    symbol: row.get("symbol"),
+                    endpoint: row.get("endpoint"),
+                    status: row.get("status"),
+                    body,
+                    timestamp,
+                });
+            }
         }
 
+        records.sort_by_key(|record| std::cmp::Reverse(record.timestamp));
+        records.truncate(limit.max(0) as usize);
+
         Ok(records)
     }
 
-    /// Get alert history for a specific pair
-    pub async fn get_alert_history(&self, pair_name: &str, limit: i64) -> Result<Vec<AlertRecord>> {
-        let rows = sqlx::query(
+    /// Record that a threshold has been triggered for a pair
+    pub async fn save_triggered_threshold(
+        &self,
+        pair_name: &str,
+        metric: &str,
+        threshold: f64,
+        triggered_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
             r#"
-            SELECT id, pair_name, ratio, change_percentage, threshold, timestamp
-            FROM alerts
-            WHERE pair_name = ?
-            ORDER BY timestamp DESC
-            LIMIT ?
+            INSERT INTO triggered_thresholds (pair_name, metric, threshold, triggered_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT(pair_name, metric, threshold) DO UPDATE SET
+                triggered_at = excluded.triggered_at
             "#,
         )
         .bind(pair_name)
-        .bind(limit)
-        .fetch_all(&self.pool)
+        .bind(metric)
+        .bind(threshold)
+        .bind(triggered_at.to_rfc3339())
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch alert history")?;
+        .context("Failed to save triggered threshold")?;
 
-        let mut records = Vec::new();
-        for row in rows {
+        Ok(())
+    }
+
+    /// Remove a triggered threshold once it has been re-armed
+    pub async fn delete_triggered_threshold(
+        &self,
+        pair_name: &str,
+        metric: &str,
+        threshold: f64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM triggered_thresholds
+            WHERE pair_name = $1 AND metric = $2 AND threshold = $3
+            "#,
+        )
+        .bind(pair_name)
+        .bind(metric)
+        .bind(threshold)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete triggered threshold")?;
+
+        Ok(())
+    }
+
+    /// Get triggered thresholds for a metric, triggered at or after `since`
+    pub async fn get_triggered_thresholds(
+        &self,
+        metric: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TriggeredThresholdRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name, threshold, triggered_at
+            FROM triggered_thresholds
+            WHERE metric = $1 AND triggered_at >= $2
+            "#,
+        )
+        .bind(metric)
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch triggered thresholds")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("triggered_at");
+            let triggered_at = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(TriggeredThresholdRecord {
+                pair_name: row.get("pair_name"),
+                threshold: row.get("threshold"),
+                triggered_at,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Record a sent alert's fingerprint, dropping fingerprints older than `prune_before`
+    pub async fn save_alert_fingerprint(
+        &self,
+        fingerprint: &str,
+        sent_at: DateTime<Utc>,
+        prune_before: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sent_alerts (fingerprint, sent_at)
+            VALUES ($1, $2)
+            ON CONFLICT(fingerprint) DO UPDATE SET
+                sent_at = excluded.sent_at
+            "#,
+        )
+        .bind(fingerprint)
+        .bind(sent_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save alert fingerprint")?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM sent_alerts WHERE sent_at < $1
+            "#,
+        )
+        .bind(prune_before.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to prune alert fingerprints")?;
+
+        Ok(())
+    }
+
+    /// Get fingerprints of alerts sent at or after `since`, with their send times
+    pub async fn get_alert_fingerprints(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<HashMap<String, DateTime<Utc>>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT fingerprint, sent_at FROM sent_alerts WHERE sent_at >= $1
+            "#,
+        )
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch alert fingerprints")?;
+
+        let mut fingerprints = HashMap::new();
+        for row in rows {
+            let timestamp_str: String = row.get("sent_at");
+            let sent_at = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+            fingerprints.insert(row.get("fingerprint"), sent_at);
+        }
+
+        Ok(fingerprints)
+    }
+
+    /// Queue a notification for a channel that couldn't deliver it
+    pub async fn queue_notification(
+        &self,
+        channel: &str,
+        payload: &str,
+        queued_at: DateTime<Utc>,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO notification_queue (channel, payload, queued_at)
+            VALUES ($1, $2, $3)
+            RETURNING id
+            "#,
+        )
+        .bind(channel)
+        .bind(payload)
+        .bind(queued_at.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to queue notification")?;
+
+        Ok(row.get("id"))
+    }
+
+    /// Get a channel's queued notifications, oldest first
+    pub async fn get_queued_notifications(&self, channel: &str) -> Result<Vec<QueuedNotificationRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, payload, queued_at FROM notification_queue
+            WHERE channel = $1
+            ORDER BY id
+            "#,
+        )
+        .bind(channel)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch queued notifications")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("queued_at");
+            let queued_at = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(QueuedNotificationRecord {
+                id: row.get("id"),
+                payload: row.get("payload"),
+                queued_at,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Destinations of a channel with notifications queued for just them,
+    /// from keys of the form `channel:destination`
+    pub async fn get_queued_destinations(&self, channel: &str) -> Result<Vec<String>> {
+        let prefix = format!("{}:", channel);
+        let rows = sqlx::query(
+            r#"
+            SELECT DISTINCT channel FROM notification_queue
+            WHERE channel LIKE $1
+            ORDER BY channel
+            "#,
+        )
+        .bind(format!("{}%", prefix))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch queued destinations")?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let key: String = row.get("channel");
+                key.strip_prefix(&prefix).map(str::to_string)
+            })
+            .collect())
+    }
+
+    /// Remove a notification from the queue once delivered
+    pub async fn delete_queued_notification(&self, id: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM notification_queue WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete queued notification")?;
+
+        Ok(())
+    }
+
+    /// Drop queued notifications older than `cutoff`, returning how many were dropped
+    pub async fn expire_queued_notifications(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM notification_queue WHERE queued_at < $1
+            "#,
+        )
+        .bind(cutoff.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to expire queued notifications")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Pause monitoring of a pair until it is resumed
+    pub async fn pause_pair(&self, pair_name: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO paused_pairs (pair_name, paused_at)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(pair_name)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to pause pair")?;
+
+        Ok(())
+    }
+
+    /// Resume monitoring of a pair, returning whether it was paused
+    pub async fn resume_pair(&self, pair_name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM paused_pairs WHERE pair_name = $1
+            "#,
+        )
+        .bind(pair_name)
+        .execute(&self.pool)
+        .await
+        .context("Failed to resume pair")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the names of all paused pairs
+    pub async fn get_paused_pairs(&self) -> Result<HashSet<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name FROM paused_pairs
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch paused pairs")?;
+
+        Ok(rows.iter().map(|row| row.get("pair_name")).collect())
+    }
+
+    /// Hold back a pair's alerts until `until`, replacing any earlier snooze
+    pub async fn snooze_pair(&self, pair_name: &str, until: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO snoozed_pairs (pair_name, until)
+            VALUES ($1, $2)
+            ON CONFLICT(pair_name) DO UPDATE SET
+                until = excluded.until
+            "#,
+        )
+        .bind(pair_name)
+        .bind(until.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to snooze pair")?;
+
+        Ok(())
+    }
+
+    /// Get the names of pairs snoozed past `now`
+    pub async fn get_snoozed_pairs(&self, now: DateTime<Utc>) -> Result<HashSet<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name FROM snoozed_pairs WHERE until > $1
+            "#,
+        )
+        .bind(now.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch snoozed pairs")?;
+
+        Ok(rows.iter().map(|row| row.get("pair_name")).collect())
+    }
+
+    /// Subscribe a chat to a pair's alerts. Returns false if it already was.
+    pub async fn subscribe(&self, chat_id: i64, pair_name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO subscriptions (chat_id, pair_name, subscribed_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(chat_id)
+        .bind(pair_name)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to subscribe")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Unsubscribe a chat from a pair's alerts. Returns false if it wasn't subscribed.
+    pub async fn unsubscribe(&self, chat_id: i64, pair_name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM subscriptions WHERE chat_id = $1 AND pair_name = $2
+            "#,
+        )
+        .bind(chat_id)
+        .bind(pair_name)
+        .execute(&self.pool)
+        .await
+        .context("Failed to unsubscribe")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the pairs a chat is subscribed to, by name
+    pub async fn get_subscriptions(&self, chat_id: i64) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name FROM subscriptions WHERE chat_id = $1 ORDER BY pair_name
+            "#,
+        )
+        .bind(chat_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch subscriptions")?;
+
+        Ok(rows.iter().map(|row| row.get("pair_name")).collect())
+    }
+
+    /// Get the chats subscribed to a pair
+    pub async fn get_subscribers(&self, pair_name: &str) -> Result<Vec<i64>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT chat_id FROM subscriptions WHERE pair_name = $1 ORDER BY subscribed_at
+            "#,
+        )
+        .bind(pair_name)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch subscribers")?;
+
+        Ok(rows.iter().map(|row| row.get("chat_id")).collect())
+    }
+
+    /// Star a pair for a bot user. Returns false if it already was.
+    pub async fn add_favorite(&self, user_id: u64, pair_name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO favorites (user_id, pair_name, added_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(user_id as i64)
+        .bind(pair_name)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to add favorite")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Unstar a pair for a bot user. Returns false if it wasn't starred.
+    pub async fn remove_favorite(&self, user_id: u64, pair_name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM favorites WHERE user_id = $1 AND pair_name = $2
+            "#,
+        )
+        .bind(user_id as i64)
+        .bind(pair_name)
+        .execute(&self.pool)
+        .await
+        .context("Failed to remove favorite")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get a bot user's favorite pairs, by name, in the order they were starred
+    pub async fn get_favorites(&self, user_id: u64) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name FROM favorites WHERE user_id = $1 ORDER BY added_at
+            "#,
+        )
+        .bind(user_id as i64)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch favorites")?;
+
+        Ok(rows.iter().map(|row| row.get("pair_name")).collect())
+    }
+
+    /// Store a pair added through the bot. Returns false if one with that name exists.
+    pub async fn add_bot_pair(
+        &self,
+        name: &str,
+        symbol_a: &str,
+        symbol_b: &str,
+        created_by: u64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO bot_pairs (name, symbol_a, symbol_b, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(name)
+        .bind(symbol_a)
+        .bind(symbol_b)
+        .bind(created_by as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to add pair")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a pair added through the bot, returning whether it existed
+    pub async fn remove_bot_pair(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM bot_pairs WHERE name = $1
+            "#,
+        )
+        .bind(name)
+        .execute(&self.pool)
+        .await
+        .context("Failed to remove pair")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the pairs added through the bot, oldest first
+    pub async fn get_bot_pairs(&self) -> Result<Vec<BotPairRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT name, symbol_a, symbol_b FROM bot_pairs ORDER BY created_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch bot pairs")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| BotPairRecord {
+                name: row.get("name"),
+                symbol_a: row.get("symbol_a"),
+                symbol_b: row.get("symbol_b"),
+            })
+            .collect())
+    }
+
+    /// Replace a pair's change rule with one window and its thresholds
+    pub async fn set_rule_override(
+        &self,
+        pair_name: &str,
+        window_secs: u64,
+        thresholds: &[f64],
+        updated_by: u64,
+    ) -> Result<()> {
+        let thresholds = thresholds
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        sqlx::query(
+            r#"
+            INSERT INTO rule_overrides (pair_name, window_secs, thresholds, updated_by, updated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT(pair_name) DO UPDATE SET
+                window_secs = excluded.window_secs,
+                thresholds = excluded.thresholds,
+                updated_by = excluded.updated_by,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(pair_name)
+        .bind(window_secs as i64)
+        .bind(thresholds)
+        .bind(updated_by as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to set change rule")?;
+
+        Ok(())
+    }
+
+    /// Go back to a pair's configured change rules, returning whether it had an override
+    pub async fn delete_rule_override(&self, pair_name: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM rule_overrides WHERE pair_name = $1
+            "#,
+        )
+        .bind(pair_name)
+        .execute(&self.pool)
+        .await
+        .context("Failed to reset change rule")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the change rules set through the bot
+    pub async fn get_rule_overrides(&self) -> Result<Vec<RuleOverrideRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name, window_secs, thresholds FROM rule_overrides
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch change rules")?;
+
+        rows.iter()
+            .map(|row| {
+                let thresholds: String = row.get("thresholds");
+                Ok(RuleOverrideRecord {
+                    pair_name: row.get("pair_name"),
+                    window_secs: row.get::<i64, _>("window_secs") as u64,
+                    thresholds: thresholds
+                        .split(',')
+                        .map(|t| t.parse().with_context(|| format!("Invalid stored threshold: {}", t)))
+                        .collect::<Result<_>>()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Record that a user acknowledged a pair's alert message
+    pub async fn insert_acknowledgement(
+        &self,
+        pair_name: &str,
+        user_id: u64,
+        user_name: &str,
+        chat_id: i64,
+        message_id: i32,
+        acknowledged_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO acknowledgements (pair_name, user_id, user_name, chat_id, message_id, acknowledged_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(pair_name)
+        .bind(user_id as i64)
+        .bind(user_name)
+        .bind(chat_id)
+        .bind(message_id)
+        .bind(acknowledged_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to insert acknowledgement")?;
+
+        Ok(())
+    }
+
+    /// Get the time of the most recent acknowledgement of a pair's alerts
+    pub async fn get_last_acknowledgement_time(&self, pair_name: &str) -> Result<Option<DateTime<Utc>>> {
+        let row = sqlx::query(
+            r#"
+            SELECT MAX(acknowledged_at) as last_acknowledged
+            FROM acknowledgements
+            WHERE pair_name = $1
+            "#,
+        )
+        .bind(pair_name)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch last acknowledgement time")?;
+
+        let timestamp_str: Option<String> = row.get("last_acknowledged");
+        timestamp_str
+            .map(|s| {
+                DateTime::parse_from_rfc3339(&s)
+                    .map(|t| t.with_timezone(&Utc))
+                    .context("Failed to parse timestamp")
+            })
+            .transpose()
+    }
+
+    /// Record a command or button press handled by the bot
+    pub async fn insert_bot_log(
+        &self,
+        user_id: u64,
+        user_name: &str,
+        chat_id: i64,
+        action: &str,
+        result: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO bot_log (user_id, user_name, chat_id, action, result, timestamp)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(user_id as i64)
+        .bind(user_name)
+        .bind(chat_id)
+        .bind(action)
+        .bind(result)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to insert bot log entry")?;
+
+        Ok(())
+    }
+
+    /// Get the most recent bot log entries, newest first, optionally of one user
+    pub async fn get_bot_log(&self, user_id: Option<u64>, limit: i64) -> Result<Vec<BotLogRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT user_id, user_name, chat_id, action, result, timestamp
+            FROM bot_log
+            WHERE $1 IS NULL OR user_id = $1
+            ORDER BY timestamp DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id.map(|id| id as i64))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch bot log")?;
+
+        rows.iter()
+            .map(|row| {
+                let timestamp_str: String = row.get("timestamp");
+                Ok(BotLogRecord {
+                    timestamp: DateTime::parse_from_rfc3339(&timestamp_str)
+                        .context("Failed to parse timestamp")?
+                        .with_timezone(&Utc),
+                    user_id: row.get("user_id"),
+                    user_name: row.get("user_name"),
+                    chat_id: row.get("chat_id"),
+                    action: row.get("action"),
+                    result: row.get("result"),
+                })
+            })
+            .collect()
+    }
+
+    /// Take the monitor lease unless another owner renewed it at or after `stale_before`.
+    /// Returns the current holder when the lease could not be taken.
+    pub async fn try_acquire_lease(
+        &self,
+        owner: &str,
+        stale_before: DateTime<Utc>,
+    ) -> Result<Option<LeaseRecord>> {
+        let now = Utc::now().to_rfc3339();
+        let result = sqlx::query(
+            r#"
+            INSERT INTO monitor_lease (id, owner, acquired_at, heartbeat_at)
+            VALUES (1, $1, $2, $3)
+            ON CONFLICT(id) DO UPDATE SET
+                owner = excluded.owner,
+                acquired_at = excluded.acquired_at,
+                heartbeat_at = excluded.heartbeat_at
+            WHERE monitor_lease.heartbeat_at < $4
+            "#,
+        )
+        .bind(owner)
+        .bind(&now)
+        .bind(&now)
+        .bind(stale_before.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to acquire monitor lease")?;
+
+        if result.rows_affected() > 0 {
+            return Ok(None);
+        }
+
+        let row = sqlx::query(
+            r#"
+            SELECT owner, heartbeat_at FROM monitor_lease WHERE id = 1
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch monitor lease")?;
+
+        let timestamp_str: String = row.get("heartbeat_at");
+        let heartbeat_at = DateTime::parse_from_rfc3339(&timestamp_str)
+            .context("Failed to parse timestamp")?
+            .with_timezone(&Utc);
+
+        Ok(Some(LeaseRecord {
+            owner: row.get("owner"),
+            heartbeat_at,
+        }))
+    }
+
+    /// Take the monitor lease regardless of who holds it
+    pub async fn force_acquire_lease(&self, owner: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT INTO monitor_lease (id, owner, acquired_at, heartbeat_at)
+            VALUES (1, $1, $2, $3)
+            ON CONFLICT(id) DO UPDATE SET
+                owner = excluded.owner,
+                acquired_at = excluded.acquired_at,
+                heartbeat_at = excluded.heartbeat_at
+            "#,
+        )
+        .bind(owner)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .context("Failed to acquire monitor lease")?;
+
+        Ok(())
+    }
+
+    /// Refresh the lease heartbeat, returning false if `owner` no longer holds it
+    pub async fn renew_lease(&self, owner: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE monitor_lease SET heartbeat_at = $1 WHERE id = 1 AND owner = $2
+            "#,
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(owner)
+        .execute(&self.pool)
+        .await
+        .context("Failed to renew monitor lease")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Give up the lease if `owner` still holds it
+    pub async fn release_lease(&self, owner: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM monitor_lease WHERE id = 1 AND owner = $1
+            "#,
+        )
+        .bind(owner)
+        .execute(&self.pool)
+        .await
+        .context("Failed to release monitor lease")?;
+
+        Ok(())
+    }
+
+    /// Get ratio history for a specific pair
+    pub async fn get_ratio_history(&self, pair_name: &str, limit: i64) -> Result<Vec<RatioRecord>> {
+        let rows = sqlx::query(&ratio_history_sql())
+            .bind(pair_name)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch ratio history")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(RatioRecord {
+                id: row.get("id"),
+                pair_name: row.get("pair_name"),
+                symbol_a: row.get("symbol_a"),
+                symbol_b: row.get("symbol_b"),
+                price_a: row.get("price_a"),
+                price_b: row.get("price_b"),
+                ratio: row.get("ratio"),
+                timestamp,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Get the oldest snapshot for a pair taken at or after `since`
+    pub async fn get_first_snapshot_since(
+        &self,
+        pair_name: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<RatioRecord>> {
+        let row = sqlx::query(&first_snapshot_since_sql())
+            .bind(pair_name)
+            .bind(since.to_rfc3339())
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch snapshot")?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let timestamp_str: String = row.get("timestamp");
+        let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+            .context("Failed to parse timestamp")?
+            .with_timezone(&Utc);
+
+        Ok(Some(RatioRecord {
+            id: row.get("id"),
+            pair_name: row.get("pair_name"),
+            symbol_a: row.get("symbol_a"),
+            symbol_b: row.get("symbol_b"),
+            price_a: row.get("price_a"),
+            price_b: row.get("price_b"),
+            ratio: row.get("ratio"),
+            timestamp,
+        }))
+    }
+
+    /// Get ratio history within a time range
+    pub async fn get_ratio_history_range(
+        &self,
+        pair_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<RatioRecord>> {
+        let rows = sqlx::query(&ratio_history_range_sql())
+            .bind(pair_name)
+            .bind(start.to_rfc3339())
+            .bind(end.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch ratio history range")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(RatioRecord {
+                id: row.get("id"),
+                pair_name: row.get("pair_name"),
+                symbol_a: row.get("symbol_a"),
+                symbol_b: row.get("symbol_b"),
+                price_a: row.get("price_a"),
+                price_b: row.get("price_b"),
+                ratio: row.get("ratio"),
+                timestamp,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Get the latest volume-based ratios for a pair, newest first
+    pub async fn get_volume_ratio_history(&self, pair_name: &str, limit: i64) -> Result<Vec<VolumeRatioRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, pair_name, symbol_a, symbol_b, volume, effective_price_a,
+                   effective_price_b, ratio, slippage_a, slippage_b, timestamp
+            FROM volume_ratios
+            WHERE pair_name = $1
+            ORDER BY timestamp DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(pair_name)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch volume ratio history")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(VolumeRatioRecord {
+                id: row.get("id"),
+                pair_name: row.get("pair_name"),
+                symbol_a: row.get("symbol_a"),
+                symbol_b: row.get("symbol_b"),
+                volume: row.get("volume"),
+                effective_price_a: row.get("effective_price_a"),
+                effective_price_b: row.get("effective_price_b"),
+                ratio: row.get("ratio"),
+                slippage_a: row.get("slippage_a"),
+                slippage_b: row.get("slippage_b"),
+                timestamp,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Get volume-based ratios for a pair within a time range, newest first
+    pub async fn get_volume_ratio_history_range(
+        &self,
+        pair_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<VolumeRatioRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, pair_name, symbol_a, symbol_b, volume, effective_price_a,
+                   effective_price_b, ratio, slippage_a, slippage_b, timestamp
+            FROM volume_ratios
+            WHERE pair_name = $1 AND timestamp >= $2 AND timestamp <= $3
+            ORDER BY timestamp DESC
+            "#,
+        )
+        .bind(pair_name)
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch volume ratio history range")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(VolumeRatioRecord {
+                id: row.get("id"),
+                pair_name: row.get("pair_name"),
+                symbol_a: row.get("symbol_a"),
+                symbol_b: row.get("symbol_b"),
+                volume: row.get("volume"),
+                effective_price_a: row.get("effective_price_a"),
+                effective_price_b: row.get("effective_price_b"),
+                ratio: row.get("ratio"),
+                slippage_a: row.get("slippage_a"),
+                slippage_b: row.get("slippage_b"),
+                timestamp,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Get alert history for a specific pair
+    pub async fn get_alert_history(&self, pair_name: &str, limit: i64) -> Result<Vec<AlertRecord>> {
+        let rows = sqlx::query(ALERT_HISTORY_SQL)
+            .bind(pair_name)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch alert history")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(AlertRecord {
+                id: row.get("id"),
+                pair_name: row.get("pair_name"),
+                ratio: row.get("ratio"),
+                change_percentage: row.get("change_percentage"),
+                threshold: row.get("threshold"),
+                timestamp,
+                direction: row.get("direction"),
+                price_a: row.get("price_a"),
+                price_b: row.get("price_b"),
+                window_secs: row.get("window_secs"),
+                severity: row.get("severity"),
+                metric: row.get("metric"),
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Get alerts for a pair within a time range, newest first
+    pub async fn get_alert_history_range(
+        &self,
+        pair_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AlertRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, pair_name, ratio, change_percentage, threshold, timestamp,
+                   direction, price_a, price_b, window_secs, severity, metric
+            FROM alerts
+            WHERE pair_name = $1 AND timestamp >= $2 AND timestamp <= $3
+            ORDER BY timestamp DESC
+            "#,
+        )
+        .bind(pair_name)
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch alert history range")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(AlertRecord {
+                id: row.get("id"),
+                pair_name: row.get("pair_name"),
+                ratio: row.get("ratio"),
+                change_percentage: row.get("change_percentage"),
+                threshold: row.get("threshold"),
+                timestamp,
+                direction: row.get("direction"),
+                price_a: row.get("price_a"),
+                price_b: row.get("price_b"),
+                window_secs: row.get("window_secs"),
+                severity: row.get("severity"),
+                metric: row.get("metric"),
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Get the time of the most recent alert for a pair and threshold raised
+    /// by one check (see `insert_alert`), or among daily open alerts when
+    /// `metric` is `None`. Older rows without a metric count by their window.
+    pub async fn get_last_alert_time(
+        &self,
+        pair_name: &str,
+        threshold: f64,
+        metric: Option<&str>,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let row = sqlx::query(
+            r#"
+            SELECT MAX(timestamp) as last_timestamp
+            FROM alerts
+            WHERE pair_name = $1 AND threshold = $2
+              AND COALESCE(metric, 'window:' || window_secs) IS NOT DISTINCT FROM $3
+            "#,
+        )
+        .bind(pair_name)
+        .bind(threshold)
+        .bind(metric)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch last alert time")?;
+
+        let timestamp_str: Option<String> = row.get("last_timestamp");
+        timestamp_str
+            .map(|s| {
+                DateTime::parse_from_rfc3339(&s)
+                    .map(|t| t.with_timezone(&Utc))
+                    .context("Failed to parse timestamp")
+            })
+            .transpose()
+    }
+
+    /// Count alerts for a pair raised within a time range
+    pub async fn count_alerts(
+        &self,
+        pair_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) as count
+            FROM alerts
+            WHERE pair_name = $1 AND timestamp >= $2 AND timestamp <= $3
+            "#,
+        )
+        .bind(pair_name)
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count alerts")?;
+
+        Ok(row.get("count"))
+    }
+
+    /// Get all alerts
+    pub async fn get_all_alerts(&self, limit: i64) -> Result<Vec<AlertRecord>> {
+        let rows = sqlx::query(ALL_ALERTS_SQL)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch all alerts")?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let timestamp_str: String = row.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+                .context("Failed to parse timestamp")?
+                .with_timezone(&Utc);
+
+            records.push(AlertRecord {
+                id: row.get("id"),
+                pair_name: row.get("pair_name"),
+                ratio: row.get("ratio"),
+                change_percentage: row.get("change_percentage"),
+                threshold: row.get("threshold"),
+                timestamp,
+                direction: row.get("direction"),
+                price_a: row.get("price_a"),
+                price_b: row.get("price_b"),
+                window_secs: row.get("window_secs"),
+                severity: row.get("severity"),
+                metric: row.get("metric"),
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// How often each pair, or just `pair_name`, alerted over the last `days`:
+    /// alerts per UTC day, per threshold, and how long its excursions took to recover
+    pub async fn get_alert_summary(&self, pair_name: Option<&str>, days: i64) -> Result<Vec<PairAlertSummary>> {
+        let since = (Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+        let mut summaries: BTreeMap<String, PairAlertSummary> = BTreeMap::new();
+
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name, SUBSTR(timestamp, 1, 10) AS day, COUNT(*) AS count
+            FROM alerts
+            WHERE timestamp >= $1 AND ($2 IS NULL OR pair_name = $2)
+            GROUP BY pair_name, SUBSTR(timestamp, 1, 10)
+            ORDER BY pair_name, day
+            "#,
+        )
+        .bind(&since)
+        .bind(pair_name)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to count alerts per day")?;
+
+        for row in rows {
+            let pair_name: String = row.get("pair_name");
+            summaries
+                .entry(pair_name.clone())
+                .or_insert_with(|| PairAlertSummary::new(pair_name, days))
+                .per_day
+                .push((row.get("day"), row.get("count")));
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name, threshold, COUNT(*) AS count,
+                   CAST(CASE WHEN metric LIKE 'volume_window:%' THEN 1 ELSE 0 END AS BIGINT) AS executable
+            FROM alerts
+            WHERE timestamp >= $1 AND ($2 IS NULL OR pair_name = $2)
+            GROUP BY pair_name, threshold, CASE WHEN metric LIKE 'volume_window:%' THEN 1 ELSE 0 END
+            ORDER BY pair_name, count DESC, threshold
+            "#,
+        )
+        .bind(&since)
+        .bind(pair_name)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to count alerts per threshold")?;
+
+        for row in rows {
+            let pair_name: String = row.get("pair_name");
+            let summary = summaries
+                .entry(pair_name.clone())
+                .or_insert_with(|| PairAlertSummary::new(pair_name, days));
+            let counts = if row.get::<i64, _>("executable") == 1 {
+                &mut summary.per_executable_threshold
+            } else {
+                &mut summary.per_threshold
+            };
+            counts.push((row.get("threshold"), row.get("count")));
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name, COUNT(*) AS count,
+                   CAST(AVG(duration_secs) AS DOUBLE PRECISION) AS avg_secs
+            FROM recoveries
+            WHERE timestamp >= $1 AND ($2 IS NULL OR pair_name = $2)
+            GROUP BY pair_name
+            "#,
+        )
+        .bind(&since)
+        .bind(pair_name)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to summarize recoveries")?;
+
+        for row in rows {
+            let pair_name: String = row.get("pair_name");
+            let summary = summaries
+                .entry(pair_name.clone())
+                .or_insert_with(|| PairAlertSummary::new(pair_name, days));
+            summary.recoveries = row.get("count");
+            summary.avg_recovery_secs = Some(row.get::<f64, _>("avg_secs") as u64);
+        }
+
+        Ok(summaries.into_values().collect())
+    }
+
+    /// Log one check of a pair: how long fetching its ratio took, and the
+    /// class and message of its error if it failed
+    pub async fn insert_check(
+        &self,
+        pair_name: &str,
+        latency_ms: u64,
+        error: Option<(&str, &str)>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO checks (pair_name, latency_ms, error_class, error, timestamp)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(pair_name)
+        .bind(latency_ms as i64)
+        .bind(error.map(|(class, _)| class))
+        .bind(error.map(|(_, message)| message))
+        .bind(timestamp.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to insert check")?;
+
+        Ok(())
+    }
+
+    /// How the checks of each pair, or only of `pair_name`, went from `start`
+    /// to `end`, by pair name. Checks slower than `slow_ms` count as slow
+    pub async fn get_check_summary(
+        &self,
+        pair_name: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        slow_ms: u64,
+    ) -> Result<Vec<CheckSummary>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT pair_name, latency_ms, error_class, error, timestamp
+            FROM checks
+            WHERE timestamp >= $1 AND timestamp <= $2 AND ($3 IS NULL OR pair_name = $3)
+            ORDER BY timestamp
+            "#,
+        )
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(pair_name)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch checks")?;
+
+        let mut summaries: BTreeMap<String, CheckSummary> = BTreeMap::new();
+        for row in rows {
+            let pair_name: String = row.get("pair_name");
+            let summary = summaries
+                .entry(pair_name.clone())
+                .or_insert_with(|| CheckSummary::new(pair_name, slow_ms));
+            summary.checks += 1;
+
+            let latency_ms = row.get::<i64, _>("latency_ms").max(0) as u64;
+            let Some(class) = row.get::<Option<String>, _>("error_class") else {
+                summary.latencies.push(latency_ms);
+                continue;
+            };
+
+            summary.failures += 1;
+            match summary.per_class.iter_mut().find(|(c, _)| *c == class) {
+                Some((_, count)) => *count += 1,
+                None => summary.per_class.push((class.clone(), 1)),
+            }
             let timestamp_str: String = row.get("timestamp");
             let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
                 .context("Failed to parse timestamp")?
                 .with_timezone(&Utc);
+            let error = row.get::<Option<String>, _>("error").unwrap_or_default();
+            summary.last_failure = Some((timestamp, class, error));
+        }
+
+        Ok(summaries
+            .into_values()
+            .map(|mut summary| {
+                summary.per_class.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+                summary.latencies.sort_unstable();
+                summary
+            })
+            .collect())
+    }
+
+    /// Get statistics for a pair
+    pub async fn get_pair_statistics(&self, pair_name: &str, hours: i64) -> Result<PairStatistics> {
+        let now = Utc::now();
+        self.get_pair_statistics_range(pair_name, now - chrono::Duration::hours(hours), now)
+            .await
+    }
+
+    /// Get statistics for a pair within a time range
+    pub async fn get_pair_statistics_range(
+        &self,
+        pair_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<PairStatistics> {
+        let row = sqlx::query(&pair_statistics_range_sql())
+            .bind(pair_name)
+            .bind(start.to_rfc3339())
+            .bind(end.to_rfc3339())
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to fetch statistics")?;
+
+        // Aggregates are NULL when no snapshots fall in the window
+        let count = row.get::<Option<i64>, _>("count").unwrap_or(0);
+        let avg_ratio = row.get::<Option<f64>, _>("avg_ratio").unwrap_or(0.0);
+        let squared_deviations = row.get::<Option<f64>, _>("squared_deviations").unwrap_or(0.0);
+        let first_ratio = row.get::<Option<f64>, _>("first_ratio").unwrap_or(0.0);
+        let last_ratio = row.get::<Option<f64>, _>("last_ratio").unwrap_or(0.0);
+
+        // Sample standard deviation, SQLite has no STDDEV. Deviations are
+        // summed around the mean rather than from raw squares, which cancel
+        // out for ratios that barely move.
+        let stddev = if count > 1 {
+            (squared_deviations / (count as f64 - 1.0)).max(0.0).sqrt()
+        } else {
+            0.0
+        };
+
+        Ok(PairStatistics {
+            pair_name: pair_name.to_string(),
+            count,
+            min_ratio: row.get::<Option<f64>, _>("min_ratio").unwrap_or(0.0),
+            max_ratio: row.get::<Option<f64>, _>("max_ratio").unwrap_or(0.0),
+            avg_ratio,
+            stddev,
+            first_ratio,
+            last_ratio,
+            hours: (end - start).num_hours(),
+        })
+    }
+
+    /// Statistics of each pair over the last `hours`, largest absolute change
+    /// first and pairs without data last
+    pub async fn get_all_pair_statistics(&self, pair_names: &[String], hours: i64) -> Result<Vec<PairStatistics>> {
+        let mut all_stats = Vec::with_capacity(pair_names.len());
+        for pair_name in pair_names {
+            all_stats.push(self.get_pair_statistics(pair_name, hours).await?);
+        }
+
+        all_stats.sort_by(|a, b| {
+            (b.count > 0)
+                .cmp(&(a.count > 0))
+                .then(b.change_pct().abs().total_cmp(&a.change_pct().abs()))
+        });
+
+        Ok(all_stats)
+    }
+
+    /// Statistics of a pair in two time ranges, e.g. this week and the last
+    pub async fn compare_pair_statistics(
+        &self,
+        pair_name: &str,
+        previous: (DateTime<Utc>, DateTime<Utc>),
+        current: (DateTime<Utc>, DateTime<Utc>),
+    ) -> Result<PairComparison> {
+        Ok(PairComparison {
+            previous_range: previous,
+            current_range: current,
+            previous: self.get_pair_statistics_range(pair_name, previous.0, previous.1).await?,
+            current: self.get_pair_statistics_range(pair_name, current.0, current.1).await?,
+        })
+    }
+
+    /// Slippage statistics of a pair's volume-based ratios, overall and in
+    /// up to `VOLUME_STATS_PERIODS` consecutive periods of the window
+    pub async fn get_volume_statistics(&self, pair_name: &str, hours: i64) -> Result<VolumeStatistics> {
+        let end = Utc::now();
+        let start = end - chrono::Duration::hours(hours);
+        let mut records = self.get_volume_ratio_history_range(pair_name, start, end).await?;
+        records.reverse();
+
+        let period = chrono::Duration::hours(((hours + VOLUME_STATS_PERIODS - 1) / VOLUME_STATS_PERIODS).max(1));
+        let mut periods: Vec<SlippagePeriod> = Vec::new();
+        for record in &records {
+            let index = ((record.timestamp - start).num_seconds() / period.num_seconds()) as i32;
+            let period_start = start + period * index;
+            match periods.last_mut() {
+                Some(last) if last.start == period_start => last.add(record),
+                _ => periods.push(SlippagePeriod::new(period_start, record)),
+            }
+        }
+
+        let count = records.len() as i64;
+        let (symbol_a, symbol_b) = records
+            .last()
+            .map(|r| (r.symbol_a.clone(), r.symbol_b.clone()))
+            .unwrap_or_default();
+
+        Ok(VolumeStatistics {
+            pair_name: pair_name.to_string(),
+            hours,
+            count,
+            symbol_a,
+            symbol_b,
+            min_ratio: records.iter().map(|r| r.ratio).fold(f64::INFINITY, f64::min),
+            max_ratio: records.iter().map(|r| r.ratio).fold(f64::NEG_INFINITY, f64::max),
+            avg_slippage_a: records.iter().map(|r| r.slippage_a).sum::<f64>() / count.max(1) as f64,
+            avg_slippage_b: records.iter().map(|r| r.slippage_b).sum::<f64>() / count.max(1) as f64,
+            max_slippage_a: records.iter().map(|r| r.slippage_a).fold(0.0, f64::max),
+            max_slippage_b: records.iter().map(|r| r.slippage_b).fold(0.0, f64::max),
+            periods,
+        })
+    }
+
+    /// Move a pair's history and state to another name in one transaction,
+    /// merging the history into any the new name already has. State the new
+    /// name already has, such as its own pause or a chat's subscription, wins.
+    pub async fn rename_pair(&self, old_name: &str, new_name: &str) -> Result<PairRowCounts> {
+        let mut tx = self.pool.begin().await.context("Failed to start rename")?;
+
+        let mut moved = Vec::new();
+        for table in PAIR_HISTORY_TABLES.into_iter().filter(|t| *t != "ratio_snapshots_hourly") {
+            let result = sqlx::query(&format!("UPDATE {} SET pair_name = $1 WHERE pair_name = $2", table))
+                .bind(new_name)
+                .bind(old_name)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to rename pair in {}", table))?;
+            moved.push((table, result.rows_affected()));
+        }
+
+        // Hourly rows are keyed by pair and hour, so ones both names have are merged
+        let result = sqlx::query(&format!(
+            r#"
+            INSERT INTO ratio_snapshots_hourly (pair_name, hour_start, symbol_a, symbol_b, price_a,
+                                                price_b, ratio, min_ratio, max_ratio, samples)
+            SELECT $1, hour_start, symbol_a, symbol_b, price_a, price_b, ratio, min_ratio,
+                   max_ratio, samples
+            FROM ratio_snapshots_hourly
+            WHERE pair_name = $2
+            {}
+            "#,
+            MERGE_HOURLY
+        ))
+        .bind(new_name)
+        .bind(old_name)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to rename pair in ratio_snapshots_hourly")?;
+        moved.push(("ratio_snapshots_hourly", result.rows_affected()));
+        moved.sort_by_key(|(table, _)| PAIR_HISTORY_TABLES.iter().position(|t| t == table));
+
+        sqlx::query("DELETE FROM ratio_snapshots_hourly WHERE pair_name = $1")
+            .bind(old_name)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to remove renamed hourly snapshots")?;
+
+        for (table, keys) in PAIR_STATE_TABLES {
+            let same_key: String = keys
+                .iter()
+                .map(|key| format!(" AND existing.{key} = {table}.{key}"))
+                .collect();
+            let result = sqlx::query(&format!(
+                r#"
+                UPDATE {table} SET pair_name = $1
+                WHERE pair_name = $2 AND NOT EXISTS (
+                    SELECT 1 FROM {table} AS existing WHERE existing.pair_name = $1{same_key}
+                )
+                "#
+            ))
+            .bind(new_name)
+            .bind(old_name)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to rename pair in {}", table))?;
+            moved.push((table, result.rows_affected()));
+
+            sqlx::query(&format!("DELETE FROM {} WHERE pair_name = $1", table))
+                .bind(old_name)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to remove state the new name already has from {}", table))?;
+        }
+
+        tx.commit().await.context("Failed to commit rename")?;
+
+        log::info!("Renamed pair {} to {}", old_name, new_name);
+
+        Ok(PairRowCounts { tables: moved })
+    }
+
+    /// Rows stored for a pair, per table its history and state are kept in
+    pub async fn count_pair_rows(&self, pair_name: &str) -> Result<PairRowCounts> {
+        let mut tables = Vec::new();
+        for table in PAIR_HISTORY_TABLES.into_iter().chain(PAIR_STATE_TABLES.map(|(table, _)| table)) {
+            let row = sqlx::query(&format!("SELECT COUNT(*) AS count FROM {} WHERE pair_name = $1", table))
+                .bind(pair_name)
+                .fetch_one(&self.pool)
+                .await
+                .with_context(|| format!("Failed to count rows in {}", table))?;
+            tables.push((table, row.get::<i64, _>("count") as u64));
+        }
+
+        Ok(PairRowCounts { tables })
+    }
+
+    /// Delete a pair's history and state in one transaction. Pairs added
+    /// through the bot keep their definition, see `remove_bot_pair`
+    pub async fn purge_pair(&self, pair_name: &str) -> Result<PairRowCounts> {
+        let mut tx = self.pool.begin().await.context("Failed to start purge")?;
 
-            records.push(AlertRecord {
-                id: row.get("id"),
-                pair_name: row.get("pair_name"),
-                ratio: row.get("ratio"),
-                change_percentage: row.get("change_percentage"),
-                threshold: row.get("threshold"),
-                timestamp,
-            });
+        let mut tables = Vec::new();
+        for table in PAIR_HISTORY_TABLES.into_iter().chain(PAIR_STATE_TABLES.map(|(table, _)| table)) {
+            let result = sqlx::query(&format!("DELETE FROM {} WHERE pair_name = $1", table))
+                .bind(pair_name)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to purge pair from {}", table))?;
+            tables.push((table, result.rows_affected()));
         }
 
-        Ok(records)
+        tx.commit().await.context("Failed to commit purge")?;
+
+        let counts = PairRowCounts { tables };
+        log::info!("Purged {} rows of pair {}", counts.total(), pair_name);
+
+        Ok(counts)
     }
 
-    /// Get all alerts
-    pub async fn get_all_alerts(&self, limit: i64) -> Result<Vec<AlertRecord>> {
-        let rows = sqlx::query(
+    /// Size of the database on disk
+    pub async fn size_bytes(&self) -> Result<u64> {
+        let query = match self.backend {
+            Backend::Sqlite => {
+                "SELECT page_count * page_size AS size FROM pragma_page_count(), pragma_page_size()"
+            }
+            Backend::Postgres => "SELECT pg_database_size(current_database()) AS size",
+        };
+        let row = sqlx::query(query)
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to read database size")?;
+
+        Ok(row.get::<i64, _>("size") as u64)
+    }
+
+    /// Size, tables, indexes and stored pairs of the database, for `db info`
+    pub async fn info(&self) -> Result<DatabaseInfo> {
+        let size = self.size_bytes().await?;
+        let schema_version = self.schema_version().await?.unwrap_or(0);
+
+        let (tables_query, indexes_query) = match self.backend {
+            Backend::Sqlite => (
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+                "SELECT name, tbl_name AS table_name, NULL AS scans FROM sqlite_master
+                 WHERE type = 'index' ORDER BY tbl_name, name",
+            ),
+            Backend::Postgres => (
+                "SELECT tablename AS name FROM pg_tables WHERE schemaname = current_schema() ORDER BY tablename",
+                "SELECT indexrelname AS name, relname AS table_name, idx_scan AS scans FROM pg_stat_user_indexes
+                 WHERE schemaname = current_schema() ORDER BY relname, indexrelname",
+            ),
+        };
+        let sizes = self.relation_sizes().await;
+        let since = (Utc::now() - chrono::Duration::days(GROWTH_DAYS)).to_rfc3339();
+
+        let mut tables = Vec::new();
+        for row in sqlx::query(tables_query)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list tables")?
+        {
+            let name: String = row.get("name");
+            let rows = sqlx::query(&format!("SELECT COUNT(*) AS count FROM \"{}\"", name))
+                .fetch_one(&self.pool)
+                .await
+                .with_context(|| format!("Failed to count rows in {}", name))?
+                .get::<i64, _>("count") as u64;
+
+            let recent_rows = match GROWTH_TABLES.iter().find(|(table, _)| *table == name) {
+                Some((_, column)) => Some(
+                    sqlx::query(&format!("SELECT COUNT(*) AS count FROM {} WHERE {} >= $1", name, column))
+                        .bind(&since)
+                        .fetch_one(&self.pool)
+                        .await
+                        .with_context(|| format!("Failed to count recent rows in {}", name))?
+                        .get::<i64, _>("count") as u64,
+                ),
+                None => None,
+            };
+
+            tables.push(TableInfo {
+                bytes: sizes.get(&name).copied(),
+                name,
+                rows,
+                recent_rows,
+            });
+        }
+
+        let mut indexes = Vec::new();
+        for row in sqlx::query(indexes_query)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list indexes")?
+        {
+            let name: String = row.get("name");
+            indexes.push(IndexInfo {
+                bytes: sizes.get(&name).copied(),
+                table: row.get("table_name"),
+                scans: row.get("scans"),
+                name,
+            });
+        }
+
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT id, pair_name, ratio, change_percentage, threshold, timestamp
-            FROM alerts
-            ORDER BY timestamp DESC
-            LIMIT ?
+            SELECT pair_name, COUNT(*) AS count, MIN(timestamp) AS oldest, MAX(timestamp) AS newest
+            FROM {}
+            GROUP BY pair_name
+            ORDER BY pair_name
             "#,
-        )
-        .bind(limit)
+            ALL_SNAPSHOTS
+        ))
         .fetch_all(&self.pool)
         .await
-        .context("Failed to fetch all alerts")?;
+        .context("Failed to fetch pair ranges")?;
 
-        let mut records = Vec::new();
+        let mut pairs = Vec::new();
         for row in rows {
-            let timestamp_str: String = row.get("timestamp");
-            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
-                .context("Failed to parse timestamp")?
-                .with_timezone(&Utc);
-
-            records.push(AlertRecord {
-                id: row.get("id"),
+            let parse = |column: &str| -> Result<DateTime<Utc>> {
+                let timestamp_str: String = row.get(column);
+                Ok(DateTime::parse_from_rfc3339(&timestamp_str)
+                    .context("Failed to parse timestamp")?
+                    .with_timezone(&Utc))
+            };
+            pairs.push(PairRange {
                 pair_name: row.get("pair_name"),
-                ratio: row.get("ratio"),
-                change_percentage: row.get("change_percentage"),
-                threshold: row.get("threshold"),
-                timestamp,
+                snapshots: row.get::<i64, _>("count") as u64,
+                oldest: parse("oldest")?,
+                newest: parse("newest")?,
             });
         }
 
-        Ok(records)
+        Ok(DatabaseInfo {
+            size,
+            schema_version,
+            tables,
+            indexes,
+            pairs,
+        })
     }
 
-    /// Get statistics for a pair
-    pub async fn get_pair_statistics(&self, pair_name: &str, hours: i64) -> Result<PairStatistics> {
-        let since = Utc::now() - chrono::Duration::hours(hours);
+    /// How the database runs the queries behind `history`, `alerts` and
+    /// `stats` for `pair_name`, for `db explain`. Plans are as printed by
+    /// `EXPLAIN QUERY PLAN` on SQLite and `EXPLAIN` on PostgreSQL
+    pub async fn explain_queries(&self, pair_name: &str) -> Result<Vec<QueryPlan>> {
+        let explain = |sql: String| match self.backend {
+            Backend::Sqlite => format!("EXPLAIN QUERY PLAN {}", sql),
+            Backend::Postgres => format!("EXPLAIN {}", sql),
+        };
+        let history = explain(ratio_history_sql());
+        let first_since = explain(first_snapshot_since_sql());
+        let history_range = explain(ratio_history_range_sql());
+        let statistics = explain(pair_statistics_range_sql());
+        let alerts = explain(ALERT_HISTORY_SQL.to_string());
+        let all_alerts = explain(ALL_ALERTS_SQL.to_string());
 
-        let row = sqlx::query(
-            r#"
-            SELECT
-                COUNT(*) as count,
-                MIN(ratio) as min_ratio,
-                MAX(ratio) as max_ratio,
-                AVG(ratio) as avg_ratio
-            FROM ratio_snapshots
-            WHERE pair_name = ? AND timestamp >= ?
-            "#,
-        )
-        .bind(pair_name)
-        .bind(since.to_rfc3339())
-        .fetch_one(&self.pool)
-        .await
-        .context("Failed to fetch statistics")?;
+        let end = Utc::now();
+        let start = (end - chrono::Duration::days(1)).to_rfc3339();
+        let end = end.to_rfc3339();
+        let queries = [
+            ("Ratio history", sqlx::query(&history).bind(pair_name).bind(10i64)),
+            ("First snapshot since", sqlx::query(&first_since).bind(pair_name).bind(start.clone())),
+            (
+                "Ratio history range",
+                sqlx::query(&history_range).bind(pair_name).bind(start.clone()).bind(end.clone()),
+            ),
+            (
+                "Statistics range",
+                sqlx::query(&statistics).bind(pair_name).bind(start.clone()).bind(end.clone()),
+            ),
+            ("Pair alerts", sqlx::query(&alerts).bind(pair_name).bind(10i64)),
+            ("All alerts", sqlx::query(&all_alerts).bind(10i64)),
+        ];
 
-        Ok(PairStatistics {
-            pair_name: pair_name.to_string(),
-            count: row.get("count"),
-            min_ratio: row.get("min_ratio"),
-            max_ratio: row.get("max_ratio"),
-            avg_ratio: row.get("avg_ratio"),
-            hours,
-        })
+        let mut plans = Vec::new();
+        for (name, query) in queries {
+            let rows = query
+                .fetch_all(&self.pool)
+                .await
+                .with_context(|| format!("Failed to explain {}", name.to_lowercase()))?;
+
+            let lines = match self.backend {
+                // Steps name their parent step; indent them beneath it
+                Backend::Sqlite => {
+                    let mut depths: HashMap<i64, usize> = HashMap::new();
+                    rows.iter()
+                        .map(|row| {
+                            let depth = depths.get(&row.get::<i64, _>("parent")).map_or(0, |depth| depth + 1);
+                            depths.insert(row.get("id"), depth);
+                            format!("{}{}", "  ".repeat(depth), row.get::<String, _>("detail"))
+                        })
+                        .collect()
+                }
+                Backend::Postgres => rows.iter().map(|row| row.get("QUERY PLAN")).collect(),
+            };
+            plans.push(QueryPlan { name, lines });
+        }
+
+        Ok(plans)
     }
 
-    /// Clean up old records (older than specified days)
-    pub async fn cleanup_old_records(&self, days: i64) -> Result<u64> {
-        let cutoff = Utc::now() - chrono::Duration::days(days);
+    /// Bytes of each table and index by name. Empty where the backend can't
+    /// tell, e.g. SQLite built without the `dbstat` table
+    async fn relation_sizes(&self) -> HashMap<String, u64> {
+        let query = match self.backend {
+            Backend::Sqlite => "SELECT name, CAST(SUM(pgsize) AS BIGINT) AS size FROM dbstat GROUP BY name",
+            Backend::Postgres => {
+                "SELECT relname AS name, pg_relation_size(oid) AS size FROM pg_class
+                 WHERE relnamespace = current_schema()::regnamespace AND relkind IN ('r', 'i')"
+            }
+        };
+        match sqlx::query(query).fetch_all(&self.pool).await {
+            Ok(rows) => rows
+                .iter()
+                .map(|row| (row.get("name"), row.get::<i64, _>("size") as u64))
+                .collect(),
+            Err(e) => {
+                log::debug!("Table sizes unavailable: {}", e);
+                HashMap::new()
+            }
+        }
+    }
 
-        let result = sqlx::query(
+    /// Rebuild the database to return the space of deleted rows
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .context("Failed to vacuum database")?;
+
+        Ok(())
+    }
+
+    /// Problems found by SQLite's integrity check, empty if there are none.
+    /// `None` for PostgreSQL, which has no equivalent
+    pub async fn integrity_check(&self) -> Result<Option<Vec<String>>> {
+        if self.backend == Backend::Postgres {
+            return Ok(None);
+        }
+
+        let rows = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to check database integrity")?;
+
+        Ok(Some(
+            rows.iter()
+                .map(|row| row.get::<String, _>(0))
+                .filter(|result| result != "ok")
+                .collect(),
+        ))
+    }
+
+    /// Condense snapshots older than `days` into hourly aggregates and delete
+    /// them, returning how many were condensed. Only whole hours are condensed
+    pub async fn rollup_snapshots(&self, days: i64) -> Result<u64> {
+        let cutoff = (Utc::now() - chrono::Duration::days(days))
+            .duration_trunc(chrono::Duration::hours(1))
+            .context("Failed to compute rollup cutoff")?;
+
+        let mut tx = self.pool.begin().await.context("Failed to start rollup")?;
+
+        // Timestamps are stored as UTC RFC 3339, so their first 13 characters
+        // name the hour. An hour condensed before only gains late snapshots
+        let result = sqlx::query(&format!(
             r#"
-            DELETE FROM ratio_snapshots WHERE timestamp < ?
+            INSERT INTO ratio_snapshots_hourly (pair_name, hour_start, symbol_a, symbol_b, price_a,
+                                                price_b, ratio, min_ratio, max_ratio, samples)
+            SELECT pair_name, SUBSTR(timestamp, 1, 13) || ':00:00+00:00', MAX(symbol_a), MAX(symbol_b),
+                   AVG(price_a), AVG(price_b), AVG(ratio), MIN(ratio), MAX(ratio), COUNT(*)
+            FROM ratio_snapshots
+            WHERE timestamp < $1
+            GROUP BY pair_name, SUBSTR(timestamp, 1, 13)
+            {}
             "#,
-        )
+            MERGE_HOURLY
+        ))
         .bind(cutoff.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
-        .context("Failed to clean up old ratio snapshots")?;
+        .context("Failed to condense old ratio snapshots")?;
 
-        let deleted_ratios = result.rows_affected();
+        let hours = result.rows_affected();
 
         let result = sqlx::query(
             r#"
-            DELETE FROM alerts WHERE timestamp < ?
+            DELETE FROM ratio_snapshots WHERE timestamp < $1
             "#,
         )
         .bind(cutoff.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
-        .context("Failed to clean up old alerts")?;
+        .context("Failed to delete condensed ratio snapshots")?;
+
+        let condensed = result.rows_affected();
 
-        let deleted_alerts = result.rows_affected();
+        tx.commit().await.context("Failed to commit rollup")?;
+
+        log::info!(
+            "Condensed {} ratio snapshots older than {} days into {} hourly rows",
+            condensed,
+            days,
+            hours
+        );
+
+        Ok(condensed)
+    }
+
+    /// Clean up records older than the retention period of their kind; kinds
+    /// without one are kept
+    pub async fn cleanup_old_records(&self, retention: &RetentionConfig) -> Result<CleanupSummary> {
+        let mut summary = CleanupSummary {
+            ratio_snapshots: 0,
+            alerts: 0,
+            volume_ratios: 0,
+            order_books: 0,
+            checks: 0,
+            retention: retention.clone(),
+        };
+
+        if let Some(days) = retention.snapshots {
+            // Hourly aggregates are counted with the snapshots they replaced
+            summary.ratio_snapshots = self.delete_older_than("ratio_snapshots", "timestamp", days).await?
+                + self.delete_older_than("ratio_snapshots_hourly", "hour_start", days).await?;
+        }
+        if let Some(days) = retention.alerts {
+            // Recoveries belong to the alert history
+            summary.alerts = self.delete_older_than("alerts", "timestamp", days).await?
+                + self.delete_older_than("recoveries", "timestamp", days).await?;
+        }
+        if let Some(days) = retention.volume_ratios {
+            summary.volume_ratios = self.delete_older_than("volume_ratios", "timestamp", days).await?;
+        }
+        if let Some(days) = retention.order_books {
+            summary.order_books = self.delete_older_than("order_book_snapshots", "timestamp", days).await?;
+        }
+        if let Some(days) = retention.checks {
+            summary.checks = self.delete_older_than("checks", "timestamp", days).await?;
+        }
 
         log::info!(
-            "Cleaned up {} ratio snapshots and {} alerts older than {} days",
-            deleted_ratios,
-            deleted_alerts,
-            days
+            "Cleaned up {} ratio snapshots, {} alerts, {} volume ratios, {} order books and {} checks",
+            summary.ratio_snapshots,
+            summary.alerts,
+            summary.volume_ratios,
+            summary.order_books,
+            summary.checks
         );
 
-        Ok(deleted_ratios + deleted_alerts)
+        Ok(summary)
+    }
+
+    /// Delete the rows of `table` whose `column` is more than `days` old
+    async fn delete_older_than(&self, table: &str, column: &str, days: i64) -> Result<u64> {
+        let cutoff = Utc::now() - chrono::Duration::days(days);
+
+        let result = sqlx::query(&format!("DELETE FROM {} WHERE {} < $1", table, column))
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to clean up old rows of {}", table))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// A query's plan, one step per line
+#[derive(Debug)]
+pub struct QueryPlan {
+    pub name: &'static str,
+    pub lines: Vec<String>,
+}
+
+/// What `db info` reports about the database
+#[derive(Debug)]
+pub struct DatabaseInfo {
+    pub size: u64,
+    pub schema_version: i64,
+    pub tables: Vec<TableInfo>,
+    pub indexes: Vec<IndexInfo>,
+    /// Pairs with stored snapshots
+    pub pairs: Vec<PairRange>,
+}
+
+impl DatabaseInfo {
+    /// Bytes a day the append-only tables grew by recently, at each table's
+    /// average row size including its indexes. `None` without table sizes
+    pub fn growth_per_day(&self) -> Option<f64> {
+        let mut growth = 0.0;
+        for table in &self.tables {
+            let Some(recent_rows) = table.recent_rows else {
+                continue;
+            };
+            if table.rows == 0 {
+                continue;
+            }
+            let index_bytes: u64 = self
+                .indexes
+                .iter()
+                .filter(|index| index.table == table.name)
+                .filter_map(|index| index.bytes)
+                .sum();
+            let bytes_per_row = (table.bytes? + index_bytes) as f64 / table.rows as f64;
+            growth += recent_rows as f64 * bytes_per_row / GROWTH_DAYS as f64;
+        }
+        Some(growth)
+    }
+}
+
+#[derive(Debug)]
+pub struct TableInfo {
+    pub name: String,
+    pub rows: u64,
+    pub bytes: Option<u64>,
+    /// Rows added over the last `GROWTH_DAYS`, for append-only tables
+    pub recent_rows: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct IndexInfo {
+    pub name: String,
+    pub table: String,
+    pub bytes: Option<u64>,
+    /// Scans since the statistics were reset; PostgreSQL only
+    pub scans: Option<i64>,
+}
+
+/// Time span of a pair's stored snapshots, raw and hourly
+#[derive(Debug)]
+pub struct PairRange {
+    pub pair_name: String,
+    pub snapshots: u64,
+    pub oldest: DateTime<Utc>,
+    pub newest: DateTime<Utc>,
+}
+
+/// Rows removed by a retention cleanup
+#[derive(Debug)]
+pub struct CleanupSummary {
+    pub ratio_snapshots: u64,
+    pub alerts: u64,
+    pub volume_ratios: u64,
+    pub order_books: u64,
+    pub checks: u64,
+    /// Days each kind was kept for
+    pub retention: RetentionConfig,
+}
+
+impl CleanupSummary {
+    pub fn total(&self) -> u64 {
+        self.ratio_snapshots + self.alerts + self.volume_ratios + self.order_books + self.checks
     }
 }
 
@@ -472,25 +2864,465 @@ pub struct PairStatistics {
     pub min_ratio: f64,
     pub max_ratio: f64,
     pub avg_ratio: f64,
+    /// Sample standard deviation of the ratio
+    pub stddev: f64,
+    /// Oldest ratio in the window
+    pub first_ratio: f64,
+    /// Latest ratio in the window
+    pub last_ratio: f64,
+    pub hours: i64,
+}
+
+/// A pair's rows per table, e.g. moved by a rename or deleted by a purge
+#[derive(Debug)]
+pub struct PairRowCounts {
+    pub tables: Vec<(&'static str, u64)>,
+}
+
+impl PairRowCounts {
+    pub fn total(&self) -> u64 {
+        self.tables.iter().map(|(_, rows)| rows).sum()
+    }
+}
+
+/// How a pair's checks went over a time range, for `health-history`
+#[derive(Debug)]
+pub struct CheckSummary {
+    pub pair_name: String,
+    pub checks: u64,
+    pub failures: u64,
+    /// Failed checks per error class, most frequent first
+    pub per_class: Vec<(String, u64)>,
+    /// Latencies of successful checks in milliseconds, fastest first
+    latencies: Vec<u64>,
+    slow_ms: u64,
+    /// Time, error class and message of the most recent failure
+    pub last_failure: Option<(DateTime<Utc>, String, String)>,
+}
+
+impl CheckSummary {
+    fn new(pair_name: String, slow_ms: u64) -> Self {
+        Self {
+            pair_name,
+            checks: 0,
+            failures: 0,
+            per_class: Vec::new(),
+            latencies: Vec::new(),
+            slow_ms,
+            last_failure: None,
+        }
+    }
+
+    /// Share of checks that failed, in percent
+    pub fn failure_rate(&self) -> f64 {
+        if self.checks == 0 {
+            return 0.0;
+        }
+        self.failures as f64 / self.checks as f64 * 100.0
+    }
+
+    /// Latency that `percent` of successful checks stayed within
+    pub fn latency_percentile(&self, percent: f64) -> Option<u64> {
+        let rank = (self.latencies.len() as f64 * percent / 100.0).ceil() as usize;
+        self.latencies.get(rank.max(1) - 1).copied()
+    }
+
+    /// Successful checks slower than `slow_ms`
+    pub fn slow(&self) -> usize {
+        self.latencies.iter().filter(|ms| **ms > self.slow_ms).count()
+    }
+
+    pub fn format_summary(&self, tz: &chrono_tz::Tz) -> String {
+        let mut text = format!(
+            "{}: {} checks, {} failed ({:.1}%)",
+            self.pair_name,
+            self.checks,
+            self.failures,
+            self.failure_rate()
+        );
+
+        if !self.per_class.is_empty() {
+            let classes: Vec<String> = self
+                .per_class
+                .iter()
+                .map(|(class, count)| format!("{} ×{}", class, count))
+                .collect();
+            text.push_str(&format!("\n  Errors: {}", classes.join(", ")));
+        }
+
+        if let (Some(p50), Some(p95), Some(max)) = (
+            self.latency_percentile(50.0),
+            self.latency_percentile(95.0),
+            self.latencies.last(),
+        ) {
+            let slow = self.slow();
+            text.push_str(&format!(
+                "\n  Latency: p50 {} ms, p95 {} ms, max {} ms\n  Slower than {} ms: {} ({:.1}%)",
+                p50,
+                p95,
+                max,
+                self.slow_ms,
+                slow,
+                slow as f64 / self.latencies.len() as f64 * 100.0
+            ));
+        }
+
+        if let Some((timestamp, class, error)) = &self.last_failure {
+            text.push_str(&format!(
+                "\n  Last failure: {} ({}) {}",
+                timestamp.with_timezone(tz).format("%Y-%m-%d %H:%M:%S %Z"),
+                class,
+                error
+            ));
+        }
+
+        text
+    }
+}
+
+/// How often a pair alerted over a number of days
+#[derive(Debug)]
+pub struct PairAlertSummary {
+    pub pair_name: String,
+    pub days: i64,
+    /// Alerts per UTC day (YYYY-MM-DD), oldest first, days without alerts left out
+    pub per_day: Vec<(String, i64)>,
+    /// Alerts per threshold of the mid ratio and daily open, most frequent first
+    pub per_threshold: Vec<(f64, i64)>,
+    /// Alerts per threshold of the executable ratio, most frequent first
+    pub per_executable_threshold: Vec<(f64, i64)>,
+    pub recoveries: i64,
+    /// Average time from the start of an excursion to its recovery
+    pub avg_recovery_secs: Option<u64>,
+}
+
+impl PairAlertSummary {
+    fn new(pair_name: String, days: i64) -> Self {
+        Self {
+            pair_name,
+            days,
+            per_day: Vec::new(),
+            per_threshold: Vec::new(),
+            per_executable_threshold: Vec::new(),
+            recoveries: 0,
+            avg_recovery_secs: None,
+        }
+    }
+
+    pub fn total(&self) -> i64 {
+        self.per_day.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn format_summary(&self) -> String {
+        let total = self.total();
+        let mut text = format!(
+            "{}: {} alerts on {} of the last {} days ({:.1} per day)",
+            self.pair_name,
+            total,
+            self.per_day.len(),
+            self.days,
+            total as f64 / self.days as f64
+        );
+
+        if let Some((day, count)) = self.per_day.iter().max_by_key(|(_, count)| *count) {
+            text.push_str(&format!("\n  Busiest day: {} with {}", day, count));
+        }
+
+        for (label, counts) in [
+            ("Thresholds", &self.per_threshold),
+            ("Executable ratio thresholds", &self.per_executable_threshold),
+        ] {
+            if counts.is_empty() {
+                continue;
+            }
+            let thresholds: Vec<String> = counts
+                .iter()
+                .map(|(threshold, count)| format!("{}% ×{}", threshold, count))
+                .collect();
+            text.push_str(&format!("\n  {}: {}", label, thresholds.join(", ")));
+        }
+
+        match self.avg_recovery_secs {
+            Some(secs) => text.push_str(&format!(
+                "\n  Recoveries: {}, on average after {}",
+                self.recoveries,
+                crate::monitor::format_duration(secs)
+            )),
+            None => text.push_str("\n  Recoveries: none recorded"),
+        }
+
+        if !self.per_day.is_empty() {
+            let days: Vec<String> = self
+                .per_day
+                .iter()
+                .map(|(day, count)| format!("{} {}", day, count))
+                .collect();
+            text.push_str(&format!("\n  Per day: {}", days.join(", ")));
+        }
+
+        text
+    }
+}
+
+/// A pair's statistics in two time ranges
+#[derive(Debug)]
+pub struct PairComparison {
+    pub previous_range: (DateTime<Utc>, DateTime<Utc>),
+    pub current_range: (DateTime<Utc>, DateTime<Utc>),
+    pub previous: PairStatistics,
+    pub current: PairStatistics,
+}
+
+/// Slippage of a pair's volume-based ratios over a window
+#[derive(Debug)]
+pub struct VolumeStatistics {
+    pub pair_name: String,
     pub hours: i64,
+    pub count: i64,
+    pub symbol_a: String,
+    pub symbol_b: String,
+    pub min_ratio: f64,
+    pub max_ratio: f64,
+    pub avg_slippage_a: f64,
+    pub avg_slippage_b: f64,
+    pub max_slippage_a: f64,
+    pub max_slippage_b: f64,
+    /// Periods of the window with data, oldest first
+    pub periods: Vec<SlippagePeriod>,
+}
+
+/// Average and worst slippage of the volume-based ratios in one period
+#[derive(Debug)]
+pub struct SlippagePeriod {
+    pub start: DateTime<Utc>,
+    pub count: i64,
+    pub avg_slippage_a: f64,
+    pub avg_slippage_b: f64,
+    pub max_slippage: f64,
+}
+
+impl SlippagePeriod {
+    fn new(start: DateTime<Utc>, record: &VolumeRatioRecord) -> Self {
+        Self {
+            start,
+            count: 1,
+            avg_slippage_a: record.slippage_a,
+            avg_slippage_b: record.slippage_b,
+            max_slippage: record.slippage_a.max(record.slippage_b),
+        }
+    }
+
+    fn add(&mut self, record: &VolumeRatioRecord) {
+        let n = self.count as f64;
+        self.avg_slippage_a = (self.avg_slippage_a * n + record.slippage_a) / (n + 1.0);
+        self.avg_slippage_b = (self.avg_slippage_b * n + record.slippage_b) / (n + 1.0);
+        self.max_slippage = self.max_slippage.max(record.slippage_a).max(record.slippage_b);
+        self.count += 1;
+    }
+}
+
+impl AlertRecord {
+    /// Whether it was raised for the executable ratio rather than the mid ratio
+    pub fn is_executable(&self) -> bool {
+        self.metric.as_deref().is_some_and(|m| m.starts_with("volume_window:"))
+    }
+
+    /// Change, window, direction and severity, plus leg prices when recorded
+    pub fn format_details(&self, t: &Texts) -> String {
+        let window = match (self.window_secs, &self.direction) {
+            (Some(secs), _) => {
+                let window = crate::monitor::format_duration(secs as u64);
+                format!(" {}", fill(t.in_window, &[("window", &window)]))
+            }
+            // Rows with a direction but no window came from the daily open check
+            (None, Some(_)) => format!(" {}", t.on_the_day),
+            (None, None) => String::new(),
+        };
+        let window = if self.is_executable() {
+            format!("{} {}", window, t.executable)
+        } else {
+            window
+        };
+
+        let mut text = fill(
+            t.bot_alert_details,
+            &[
+                ("change", &format!("{:+.2}", self.change_percentage)),
+                ("window", &window),
+                ("threshold", &self.threshold),
+            ],
+        );
+        if let Some(direction) = &self.direction {
+            text.push_str(&format!(" | {}", t.direction(direction)));
+        }
+        if let Some(severity) = &self.severity {
+            text.push_str(&format!(" | {}", severity));
+        }
+        if let (Some(price_a), Some(price_b)) = (self.price_a, self.price_b) {
+            let legs = fill(
+                t.bot_legs,
+                &[("price_a", &format!("{:.8}", price_a)), ("price_b", &format!("{:.8}", price_b))],
+            );
+            text.push_str(&format!("\n  {}", legs));
+        }
+
+        text
+    }
 }
 
 impl PairStatistics {
+    /// Percentage change from the first to the last ratio of the window
+    pub fn change_pct(&self) -> f64 {
+        if self.first_ratio == 0.0 {
+            return 0.0;
+        }
+        (self.last_ratio - self.first_ratio) / self.first_ratio * 100.0
+    }
+
     pub fn format_summary(&self) -> String {
+        if self.count == 0 {
+            return format!(
+                "{} (last {} hours):\n  No data recorded. Run `monitor` or `start` to collect snapshots.",
+                self.pair_name, self.hours
+            );
+        }
+
         format!(
             "{} (last {} hours):\n  \
             Samples: {}\n  \
             Min: {:.8}\n  \
             Max: {:.8}\n  \
             Avg: {:.8}\n  \
-            Range: {:.2}%",
+            Std dev: {:.8}\n  \
+            Range: {:.2}%\n  \
+            First: {:.8}\n  \
+            Last: {:.8}\n  \
+            Change: {:+.2}%",
             self.pair_name,
             self.hours,
             self.count,
             self.min_ratio,
             self.max_ratio,
             self.avg_ratio,
-            ((self.max_ratio - self.min_ratio) / self.min_ratio * 100.0)
+            self.stddev,
+            ((self.max_ratio - self.min_ratio) / self.min_ratio * 100.0),
+            self.first_ratio,
+            self.last_ratio,
+            self.change_pct()
         )
     }
 }
+
+impl VolumeStatistics {
+    /// Overall figures, then one line per period with times in `tz`
+    pub fn format_summary(&self, tz: &chrono_tz::Tz) -> String {
+        if self.count == 0 {
+            return format!(
+                "{} volume ratios (last {} hours):\n  No data recorded. Set `analysis_volume` and \
+                 `volume_check_interval_secs` to record them.",
+                self.pair_name, self.hours
+            );
+        }
+
+        let mut text = format!(
+            "{} volume ratios (last {} hours):\n  \
+            Samples: {}\n  \
+            Min: {:.8}\n  \
+            Max: {:.8}\n  \
+            {} slippage: avg {:.3}%, max {:.3}%\n  \
+            {} slippage: avg {:.3}%, max {:.3}%\n\n\
+            Slippage over time:",
+            self.pair_name,
+            self.hours,
+            self.count,
+            self.min_ratio,
+            self.max_ratio,
+            self.symbol_a,
+            self.avg_slippage_a,
+            self.max_slippage_a,
+            self.symbol_b,
+            self.avg_slippage_b,
+            self.max_slippage_b
+        );
+
+        for period in &self.periods {
+            text.push_str(&format!(
+                "\n  {} | {:>4} samples | {} {:.3}% | {} {:.3}% | max {:.3}%",
+                period.start.with_timezone(tz).format("%Y-%m-%d %H:%M"),
+                period.count,
+                self.symbol_a,
+                period.avg_slippage_a,
+                self.symbol_b,
+                period.avg_slippage_b,
+                period.max_slippage
+            ));
+        }
+
+        text
+    }
+}
+
+impl PairComparison {
+    /// Both ranges' mean, volatility and extremes side by side with the change
+    /// between them, times in `tz`
+    pub fn format_summary(&self, tz: &chrono_tz::Tz) -> String {
+        let range = |(start, end): (DateTime<Utc>, DateTime<Utc>)| {
+            format!(
+                "{} - {}",
+                start.with_timezone(tz).format("%Y-%m-%d %H:%M"),
+                end.with_timezone(tz).format("%Y-%m-%d %H:%M %Z")
+            )
+        };
+        let mut text = format!(
+            "{}\n  Previous: {} ({} samples)\n  Current:  {} ({} samples)",
+            self.current.pair_name,
+            range(self.previous_range),
+            self.previous.count,
+            range(self.current_range),
+            self.current.count
+        );
+
+        if self.previous.count == 0 || self.current.count == 0 {
+            text.push_str("\n\n  Both ranges need recorded snapshots to compare.");
+            return text;
+        }
+
+        let spread = |s: &PairStatistics| (s.max_ratio - s.min_ratio) / s.min_ratio * 100.0;
+        let rows = [
+            ("Mean", self.previous.avg_ratio, self.current.avg_ratio),
+            ("Std dev", self.previous.stddev, self.current.stddev),
+            ("Min", self.previous.min_ratio, self.current.min_ratio),
+            ("Max", self.previous.max_ratio, self.current.max_ratio),
+        ];
+
+        text.push_str(&format!(
+            "\n\n  {:<8} {:>16} {:>16} {:>10}",
+            "", "Previous", "Current", "Change"
+        ));
+        for (label, previous, current) in rows {
+            let change = if previous == 0.0 {
+                "-".to_string()
+            } else {
+                format!("{:+.2}%", (current - previous) / previous * 100.0)
+            };
+            text.push_str(&format!(
+                "\n  {:<8} {:>16.8} {:>16.8} {:>10}",
+                label, previous, current, change
+            ));
+        }
+        for (label, previous, current) in [
+            ("Range", spread(&self.previous), spread(&self.current)),
+            ("Net move", self.previous.change_pct(), self.current.change_pct()),
+        ] {
+            text.push_str(&format!(
+                "\n  {:<8} {:>+15.2}% {:>+15.2}% {:>+9.2}pp",
+                label,
+                previous,
+                current,
+                current - previous
+            ));
+        }
+
+        text
+    }
+}
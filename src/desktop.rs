@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use notify_rust::Notification;
+
+use crate::config::Severity;
+use crate::notifier::{Alert, AlertKind, Message, Notifier};
+
+/// How insistently a popup is shown; only Linux/BSD notification servers
+/// distinguish these, elsewhere every popup looks the same
+#[derive(Debug, Clone, Copy)]
+enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+/// Shows notifications as popups on the local desktop
+pub struct DesktopNotifier;
+
+impl DesktopNotifier {
+    pub fn new() -> Self {
+        Self
+    }
+
+    async fn show(&self, message: &Message, urgency: Urgency) -> Result<()> {
+        let mut notification = Notification::new();
+        notification
+            .appname("ratio-noti")
+            .summary(&message.title.plain_text())
+            .body(&message.body_text());
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        notification.urgency(match urgency {
+            Urgency::Low => notify_rust::Urgency::Low,
+            Urgency::Normal => notify_rust::Urgency::Normal,
+            Urgency::Critical => notify_rust::Urgency::Critical,
+        });
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        let _ = urgency;
+
+        // Talking to the notification server blocks
+        tokio::task::spawn_blocking(move || notification.show().map(|_| ())).await??;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let urgency = match (&alert.tier, alert.kind) {
+            (Some(tier), _) => match tier.severity {
+                Severity::Info => Urgency::Low,
+                Severity::Warning => Urgency::Normal,
+                Severity::Critical => Urgency::Critical,
+            },
+            (None, AlertKind::Escalation) => Urgency::Critical,
+            (None, _) if alert.silent => Urgency::Low,
+            (None, _) => Urgency::Normal,
+        };
+
+        self.show(&alert.message, urgency)
+            .await
+            .context("Failed to show desktop notification")
+    }
+
+    async fn send_periodic(&self, message: &Message) -> Result<()> {
+        self.show(message, Urgency::Low)
+            .await
+            .context("Failed to show desktop periodic update")
+    }
+
+    async fn send_report(&self, message: &Message) -> Result<()> {
+        self.show(message, Urgency::Low)
+            .await
+            .context("Failed to show desktop report")
+    }
+}
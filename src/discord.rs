@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::config::Severity;
+use crate::notifier::{Alert, AlertKind, Line, Message, Notifier, Span};
+
+/// How long one request may take, so an unresponsive server can't hold up the monitor
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Discord's limit on an embed title
+const MAX_TITLE_CHARS: usize = 256;
+
+/// Discord's limit on an embed description
+const MAX_DESCRIPTION_CHARS: usize = 4096;
+
+/// Embed colors
+const COLOR_INFO: u32 = 0x3498db;
+const COLOR_WARNING: u32 = 0xf1c40f;
+const COLOR_CRITICAL: u32 = 0xe74c3c;
+const COLOR_DEFAULT: u32 = 0x5865f2;
+const COLOR_NOTICE: u32 = 0x95a5a6;
+
+/// Posts notifications as embeds to a Discord channel webhook
+pub struct DiscordNotifier {
+    client: Client,
+    webhook_url: String,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            webhook_url: webhook_url.to_string(),
+        }
+    }
+
+    async fn send_embed(&self, message: &Message, color: u32) -> Result<()> {
+        let mut embed = json!({
+            "title": truncate(&message.title.plain_text(), MAX_TITLE_CHARS),
+            "description": truncate(&render(message), MAX_DESCRIPTION_CHARS),
+            "color": color,
+        });
+        // Discord shows the timestamp in each reader's own timezone
+        if let Some(time) = message.time {
+            embed["timestamp"] = json!(time.to_rfc3339());
+        }
+
+        self.client
+            .post(&self.webhook_url)
+            .timeout(REQUEST_TIMEOUT)
+            .json(&json!({ "embeds": [embed] }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let color = match (&alert.tier, alert.kind) {
+            (Some(tier), _) => match tier.severity {
+                Severity::Info => COLOR_INFO,
+                Severity::Warning => COLOR_WARNING,
+                Severity::Critical => COLOR_CRITICAL,
+            },
+            (None, AlertKind::Escalation) => COLOR_CRITICAL,
+            (None, AlertKind::Alert) => COLOR_DEFAULT,
+            (None, AlertKind::Notice) => COLOR_NOTICE,
+        };
+
+        self.send_embed(&alert.message, color)
+            .await
+            .context("Failed to send Discord alert")
+    }
+
+    async fn send_periodic(&self, message: &Message) -> Result<()> {
+        self.send_embed(message, COLOR_DEFAULT)
+            .await
+            .context("Failed to send Discord periodic update")
+    }
+
+    async fn send_report(&self, message: &Message) -> Result<()> {
+        self.send_embed(message, COLOR_DEFAULT)
+            .await
+            .context("Failed to send Discord report")
+    }
+}
+
+/// Embed description: the sections in Discord markdown, separated by blank lines
+fn render(message: &Message) -> String {
+    message
+        .sections
+        .iter()
+        .map(|section| {
+            section
+                .iter()
+                .map(render_line)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn render_line(line: &Line) -> String {
+    line.0
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) => escape_markdown(text),
+            Span::Bold(text) => format!("**{}**", escape_markdown(text)),
+            Span::Code(text) => format!("`{}`", text.replace('`', "'")),
+        })
+        .collect()
+}
+
+/// Cut `text` to at most `max` characters, marking the cut with an ellipsis
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// Escape characters Discord treats as markdown
+fn escape_markdown(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '*' | '_' | '`' | '~' | '|' | '>' | '\\' => format!("\\{}", c),
+            _ => c.to_string(),
+        })
+        .collect()
+}
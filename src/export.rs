@@ -0,0 +1,345 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::Write;
+
+use crate::database::{AlertRecord, RatioRecord, VolumeRatioRecord};
+
+/// File formats `export` writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            "parquet" if cfg!(feature = "parquet") => Ok(Self::Parquet),
+            "parquet" => bail!("Parquet export needs a build with the `parquet` feature"),
+            _ => bail!(
+                "Invalid format: {}. Must be 'csv', 'jsonl' or 'parquet'",
+                name
+            ),
+        }
+    }
+}
+
+/// Values of one exported column, `None` where a row has none
+enum Column {
+    Time(Vec<DateTime<Utc>>),
+    Text(Vec<Option<String>>),
+    Float(Vec<Option<f64>>),
+    Int(Vec<Option<i64>>),
+}
+
+/// Stored rows laid out by column, in the order they were passed in
+pub struct ExportTable {
+    /// Parquet schema name
+    #[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+    name: &'static str,
+    columns: Vec<(&'static str, Column)>,
+    rows: usize,
+}
+
+impl ExportTable {
+    pub fn snapshots(records: &[RatioRecord]) -> Self {
+        Self {
+            name: "ratio_snapshots",
+            columns: vec![
+                (
+                    "timestamp",
+                    Column::Time(records.iter().map(|r| r.timestamp).collect()),
+                ),
+                (
+                    "pair",
+                    Column::Text(records.iter().map(|r| Some(r.pair_name.clone())).collect()),
+                ),
+                (
+                    "symbol_a",
+                    Column::Text(records.iter().map(|r| Some(r.symbol_a.clone())).collect()),
+                ),
+                (
+                    "symbol_b",
+                    Column::Text(records.iter().map(|r| Some(r.symbol_b.clone())).collect()),
+                ),
+                (
+                    "price_a",
+                    Column::Float(records.iter().map(|r| Some(r.price_a)).collect()),
+                ),
+                (
+                    "price_b",
+                    Column::Float(records.iter().map(|r| Some(r.price_b)).collect()),
+                ),
+                (
+                    "ratio",
+                    Column::Float(records.iter().map(|r| Some(r.ratio)).collect()),
+                ),
+            ],
+            rows: records.len(),
+        }
+    }
+
+    pub fn alerts(records: &[AlertRecord]) -> Self {
+        Self {
+            name: "alerts",
+            columns: vec![
+                (
+                    "timestamp",
+                    Column::Time(records.iter().map(|r| r.timestamp).collect()),
+                ),
+                (
+                    "pair",
+                    Column::Text(records.iter().map(|r| Some(r.pair_name.clone())).collect()),
+                ),
+                (
+                    "ratio",
+                    Column::Float(records.iter().map(|r| Some(r.ratio)).collect()),
+                ),
+                (
+                    "change_percentage",
+                    Column::Float(records.iter().map(|r| Some(r.change_percentage)).collect()),
+                ),
+                (
+                    "threshold",
+                    Column::Float(records.iter().map(|r| Some(r.threshold)).collect()),
+                ),
+                (
+                    "direction",
+                    Column::Text(records.iter().map(|r| r.direction.clone()).collect()),
+                ),
+                (
+                    "price_a",
+                    Column::Float(records.iter().map(|r| r.price_a).collect()),
+                ),
+                (
+                    "price_b",
+                    Column::Float(records.iter().map(|r| r.price_b).collect()),
+                ),
+                (
+                    "window_secs",
+                    Column::Int(records.iter().map(|r| r.window_secs).collect()),
+                ),
+                (
+                    "severity",
+                    Column::Text(records.iter().map(|r| r.severity.clone()).collect()),
+                ),
+                (
+                    "metric",
+                    Column::Text(records.iter().map(|r| r.metric.clone()).collect()),
+                ),
+            ],
+            rows: records.len(),
+        }
+    }
+
+    pub fn volume_ratios(records: &[VolumeRatioRecord]) -> Self {
+        Self {
+            name: "volume_ratios",
+            columns: vec![
+                (
+                    "timestamp",
+                    Column::Time(records.iter().map(|r| r.timestamp).collect()),
+                ),
+                (
+                    "pair",
+                    Column::Text(records.iter().map(|r| Some(r.pair_name.clone())).collect()),
+                ),
+                (
+                    "symbol_a",
+                    Column::Text(records.iter().map(|r| Some(r.symbol_a.clone())).collect()),
+                ),
+                (
+                    "symbol_b",
+                    Column::Text(records.iter().map(|r| Some(r.symbol_b.clone())).collect()),
+                ),
+                (
+                    "volume",
+                    Column::Float(records.iter().map(|r| Some(r.volume)).collect()),
+                ),
+                (
+                    "effective_price_a",
+                    Column::Float(records.iter().map(|r| Some(r.effective_price_a)).collect()),
+                ),
+                (
+                    "effective_price_b",
+                    Column::Float(records.iter().map(|r| Some(r.effective_price_b)).collect()),
+                ),
+                (
+                    "ratio",
+                    Column::Float(records.iter().map(|r| Some(r.ratio)).collect()),
+                ),
+                (
+                    "slippage_a",
+                    Column::Float(records.iter().map(|r| Some(r.slippage_a)).collect()),
+                ),
+                (
+                    "slippage_b",
+                    Column::Float(records.iter().map(|r| Some(r.slippage_b)).collect()),
+                ),
+            ],
+            rows: records.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn write<W: Write + Send>(&self, format: ExportFormat, out: W) -> Result<()> {
+        match format {
+            ExportFormat::Csv => self.write_csv(out),
+            ExportFormat::Jsonl => self.write_jsonl(out),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => self.write_parquet(out),
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => unreachable!("Parquet needs the parquet feature"),
+        }
+    }
+
+    fn write_csv<W: Write>(&self, mut out: W) -> Result<()> {
+        let header: Vec<&str> = self.columns.iter().map(|(name, _)| *name).collect();
+        writeln!(out, "{}", header.join(","))?;
+
+        for row in 0..self.rows {
+            let fields: Vec<String> = self
+                .columns
+                .iter()
+                .map(|(_, column)| match column {
+                    Column::Time(values) => format_time(values[row]),
+                    Column::Text(values) => {
+                        values[row].as_deref().map(csv_field).unwrap_or_default()
+                    }
+                    Column::Float(values) => values[row].map(|v| v.to_string()).unwrap_or_default(),
+                    Column::Int(values) => values[row].map(|v| v.to_string()).unwrap_or_default(),
+                })
+                .collect();
+            writeln!(out, "{}", fields.join(","))?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+
+    fn write_jsonl<W: Write>(&self, mut out: W) -> Result<()> {
+        for row in 0..self.rows {
+            // Written field by field to keep the column order
+            let fields: Vec<String> = self
+                .columns
+                .iter()
+                .map(|(name, column)| {
+                    let value: serde_json::Value = match column {
+                        Column::Time(values) => format_time(values[row]).into(),
+                        Column::Text(values) => values[row].clone().into(),
+                        Column::Float(values) => values[row].into(),
+                        Column::Int(values) => values[row].into(),
+                    };
+                    format!("{:?}:{}", name, value)
+                })
+                .collect();
+            writeln!(out, "{{{}}}", fields.join(","))?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+
+    /// One row group, Snappy compressed. Timestamps are UTC microseconds
+    #[cfg(feature = "parquet")]
+    fn write_parquet<W: Write + Send>(&self, out: W) -> Result<()> {
+        use parquet::basic::Compression;
+        use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let fields: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(name, column)| match column {
+                Column::Time(_) => format!("REQUIRED INT64 {} (TIMESTAMP(MICROS,true));", name),
+                Column::Text(_) => format!("OPTIONAL BYTE_ARRAY {} (UTF8);", name),
+                Column::Float(_) => format!("OPTIONAL DOUBLE {};", name),
+                Column::Int(_) => format!("OPTIONAL INT64 {};", name),
+            })
+            .collect();
+        let schema =
+            parse_message_type(&format!("message {} {{ {} }}", self.name, fields.join(" ")))?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = SerializedFileWriter::new(out, Arc::new(schema), Arc::new(properties))?;
+
+        // Definition level 1 marks a value, 0 a null
+        fn split<T: Clone>(values: &[Option<T>]) -> (Vec<T>, Vec<i16>) {
+            let present = values.iter().flatten().cloned().collect();
+            let levels = values.iter().map(|v| v.is_some() as i16).collect();
+            (present, levels)
+        }
+
+        let mut row_group = writer.next_row_group()?;
+        for (_, column) in &self.columns {
+            let mut column_writer = row_group
+                .next_column()?
+                .expect("a column writer per schema field");
+            match column {
+                Column::Time(values) => {
+                    let micros: Vec<i64> = values.iter().map(|t| t.timestamp_micros()).collect();
+                    column_writer
+                        .typed::<Int64Type>()
+                        .write_batch(&micros, None, None)?;
+                }
+                Column::Text(values) => {
+                    let (present, levels) = split(values);
+                    let bytes: Vec<ByteArray> = present
+                        .iter()
+                        .map(|v| ByteArray::from(v.as_str()))
+                        .collect();
+                    column_writer.typed::<ByteArrayType>().write_batch(
+                        &bytes,
+                        Some(&levels),
+                        None,
+                    )?;
+                }
+                Column::Float(values) => {
+                    let (present, levels) = split(values);
+                    column_writer.typed::<DoubleType>().write_batch(
+                        &present,
+                        Some(&levels),
+                        None,
+                    )?;
+                }
+                Column::Int(values) => {
+                    let (present, levels) = split(values);
+                    column_writer.typed::<Int64Type>().write_batch(
+                        &present,
+                        Some(&levels),
+                        None,
+                    )?;
+                }
+            }
+            column_writer.close()?;
+        }
+        row_group.close()?;
+        writer.close()?;
+
+        Ok(())
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
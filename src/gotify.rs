@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::config::Severity;
+use crate::notifier::{Alert, AlertKind, Message, Notifier};
+
+/// How long one request may take, so an unresponsive server can't hold up the monitor
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Gotify priorities; the Android app stays silent below 4 and pops up from 8
+const PRIORITY_SILENT: u8 = 1;
+const PRIORITY_LOW: u8 = 2;
+const PRIORITY_DEFAULT: u8 = 4;
+const PRIORITY_HIGH: u8 = 5;
+const PRIORITY_URGENT: u8 = 8;
+
+/// Pushes notifications to a Gotify server as an application
+pub struct GotifyNotifier {
+    client: Client,
+    url: String,
+    app_token: String,
+}
+
+impl GotifyNotifier {
+    pub fn new(url: &str, app_token: &str) -> Self {
+        Self {
+            client: Client::new(),
+            url: format!("{}/message", url.trim_end_matches('/')),
+            app_token: app_token.to_string(),
+        }
+    }
+
+    async fn push(&self, message: &Message, priority: u8) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header("X-Gotify-Key", &self.app_token)
+            .json(&json!({
+                "title": message.title.plain_text(),
+                "message": message.body_text(),
+                "priority": priority,
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for GotifyNotifier {
+    fn name(&self) -> &'static str {
+        "gotify"
+    }
+
+    async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let priority = match (&alert.tier, alert.kind) {
+            (Some(tier), _) => match tier.severity {
+                Severity::Info => PRIORITY_LOW,
+                Severity::Warning => PRIORITY_HIGH,
+                Severity::Critical => PRIORITY_URGENT,
+            },
+            (None, AlertKind::Escalation) => PRIORITY_URGENT,
+            (None, _) if alert.silent => PRIORITY_LOW,
+            (None, AlertKind::Alert) => PRIORITY_HIGH,
+            (None, AlertKind::Notice) => PRIORITY_DEFAULT,
+        };
+
+        self.push(&alert.message, priority)
+            .await
+            .context("Failed to send Gotify alert")
+    }
+
+    async fn send_periodic(&self, message: &Message) -> Result<()> {
+        self.push(message, PRIORITY_SILENT)
+            .await
+            .context("Failed to send Gotify periodic update")
+    }
+
+    async fn send_report(&self, message: &Message) -> Result<()> {
+        self.push(message, PRIORITY_SILENT)
+            .await
+            .context("Failed to send Gotify report")
+    }
+}
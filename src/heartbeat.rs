@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use std::time::Duration;
+
+/// How long a ping may take before it is abandoned, so a slow monitoring
+/// service never holds up the next cycle
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Dead man's switch: pings an external URL (e.g. healthchecks.io) after each
+/// successful cycle, so that service alerts when the pings stop
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    client: Client,
+    url: String,
+}
+
+impl Heartbeat {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.to_string(),
+        }
+    }
+
+    pub async fn ping(&self) -> Result<()> {
+        self.client
+            .get(&self.url)
+            .timeout(PING_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to ping heartbeat URL {}", self.url))?;
+
+        Ok(())
+    }
+}
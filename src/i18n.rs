@@ -0,0 +1,692 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::config::Severity;
+
+/// Language of alerts, reports and bot replies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    De,
+}
+
+impl Language {
+    pub fn texts(self) -> &'static Texts {
+        match self {
+            Language::En => &EN,
+            Language::De => &DE,
+        }
+    }
+}
+
+/// Every user-facing string of one language. `{name}` placeholders are
+/// filled with `fill`; bot texts marked MarkdownV2 are sent as they are and
+/// must keep their escapes, with placeholders filled by `markdown::fill`.
+pub struct Texts {
+    // Alerts
+    pub ratio_alert: &'static str,
+    pub executable_ratio_alert: &'static str,
+    pub daily_change_alert: &'static str,
+    pub back_to_normal: &'static str,
+    pub still_breached: &'static str,
+    pub current_ratio: &'static str,
+    pub executable_ratio: &'static str,
+    pub at_volume: &'static str,
+    pub change: &'static str,
+    pub change_24h: &'static str,
+    pub daily_open: &'static str,
+    pub threshold: &'static str,
+    pub crossed: &'static str,
+    pub slippage: &'static str,
+    pub excursion_lasted: &'static str,
+    pub breached_for: &'static str,
+    pub in_window: &'static str,
+    pub executable: &'static str,
+    pub on_the_day: &'static str,
+    pub info: &'static str,
+    pub warning: &'static str,
+    pub critical: &'static str,
+    pub alert_digest: &'static str,
+    pub alert_count: &'static str,
+    pub alerts_suppressed: &'static str,
+
+    // Updates and reports
+    pub periodic_update: &'static str,
+    pub live_status: &'static str,
+    pub monitoring_gaps: &'static str,
+    pub gap_note: &'static str,
+    pub backfilled: &'static str,
+    pub backfill_failed: &'static str,
+    pub daily_summary: &'static str,
+    pub last_24h: &'static str,
+    pub open: &'static str,
+    pub close: &'static str,
+    pub min: &'static str,
+    pub max: &'static str,
+    pub biggest_swing: &'static str,
+    pub alerts: &'static str,
+    pub weekly_report: &'static str,
+    pub broke_out: &'static str,
+    pub pair_broke: &'static str,
+    pub above: &'static str,
+    pub below: &'static str,
+    pub above_and_below: &'static str,
+    pub avg: &'static str,
+    pub week_over_week: &'static str,
+    pub volatility: &'static str,
+    pub versus: &'static str,
+    pub last_week: &'static str,
+    pub no_prior_week: &'static str,
+    pub range: &'static str,
+    pub no_data_recorded: &'static str,
+    pub time: &'static str,
+
+    // Monitor status
+    pub no_data_for: &'static str,
+    pub failed_checks: &'static str,
+    pub last_error: &'static str,
+    pub data_resumed: &'static str,
+    pub resumed_after: &'static str,
+    pub purged: &'static str,
+    pub purged_snapshots: &'static str,
+    pub purged_alerts: &'static str,
+    pub purged_volume_ratios: &'static str,
+    pub purged_order_books: &'static str,
+    pub purged_checks: &'static str,
+    pub monitor_started: &'static str,
+    pub monitor_stopping: &'static str,
+    pub summary_pairs: &'static str,
+    pub summary_rule: &'static str,
+    pub summary_daily_open: &'static str,
+    /// Put before the title of a notification delivered late
+    pub delayed: &'static str,
+
+    // Bot
+    /// MarkdownV2
+    pub bot_welcome: &'static str,
+    /// MarkdownV2
+    pub bot_help: &'static str,
+    pub bot_use_start: &'static str,
+    pub bot_select_pair: &'static str,
+    pub bot_main_menu: &'static str,
+    /// MarkdownV2
+    pub bot_calculating: &'static str,
+    /// MarkdownV2
+    pub bot_analyzing: &'static str,
+    pub bot_ratio: &'static str,
+    pub bot_last_alert: &'static str,
+    /// MarkdownV2
+    pub bot_ratio_error: &'static str,
+    pub bot_volume_analysis: &'static str,
+    pub bot_pair: &'static str,
+    pub bot_volume: &'static str,
+    pub bot_effective_price: &'static str,
+    pub bot_slippage: &'static str,
+    /// MarkdownV2
+    pub bot_volume_error: &'static str,
+    /// MarkdownV2
+    pub bot_paused: &'static str,
+    /// MarkdownV2
+    pub bot_resumed: &'static str,
+    pub bot_configured_pairs: &'static str,
+    pub bot_alert_details: &'static str,
+    pub bot_legs: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+    pub button_get_ratios: &'static str,
+    pub button_view_pairs: &'static str,
+    pub button_back: &'static str,
+    pub button_volume_analysis: &'static str,
+    pub button_pause: &'static str,
+    pub button_resume: &'static str,
+    pub button_back_to_pairs: &'static str,
+    pub button_main_menu: &'static str,
+    pub button_snooze: &'static str,
+    /// MarkdownV2
+    pub bot_snoozed: &'static str,
+    pub button_ack: &'static str,
+    pub bot_acknowledged: &'static str,
+    /// MarkdownV2
+    pub bot_subscribed: &'static str,
+    /// MarkdownV2
+    pub bot_already_subscribed: &'static str,
+    /// MarkdownV2
+    pub bot_unsubscribed: &'static str,
+    /// MarkdownV2
+    pub bot_not_subscribed: &'static str,
+    pub bot_subscriptions: &'static str,
+    pub bot_no_subscriptions: &'static str,
+    /// MarkdownV2
+    pub bot_unknown_pair: &'static str,
+    /// MarkdownV2
+    pub bot_command_usage: &'static str,
+    pub bot_not_admin: &'static str,
+    pub bot_cancelled: &'static str,
+    pub bot_add_pair_name: &'static str,
+    pub bot_add_pair_invalid_name: &'static str,
+    /// MarkdownV2
+    pub bot_add_pair_exists: &'static str,
+    /// MarkdownV2
+    pub bot_add_pair_symbol_a: &'static str,
+    /// MarkdownV2
+    pub bot_add_pair_symbol_b: &'static str,
+    /// MarkdownV2
+    pub bot_add_pair_invalid_symbol: &'static str,
+    /// MarkdownV2
+    pub bot_add_pair_check_failed: &'static str,
+    pub bot_add_pair_same_symbol: &'static str,
+    /// MarkdownV2
+    pub bot_pair_added: &'static str,
+    /// MarkdownV2
+    pub bot_pair_removed: &'static str,
+    /// MarkdownV2
+    pub bot_pair_not_removable: &'static str,
+    pub button_thresholds: &'static str,
+    pub button_reset_rule: &'static str,
+    pub bot_rule: &'static str,
+    pub bot_rule_overridden: &'static str,
+    pub bot_invalid_thresholds: &'static str,
+    pub bot_slippage_analysis: &'static str,
+    pub bot_buy: &'static str,
+    pub bot_sell: &'static str,
+    pub bot_mid_price: &'static str,
+    pub bot_depth_consumed: &'static str,
+    pub bot_total_cost: &'static str,
+    pub bot_slippage_pick_volume: &'static str,
+    /// MarkdownV2
+    pub bot_unknown_symbol: &'static str,
+    /// MarkdownV2
+    pub bot_slippage_error: &'static str,
+    pub bot_history: &'static str,
+    pub bot_history_range: &'static str,
+    pub bot_history_recent: &'static str,
+    /// MarkdownV2
+    pub bot_no_history: &'static str,
+    pub button_stats: &'static str,
+    pub bot_statistics: &'static str,
+    pub bot_samples: &'static str,
+    pub bot_min: &'static str,
+    pub bot_max: &'static str,
+    pub bot_avg: &'static str,
+    pub bot_range: &'static str,
+    pub bot_stddev: &'static str,
+    pub bot_net_change: &'static str,
+    pub bot_recent_alerts: &'static str,
+    pub bot_no_alerts: &'static str,
+    pub bot_select_category: &'static str,
+    pub bot_other_category: &'static str,
+    pub button_all_pairs: &'static str,
+    pub button_previous_page: &'static str,
+    pub button_next_page: &'static str,
+    pub button_custom_volume: &'static str,
+    /// MarkdownV2
+    pub bot_volume_prompt: &'static str,
+    pub bot_invalid_volume: &'static str,
+    pub button_favorites: &'static str,
+    pub button_add_favorite: &'static str,
+    pub button_remove_favorite: &'static str,
+    pub bot_favorites: &'static str,
+    pub bot_no_favorites: &'static str,
+    pub bot_favorite_added: &'static str,
+    pub bot_favorite_removed: &'static str,
+    pub bot_export: &'static str,
+    pub bot_export_too_large: &'static str,
+    pub bot_rate_limited: &'static str,
+    pub button_live: &'static str,
+    pub bot_live_until: &'static str,
+    pub bot_already_live: &'static str,
+}
+
+impl Texts {
+    pub fn severity(&self, severity: Severity) -> &'static str {
+        match severity {
+            Severity::Info => self.info,
+            Severity::Warning => self.warning,
+            Severity::Critical => self.critical,
+        }
+    }
+
+    /// "up" or "down" as stored with alerts
+    pub fn direction(&self, direction: &str) -> &'static str {
+        if direction == "down" {
+            self.down
+        } else {
+            self.up
+        }
+    }
+}
+
+/// Replace each `{name}` in `text` with its value
+pub fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+pub static EN: Texts = Texts {
+    ratio_alert: "Ratio Alert: {pair}",
+    executable_ratio_alert: "Executable Ratio Alert: {pair}",
+    daily_change_alert: "{pair} is {change}% on the day",
+    back_to_normal: "Back to Normal: {pair}",
+    still_breached: "STILL BREACHED: {pair}",
+    current_ratio: "Current Ratio: ",
+    executable_ratio: "Executable Ratio: ",
+    at_volume: " at volume ",
+    change: "Change: ",
+    change_24h: "24h Change: ",
+    daily_open: "Daily Open: ",
+    threshold: "Threshold: ",
+    crossed: " (crossed {levels})",
+    slippage: " (slippage {slippage}%)",
+    excursion_lasted: "Excursion lasted: {duration}",
+    breached_for: "Breached for: ",
+    in_window: "in {window}",
+    executable: "(executable)",
+    on_the_day: "on the day",
+    info: "INFO",
+    warning: "WARNING",
+    critical: "CRITICAL",
+    alert_digest: "Alert Digest",
+    alert_count: " ({count} alerts)",
+    alerts_suppressed: "🔇 {count} additional alerts suppressed (limit: {limit} alerts per hour)",
+
+    periodic_update: "Periodic Ratio Update",
+    live_status: "Live Ratios",
+    monitoring_gaps: "Monitoring gaps",
+    gap_note: "{pair}: no data for {duration} since {since}, {outcome}",
+    backfilled: "backfilled {count} snapshots from {interval} candles",
+    backfill_failed: "backfill failed",
+    daily_summary: "Daily Summary",
+    last_24h: " (last 24h)",
+    open: "Open ",
+    close: " → Close ",
+    min: "Min ",
+    max: " / Max ",
+    biggest_swing: "Biggest swing: ",
+    alerts: " · Alerts: {count}",
+    weekly_report: "Weekly Report",
+    broke_out: "Broke out of last week's range",
+    pair_broke: "• {pair} broke {direction}",
+    above: "above",
+    below: "below",
+    above_and_below: "above and below",
+    avg: "Avg ",
+    week_over_week: " ({change}% w/w)",
+    volatility: "Volatility ",
+    versus: " vs ",
+    last_week: " last week",
+    no_prior_week: ", no data for the prior week",
+    range: "Range ",
+    no_data_recorded: "No data recorded",
+    time: "Time",
+
+    no_data_for: "No data for {pair} for {duration}",
+    failed_checks: "Failed checks: ",
+    last_error: "Last error: {error}",
+    data_resumed: "Data resumed for {pair}",
+    resumed_after: " after {duration}",
+    purged: "🧹 Purged old data:",
+    purged_snapshots: "Ratio snapshots: {count} (older than {days} days)",
+    purged_alerts: "Alerts: {count} (older than {days} days)",
+    purged_volume_ratios: "Volume ratios: {count} (older than {days} days)",
+    purged_order_books: "Order books: {count} (older than {days} days)",
+    purged_checks: "Checks: {count} (older than {days} days)",
+    monitor_started: "✅ Ratio-Noti monitor started.",
+    monitor_stopping: "🛑 Ratio-Noti monitor stopping.",
+    summary_pairs: "Monitoring {count} pairs every {secs}s",
+    summary_rule: "{thresholds} in {secs}s",
+    summary_daily_open: "Daily open: {thresholds}",
+    delayed: "⏳ Delayed: ",
+
+    bot_welcome: "👋 Welcome to Ratio\\-Noti Bot\\!\n\n\
+        I can help you monitor cryptocurrency price ratios from Binance\\.\n\n\
+        *Available Commands:*\n\
+        /pairs \\- View all configured ratio pairs\n\
+        /ratio \\- Get current ratios\n\
+        /subscribe \\- Get a pair's alerts in this chat\n\
+        /help \\- Show this help message\n\n\
+        Click the buttons below or use commands to get started\\!",
+    bot_help: "🔍 *Ratio\\-Noti Bot Help*\n\n\
+        *Commands:*\n\
+        /start \\- Start the bot\n\
+        /pairs \\- Show all configured pairs\n\
+        /ratio \\- Get current ratios for a pair\n\
+        /subscribe <pair\\> \\- Get the pair's alerts in this chat\n\
+        /unsubscribe <pair\\> \\- Stop getting the pair's alerts\n\
+        /subscriptions \\- Show the pairs this chat is subscribed to\n\
+        /addpair \\- Add a ratio pair \\(admins\\)\n\
+        /removepair <pair\\> \\- Remove a pair added with /addpair \\(admins\\)\n\
+        /cancel \\- Cancel adding a pair or entering a volume\n\
+        /setthreshold <pair\\> \\[pct\\.\\.\\.\\] \\- Show or set a pair's alert thresholds \\(admins\\)\n\
+        /slippage <symbol\\> \\[volume\\] \\[buy\\|sell\\] \\- Analyze the slippage of a trade\n\
+        /history <pair\\> \\[hours\\] \\- Recent ratios with a chart\n\
+        /stats <pair\\> \\[hours\\] \\- Min, max and average of a pair's ratio\n\
+        /alerts \\[pair\\] \\- Most recent alerts\n\
+        /favorites \\- Ratios of your favorite pairs\n\
+        /export <pair\\> \\[hours\\] \\- A pair's ratio history as CSV\n\
+        /help \\- Show this message\n\n\
+        *Features:*\n\
+        ✅ Simple price ratios\n\
+        ✅ Volume\\-based calculations\n\
+        ✅ Real\\-time data from Binance\n\
+        ✅ Interactive pair selection",
+    bot_use_start: "Use /start to see available commands or click the buttons below:",
+    bot_select_pair: "📊 Select a ratio pair:",
+    bot_main_menu: "Main menu:",
+    bot_calculating: "⏳ Calculating ratio\\.\\.\\.",
+    bot_analyzing: "⏳ Analyzing order book\\.\\.\\.",
+    bot_ratio: "Ratio:",
+    bot_last_alert: "Last alert:",
+    bot_ratio_error: "❌ Error calculating ratio: {error}",
+    bot_volume_analysis: "Volume-Based Analysis",
+    bot_pair: "Pair:",
+    bot_volume: "Volume:",
+    bot_effective_price: "Effective Price:",
+    bot_slippage: "Slippage:",
+    bot_volume_error: "❌ Error analyzing volume: {error}",
+    bot_paused: "⏸ Paused monitoring of *{pair}*\\. No checks or alerts until it is resumed\\.",
+    bot_resumed: "▶️ Resumed monitoring of *{pair}*",
+    bot_configured_pairs: "Configured Ratio Pairs",
+    bot_alert_details: "Change: {change}%{window} (threshold: {threshold}%)",
+    bot_legs: "Legs: {price_a} / {price_b}",
+    up: "up",
+    down: "down",
+    button_get_ratios: "📊 Get Ratios",
+    button_view_pairs: "📋 View Pairs",
+    button_back: "« Back",
+    button_volume_analysis: "📊 {volume}",
+    button_pause: "⏸ Pause Monitoring",
+    button_resume: "▶️ Resume Monitoring",
+    button_back_to_pairs: "« Back to Pairs",
+    button_main_menu: "« Main Menu",
+    button_snooze: "🔕 Snooze {duration}",
+    bot_snoozed: "🔕 Alerts for *{pair}* snoozed until {until}",
+    button_ack: "✅ Ack",
+    bot_acknowledged: "✅ Acknowledged by {user} at {time}",
+    bot_subscribed: "🔔 Subscribed to *{pair}*\\. Its alerts will be sent to this chat\\.",
+    bot_already_subscribed: "This chat is already subscribed to *{pair}*\\.",
+    bot_unsubscribed: "🔕 Unsubscribed from *{pair}*\\.",
+    bot_not_subscribed: "This chat isn't subscribed to *{pair}*\\.",
+    bot_subscriptions: "Subscriptions",
+    bot_no_subscriptions: "This chat has no subscriptions. Use /subscribe <pair> to add one.",
+    bot_unknown_pair: "❌ Unknown pair: *{pair}*\\. Use /pairs to see the configured pairs\\.",
+    bot_command_usage: "Usage: {usage}",
+    bot_not_admin: "⛔ Only admins can change pairs and thresholds.",
+    bot_cancelled: "Cancelled.",
+    bot_add_pair_name: "➕ Send a name for the new pair, e.g. BTC/ETH, or /cancel.",
+    bot_add_pair_invalid_name: "❌ Names are up to 32 letters, digits, -, _ or /. Send another name, or /cancel.",
+    bot_add_pair_exists: "❌ A pair named *{pair}* already exists\\. Send another name, or /cancel\\.",
+    bot_add_pair_symbol_a: "Send the first symbol of *{pair}*, e\\.g\\. BTCUSDT\\.",
+    bot_add_pair_symbol_b: "Send the second symbol of *{pair}*, e\\.g\\. ETHUSDT\\.",
+    bot_add_pair_invalid_symbol: "❌ *{symbol}* isn't trading on Binance\\. Send another symbol, or /cancel\\.",
+    bot_add_pair_check_failed: "❌ Couldn't check *{symbol}* on Binance: {error}\\. Send it again, or /cancel\\.",
+    bot_add_pair_same_symbol: "❌ The second symbol must differ from the first. Send another symbol, or /cancel.",
+    bot_pair_added: "✅ Added *{pair}* \\({symbol_a} / {symbol_b}\\)\\. The monitor checks it from its next cycle\\.",
+    bot_pair_removed: "🗑 Removed *{pair}*\\.",
+    bot_pair_not_removable: "❌ *{pair}* is defined in the config file and can only be removed there\\.",
+    button_thresholds: "⚙️ Thresholds",
+    button_reset_rule: "↩️ Reset",
+    bot_rule: "{thresholds} over {window}",
+    bot_rule_overridden: "Set from the bot; Reset restores the configured rules",
+    bot_invalid_thresholds: "❌ Thresholds are positive percentages, e.g. /setthreshold BTC/ETH 2 5",
+    bot_slippage_analysis: "Slippage: {side} {symbol}",
+    bot_buy: "Buy",
+    bot_sell: "Sell",
+    bot_mid_price: "Mid Price:",
+    bot_depth_consumed: "Depth Consumed:",
+    bot_total_cost: "Total Cost:",
+    bot_slippage_pick_volume: "💧 {symbol} trades at {price}. Pick a trade size, worth about 1k to 1M of the quote currency, or send /slippage {symbol} <volume> [buy|sell]:",
+    bot_unknown_symbol: "❌ *{symbol}* isn't trading on Binance\\.",
+    bot_slippage_error: "❌ Error analyzing slippage: {error}",
+    bot_history: "{pair}, last {hours}h",
+    bot_history_range: "Change: {change}% · Low: {low} · High: {high}",
+    bot_history_recent: "Recent values:",
+    bot_no_history: "No ratios recorded for *{pair}* in the last {hours}h\\.",
+    button_stats: "📈 Stats",
+    bot_statistics: "Statistics: {pair}, last {hours}h",
+    bot_samples: "Samples:",
+    bot_min: "Min:",
+    bot_max: "Max:",
+    bot_avg: "Avg:",
+    bot_range: "Range:",
+    bot_stddev: "Std dev:",
+    bot_net_change: "Change:",
+    bot_recent_alerts: "Recent Alerts",
+    bot_no_alerts: "No alerts recorded yet.",
+    bot_select_category: "📂 Select a category:",
+    bot_other_category: "Other",
+    button_all_pairs: "📋 All pairs",
+    button_previous_page: "‹ Prev",
+    button_next_page: "Next ›",
+    button_custom_volume: "✏️ Custom volume",
+    bot_volume_prompt: "Send a volume for *{pair}*, in units of its first symbol, or /cancel\\.",
+    bot_invalid_volume: "❌ Send a positive number, or /cancel.",
+    button_favorites: "⭐ Favorites",
+    button_add_favorite: "☆ Favorite",
+    button_remove_favorite: "⭐ Unfavorite",
+    bot_favorites: "Favorites",
+    bot_no_favorites: "You have no favorites yet. Open a pair with /ratio and press ☆ Favorite to add it.",
+    bot_favorite_added: "⭐ Added {pair} to your favorites",
+    bot_favorite_removed: "Removed {pair} from your favorites",
+    bot_export: "{pair}, last {hours}h: {count} snapshots",
+    bot_export_too_large: "❌ The last {hours}h are too large to send. Export fewer hours.",
+    bot_rate_limited: "⏳ Please slow down a little. Try again in {secs} seconds.",
+    button_live: "🔄 Live ({duration})",
+    bot_live_until: "🔄 Live until {time}",
+    bot_already_live: "This ratio is already live.",
+};
+
+pub static DE: Texts = Texts {
+    ratio_alert: "Ratio-Alarm: {pair}",
+    executable_ratio_alert: "Ausführbare Ratio – Alarm: {pair}",
+    daily_change_alert: "{pair} steht {change}% im Tagesverlauf",
+    back_to_normal: "Wieder normal: {pair}",
+    still_breached: "WEITERHIN ÜBERSCHRITTEN: {pair}",
+    current_ratio: "Aktuelle Ratio: ",
+    executable_ratio: "Ausführbare Ratio: ",
+    at_volume: " bei Volumen ",
+    change: "Änderung: ",
+    change_24h: "24h-Änderung: ",
+    daily_open: "Tageseröffnung: ",
+    threshold: "Schwelle: ",
+    crossed: " (überschritten: {levels})",
+    slippage: " (Slippage {slippage}%)",
+    excursion_lasted: "Ausschlag dauerte: {duration}",
+    breached_for: "Überschritten seit: ",
+    in_window: "in {window}",
+    executable: "(ausführbar)",
+    on_the_day: "im Tagesverlauf",
+    info: "INFO",
+    warning: "WARNUNG",
+    critical: "KRITISCH",
+    alert_digest: "Alarm-Übersicht",
+    alert_count: " ({count} Alarme)",
+    alerts_suppressed: "🔇 {count} weitere Alarme unterdrückt (Limit: {limit} Alarme pro Stunde)",
+
+    periodic_update: "Regelmäßiges Ratio-Update",
+    live_status: "Live-Ratios",
+    monitoring_gaps: "Überwachungslücken",
+    gap_note: "{pair}: keine Daten für {duration} seit {since}, {outcome}",
+    backfilled: "{count} Snapshots aus {interval}-Kerzen nachgeladen",
+    backfill_failed: "Nachladen fehlgeschlagen",
+    daily_summary: "Tageszusammenfassung",
+    last_24h: " (letzte 24h)",
+    open: "Eröffnung ",
+    close: " → Schluss ",
+    min: "Min ",
+    max: " / Max ",
+    biggest_swing: "Größter Ausschlag: ",
+    alerts: " · Alarme: {count}",
+    weekly_report: "Wochenbericht",
+    broke_out: "Aus der Spanne der Vorwoche ausgebrochen",
+    pair_broke: "• {pair} brach {direction} aus",
+    above: "nach oben",
+    below: "nach unten",
+    above_and_below: "nach oben und unten",
+    avg: "Ø ",
+    week_over_week: " ({change}% ggü. Vorwoche)",
+    volatility: "Volatilität ",
+    versus: " ggü. ",
+    last_week: " in der Vorwoche",
+    no_prior_week: ", keine Daten für die Vorwoche",
+    range: "Spanne ",
+    no_data_recorded: "Keine Daten aufgezeichnet",
+    time: "Zeit",
+
+    no_data_for: "Keine Daten für {pair} seit {duration}",
+    failed_checks: "Fehlgeschlagene Abfragen: ",
+    last_error: "Letzter Fehler: {error}",
+    data_resumed: "Daten für {pair} wieder verfügbar",
+    resumed_after: " nach {duration}",
+    purged: "🧹 Alte Daten gelöscht:",
+    purged_snapshots: "Ratio-Snapshots: {count} (älter als {days} Tage)",
+    purged_alerts: "Alarme: {count} (älter als {days} Tage)",
+    purged_volume_ratios: "Volumen-Ratios: {count} (älter als {days} Tage)",
+    purged_order_books: "Orderbücher: {count} (älter als {days} Tage)",
+    purged_checks: "Prüfungen: {count} (älter als {days} Tage)",
+    monitor_started: "✅ Ratio-Noti-Monitor gestartet.",
+    monitor_stopping: "🛑 Ratio-Noti-Monitor wird beendet.",
+    summary_pairs: "Überwache {count} Paare alle {secs}s",
+    summary_rule: "{thresholds} in {secs}s",
+    summary_daily_open: "Tageseröffnung: {thresholds}",
+    delayed: "⏳ Verspätet: ",
+
+    bot_welcome: "👋 Willkommen beim Ratio\\-Noti\\-Bot\\!\n\n\
+        Ich helfe dir, Preisverhältnisse von Kryptowährungen auf Binance zu überwachen\\.\n\n\
+        *Befehle:*\n\
+        /pairs \\- Alle konfigurierten Paare anzeigen\n\
+        /ratio \\- Aktuelle Ratios abrufen\n\
+        /subscribe \\- Alarme eines Paares in diesem Chat erhalten\n\
+        /help \\- Diese Hilfe anzeigen\n\n\
+        Nutze die Schaltflächen unten oder die Befehle, um loszulegen\\!",
+    bot_help: "🔍 *Ratio\\-Noti\\-Bot Hilfe*\n\n\
+        *Befehle:*\n\
+        /start \\- Bot starten\n\
+        /pairs \\- Alle konfigurierten Paare anzeigen\n\
+        /ratio \\- Aktuelle Ratio eines Paares abrufen\n\
+        /subscribe <Paar\\> \\- Alarme des Paares in diesem Chat erhalten\n\
+        /unsubscribe <Paar\\> \\- Keine Alarme des Paares mehr erhalten\n\
+        /subscriptions \\- Abonnierte Paare dieses Chats anzeigen\n\
+        /addpair \\- Ein Paar hinzufügen \\(Admins\\)\n\
+        /removepair <Paar\\> \\- Ein mit /addpair hinzugefügtes Paar entfernen \\(Admins\\)\n\
+        /cancel \\- Hinzufügen eines Paares oder Eingabe eines Volumens abbrechen\n\
+        /setthreshold <Paar\\> \\[Prozent\\.\\.\\.\\] \\- Alarmschwellen eines Paares anzeigen oder setzen \\(Admins\\)\n\
+        /slippage <Symbol\\> \\[Volumen\\] \\[buy\\|sell\\] \\- Slippage einer Order analysieren\n\
+        /history <Paar\\> \\[Stunden\\] \\- Letzte Ratios mit Diagramm\n\
+        /stats <Paar\\> \\[Stunden\\] \\- Minimum, Maximum und Schnitt der Ratio eines Paares\n\
+        /alerts \\[Paar\\] \\- Letzte Alarme\n\
+        /favorites \\- Ratios deiner Favoriten\n\
+        /export <Paar\\> \\[Stunden\\] \\- Ratio\\-Verlauf eines Paares als CSV\n\
+        /help \\- Diese Hilfe anzeigen\n\n\
+        *Funktionen:*\n\
+        ✅ Einfache Preisverhältnisse\n\
+        ✅ Volumenbasierte Berechnung\n\
+        ✅ Echtzeitdaten von Binance\n\
+        ✅ Interaktive Paarauswahl",
+    bot_use_start: "Mit /start siehst du die verfügbaren Befehle, oder nutze die Schaltflächen unten:",
+    bot_select_pair: "📊 Wähle ein Paar:",
+    bot_main_menu: "Hauptmenü:",
+    bot_calculating: "⏳ Berechne Ratio\\.\\.\\.",
+    bot_analyzing: "⏳ Analysiere Orderbuch\\.\\.\\.",
+    bot_ratio: "Ratio:",
+    bot_last_alert: "Letzter Alarm:",
+    bot_ratio_error: "❌ Fehler bei der Berechnung der Ratio: {error}",
+    bot_volume_analysis: "Volumenbasierte Analyse",
+    bot_pair: "Paar:",
+    bot_volume: "Volumen:",
+    bot_effective_price: "Effektiver Preis:",
+    bot_slippage: "Slippage:",
+    bot_volume_error: "❌ Fehler bei der Volumenanalyse: {error}",
+    bot_paused: "⏸ Überwachung von *{pair}* pausiert\\. Keine Abfragen oder Alarme bis zur Fortsetzung\\.",
+    bot_resumed: "▶️ Überwachung von *{pair}* fortgesetzt",
+    bot_configured_pairs: "Konfigurierte Paare",
+    bot_alert_details: "Änderung: {change}%{window} (Schwelle: {threshold}%)",
+    bot_legs: "Legs: {price_a} / {price_b}",
+    up: "steigend",
+    down: "fallend",
+    button_get_ratios: "📊 Ratios abrufen",
+    button_view_pairs: "📋 Paare anzeigen",
+    button_back: "« Zurück",
+    button_volume_analysis: "📊 {volume}",
+    button_pause: "⏸ Überwachung pausieren",
+    button_resume: "▶️ Überwachung fortsetzen",
+    button_back_to_pairs: "« Zurück zu den Paaren",
+    button_main_menu: "« Hauptmenü",
+    button_snooze: "🔕 {duration} stumm",
+    bot_snoozed: "🔕 Alarme für *{pair}* stummgeschaltet bis {until}",
+    button_ack: "✅ Bestätigen",
+    bot_acknowledged: "✅ Bestätigt von {user} um {time}",
+    bot_subscribed: "🔔 *{pair}* abonniert\\. Seine Alarme kommen in diesen Chat\\.",
+    bot_already_subscribed: "Dieser Chat hat *{pair}* bereits abonniert\\.",
+    bot_unsubscribed: "🔕 Abo für *{pair}* beendet\\.",
+    bot_not_subscribed: "Dieser Chat hat *{pair}* nicht abonniert\\.",
+    bot_subscriptions: "Abonnements",
+    bot_no_subscriptions: "Dieser Chat hat keine Abonnements. Mit /subscribe <Paar> fügst du eines hinzu.",
+    bot_unknown_pair: "❌ Unbekanntes Paar: *{pair}*\\. Mit /pairs siehst du die konfigurierten Paare\\.",
+    bot_command_usage: "Verwendung: {usage}",
+    bot_not_admin: "⛔ Nur Admins können Paare und Schwellen ändern.",
+    bot_cancelled: "Abgebrochen.",
+    bot_add_pair_name: "➕ Sende einen Namen für das neue Paar, z. B. BTC/ETH, oder /cancel.",
+    bot_add_pair_invalid_name: "❌ Namen bestehen aus bis zu 32 Buchstaben, Ziffern, -, _ oder /. Sende einen anderen Namen oder /cancel.",
+    bot_add_pair_exists: "❌ Ein Paar namens *{pair}* gibt es bereits\\. Sende einen anderen Namen oder /cancel\\.",
+    bot_add_pair_symbol_a: "Sende das erste Symbol von *{pair}*, z\\. B\\. BTCUSDT\\.",
+    bot_add_pair_symbol_b: "Sende das zweite Symbol von *{pair}*, z\\. B\\. ETHUSDT\\.",
+    bot_add_pair_invalid_symbol: "❌ *{symbol}* wird auf Binance nicht gehandelt\\. Sende ein anderes Symbol oder /cancel\\.",
+    bot_add_pair_check_failed: "❌ *{symbol}* konnte auf Binance nicht geprüft werden: {error}\\. Sende es erneut oder /cancel\\.",
+    bot_add_pair_same_symbol: "❌ Das zweite Symbol muss sich vom ersten unterscheiden. Sende ein anderes Symbol oder /cancel.",
+    bot_pair_added: "✅ *{pair}* \\({symbol_a} / {symbol_b}\\) hinzugefügt\\. Der Monitor prüft es ab dem nächsten Durchlauf\\.",
+    bot_pair_removed: "🗑 *{pair}* entfernt\\.",
+    bot_pair_not_removable: "❌ *{pair}* ist in der Konfigurationsdatei definiert und kann nur dort entfernt werden\\.",
+    button_thresholds: "⚙️ Schwellen",
+    button_reset_rule: "↩️ Zurücksetzen",
+    bot_rule: "{thresholds} über {window}",
+    bot_rule_overridden: "Im Bot gesetzt; Zurücksetzen stellt die konfigurierten Regeln wieder her",
+    bot_invalid_thresholds: "❌ Schwellen sind positive Prozentwerte, z. B. /setthreshold BTC/ETH 2 5",
+    bot_slippage_analysis: "Slippage: {side} {symbol}",
+    bot_buy: "Kauf",
+    bot_sell: "Verkauf",
+    bot_mid_price: "Mittelkurs:",
+    bot_depth_consumed: "Verbrauchte Tiefe:",
+    bot_total_cost: "Gesamtkosten:",
+    bot_slippage_pick_volume: "💧 {symbol} notiert bei {price}. Wähle eine Ordergröße im Wert von etwa 1k bis 1M der Kurswährung, oder sende /slippage {symbol} <Volumen> [buy|sell]:",
+    bot_unknown_symbol: "❌ *{symbol}* wird auf Binance nicht gehandelt\\.",
+    bot_slippage_error: "❌ Fehler bei der Slippage-Analyse: {error}",
+    bot_history: "{pair}, letzte {hours} Std.",
+    bot_history_range: "Änderung: {change}% · Tief: {low} · Hoch: {high}",
+    bot_history_recent: "Letzte Werte:",
+    bot_no_history: "Keine Ratios für *{pair}* in den letzten {hours} Std\\. aufgezeichnet\\.",
+    button_stats: "📈 Statistik",
+    bot_statistics: "Statistik: {pair}, letzte {hours} Std.",
+    bot_samples: "Messwerte:",
+    bot_min: "Min:",
+    bot_max: "Max:",
+    bot_avg: "Schnitt:",
+    bot_range: "Spanne:",
+    bot_stddev: "Std.-Abw.:",
+    bot_net_change: "Änderung:",
+    bot_recent_alerts: "Letzte Alarme",
+    bot_no_alerts: "Noch keine Alarme aufgezeichnet.",
+    bot_select_category: "📂 Wähle eine Kategorie:",
+    bot_other_category: "Sonstige",
+    button_all_pairs: "📋 Alle Paare",
+    button_previous_page: "‹ Zurück",
+    button_next_page: "Weiter ›",
+    button_custom_volume: "✏️ Eigenes Volumen",
+    bot_volume_prompt: "Sende ein Volumen für *{pair}* in Einheiten seines ersten Symbols, oder /cancel\\.",
+    bot_invalid_volume: "❌ Sende eine positive Zahl, oder /cancel.",
+    button_favorites: "⭐ Favoriten",
+    button_add_favorite: "☆ Favorit",
+    button_remove_favorite: "⭐ Kein Favorit",
+    bot_favorites: "Favoriten",
+    bot_no_favorites: "Du hast noch keine Favoriten. Öffne ein Paar mit /ratio und tippe auf ☆ Favorit.",
+    bot_favorite_added: "⭐ {pair} zu deinen Favoriten hinzugefügt",
+    bot_favorite_removed: "{pair} aus deinen Favoriten entfernt",
+    bot_export: "{pair}, letzte {hours}h: {count} Snapshots",
+    bot_export_too_large: "❌ Die letzten {hours}h sind zu groß zum Senden. Exportiere weniger Stunden.",
+    bot_rate_limited: "⏳ Bitte etwas langsamer. Versuche es in {secs} Sekunden erneut.",
+    button_live: "🔄 Live ({duration})",
+    bot_live_until: "🔄 Live bis {time}",
+    bot_already_live: "Diese Ratio wird bereits live aktualisiert.",
+};
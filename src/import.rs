@@ -0,0 +1,223 @@
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use std::collections::HashSet;
+
+use crate::database::RatioRecord;
+
+/// Columns `import` understands; any other header is rejected
+const KNOWN_COLUMNS: [&str; 7] = [
+    "timestamp",
+    "pair",
+    "symbol_a",
+    "symbol_b",
+    "price_a",
+    "price_b",
+    "ratio",
+];
+
+/// Snapshots read from a CSV file, oldest first and one per timestamp
+pub struct ParsedImport {
+    pub records: Vec<RatioRecord>,
+    /// Data rows in the file
+    pub rows: usize,
+    /// Rows dropped for repeating an earlier row's timestamp
+    pub duplicates: usize,
+}
+
+/// Read snapshots of `pair_name` from CSV with a header row. Every row needs
+/// a `timestamp` and either a `ratio` or both `price_a` and `price_b`; rows
+/// with only a ratio store it as `price_a` over a `price_b` of 1. Symbols
+/// come from `symbol_a`/`symbol_b` columns or else `default_symbols`. The
+/// first invalid row fails the whole file, naming its line
+pub fn parse_snapshots_csv(
+    text: &str,
+    pair_name: &str,
+    default_symbols: Option<(&str, &str)>,
+    tz: &chrono_tz::Tz,
+) -> Result<ParsedImport> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines.next().ok_or_else(|| anyhow!("The file is empty"))?;
+    let header: Vec<String> = split_csv_line(header.trim_start_matches('\u{feff}'))?
+        .into_iter()
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+
+    for name in &header {
+        if !KNOWN_COLUMNS.contains(&name.as_str()) {
+            bail!(
+                "Unknown column {:?}, expected {}",
+                name,
+                KNOWN_COLUMNS.join(", ")
+            );
+        }
+    }
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let timestamp_col =
+        column("timestamp").ok_or_else(|| anyhow!("Missing column \"timestamp\""))?;
+    let (price_a_col, price_b_col, ratio_col) =
+        (column("price_a"), column("price_b"), column("ratio"));
+    if price_a_col.is_some() != price_b_col.is_some() {
+        bail!("Columns \"price_a\" and \"price_b\" must be given together");
+    }
+    if ratio_col.is_none() && price_a_col.is_none() {
+        bail!("Missing column \"ratio\", or \"price_a\" and \"price_b\"");
+    }
+    let (symbol_a_col, symbol_b_col) = (column("symbol_a"), column("symbol_b"));
+    if (symbol_a_col.is_none() || symbol_b_col.is_none()) && default_symbols.is_none() {
+        bail!(
+            "{} is not configured, add \"symbol_a\" and \"symbol_b\" columns",
+            pair_name
+        );
+    }
+    let pair_col = column("pair");
+
+    let mut records = Vec::new();
+    let mut seen = HashSet::new();
+    let mut rows = 0;
+    let mut duplicates = 0;
+    for (index, line) in lines {
+        let line_number = index + 1;
+        rows += 1;
+
+        let fields = split_csv_line(line).map_err(|e| anyhow!("Line {}: {}", line_number, e))?;
+        if fields.len() != header.len() {
+            bail!(
+                "Line {}: {} fields, the header has {}",
+                line_number,
+                fields.len(),
+                header.len()
+            );
+        }
+        let field = |col: usize| fields[col].trim();
+        let number = |col: usize| -> Result<f64> {
+            field(col)
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value > 0.0)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Line {}: {} must be a positive number, got {:?}",
+                        line_number,
+                        header[col],
+                        field(col)
+                    )
+                })
+        };
+
+        if let Some(col) = pair_col
+            && field(col) != pair_name
+        {
+            bail!(
+                "Line {}: pair {:?} doesn't match {:?}",
+                line_number,
+                field(col),
+                pair_name
+            );
+        }
+
+        let timestamp = parse_timestamp(field(timestamp_col), tz)
+            .map_err(|e| anyhow!("Line {}: {}", line_number, e))?;
+        let (price_a, price_b) = match (price_a_col, price_b_col) {
+            (Some(a), Some(b)) => (number(a)?, number(b)?),
+            _ => (number(ratio_col.expect("ratio column checked above"))?, 1.0),
+        };
+        let ratio = match ratio_col {
+            Some(col) => number(col)?,
+            None => price_a / price_b,
+        };
+        let (symbol_a, symbol_b) = match (symbol_a_col, symbol_b_col, default_symbols) {
+            (Some(a), Some(b), _) => (field(a).to_string(), field(b).to_string()),
+            (_, _, Some((a, b))) => (a.to_string(), b.to_string()),
+            _ => unreachable!("symbols checked above"),
+        };
+
+        if !seen.insert(timestamp) {
+            duplicates += 1;
+            continue;
+        }
+        records.push(RatioRecord {
+            id: 0,
+            pair_name: pair_name.to_string(),
+            symbol_a,
+            symbol_b,
+            price_a,
+            price_b,
+            ratio,
+            timestamp,
+        });
+    }
+    records.sort_by_key(|record| record.timestamp);
+
+    Ok(ParsedImport {
+        records,
+        rows,
+        duplicates,
+    })
+}
+
+/// An RFC 3339 time, a `YYYY-MM-DD HH:MM[:SS]` time in `tz`, or Unix
+/// seconds; values past the year 5138 as seconds are taken as milliseconds
+fn parse_timestamp(text: &str, tz: &chrono_tz::Tz) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(number) = text.parse::<i64>() {
+        let time = if number.abs() >= 100_000_000_000 {
+            DateTime::from_timestamp_millis(number)
+        } else {
+            DateTime::from_timestamp(number, 0)
+        };
+        return time.ok_or_else(|| anyhow!("Timestamp {} is out of range", number));
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return tz
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc))
+                .ok_or_else(|| anyhow!("{} doesn't exist in {}", time, tz));
+        }
+    }
+
+    bail!(
+        "Invalid timestamp {:?}, expected RFC 3339, YYYY-MM-DD HH:MM:SS or Unix seconds",
+        text
+    )
+}
+
+/// Fields of one CSV line; quoted fields may hold commas and doubled quotes
+fn split_csv_line(line: &str) -> Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        bail!("Unterminated quoted field");
+    }
+    fields.push(field);
+
+    Ok(fields)
+}
@@ -1,19 +1,41 @@
 mod binance;
 mod bot;
+mod chart;
 mod config;
 mod database;
+mod desktop;
+mod discord;
+mod export;
+mod gotify;
+mod heartbeat;
+mod i18n;
+mod import;
+mod markdown;
+mod matrix;
+mod messages;
 mod monitor;
+mod mqtt;
+mod notifier;
+mod ntfy;
+mod rate_limit;
 mod ratio;
+mod report;
+mod systemd;
 mod telegram;
+mod templates;
+mod webhook;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use std::sync::{Arc, RwLock};
 
 use binance::BinanceClient;
 use bot::BotHandler;
-use config::Config;
+use config::{Config, Leg, RetentionConfig, SharedConfig};
 use database::Database;
+use export::{ExportFormat, ExportTable};
 use monitor::RatioMonitor;
+use notifier::Notifiers;
 use ratio::{OrderSide, RatioCalculator};
 use telegram::TelegramNotifier;
 
@@ -46,6 +68,21 @@ enum Commands {
         symbol_b: String,
     },
 
+    /// Calculate a cross ratio between two assets, synthesized via a common quote if needed
+    Cross {
+        /// Base asset (e.g., SOL)
+        #[arg(short = 'a', long)]
+        base: String,
+
+        /// Quote asset (e.g., AVAX)
+        #[arg(short = 'b', long)]
+        quote: String,
+
+        /// Common quote asset used when no direct market exists
+        #[arg(long, default_value = "USDT")]
+        via: String,
+    },
+
     /// Calculate volume-based ratio with order book analysis
     Volume {
         /// Name for the ratio pair
@@ -81,13 +118,22 @@ enum Commands {
     },
 
     /// Start monitoring ratios (uses config file)
-    Monitor,
+    Monitor {
+        /// Take over from another monitor running against the same database
+        #[arg(long)]
+        force: bool,
+    },
 
     /// Start interactive Telegram bot with buttons
     Bot,
 
     /// Start both monitoring and interactive bot together
-    Start,
+    #[command(alias = "run")]
+    Start {
+        /// Take over from another monitor running against the same database
+        #[arg(long)]
+        force: bool,
+    },
 
     /// Test Telegram connection
     TestTelegram,
@@ -95,6 +141,12 @@ enum Commands {
     /// Show all configured ratio pairs
     ListPairs,
 
+    /// Pause or resume monitoring of a pair
+    Pair {
+        #[command(subcommand)]
+        action: PairAction,
+    },
+
     /// Query historical ratio data
     History {
         /// Pair name to query
@@ -104,6 +156,10 @@ enum Commands {
         /// Number of records to show (default: 100)
         #[arg(short, long, default_value = "100")]
         limit: i64,
+
+        /// Show volume-based ratios and their slippage instead
+        #[arg(long)]
+        volume: bool,
     },
 
     /// Show alert history
@@ -115,17 +171,209 @@ enum Commands {
         /// Number of alerts to show (default: 50)
         #[arg(short, long, default_value = "50")]
         limit: i64,
+
+        /// Summarize alerts per pair, day and threshold, and time to recovery
+        #[arg(long)]
+        summary: bool,
+
+        /// Days the summary covers (default: 30)
+        #[arg(long, default_value = "30", requires = "summary")]
+        days: i64,
     },
 
     /// Show statistics for a pair
     Stats {
+        /// Pair name
+        #[arg(short, long, required_unless_present = "all")]
+        pair: Option<String>,
+
+        /// Summarize every configured pair, largest change first
+        #[arg(long, conflicts_with_all = ["pair", "volume"])]
+        all: bool,
+
+        /// Number of hours to analyze (default: 24)
+        #[arg(long, default_value = "24")]
+        hours: i64,
+
+        /// Show slippage of volume-based ratios over time instead
+        #[arg(long)]
+        volume: bool,
+    },
+
+    /// Compare a pair's statistics in two time ranges
+    Compare {
         /// Pair name
         #[arg(short, long)]
         pair: String,
 
-        /// Number of hours to analyze (default: 24)
+        /// Length of each range in hours when not given explicitly (default: 168)
+        #[arg(long, default_value = "168")]
+        hours: i64,
+
+        /// Range to compare, as START..END dates (YYYY-MM-DD, in `timezone`) or
+        /// RFC 3339 times (default: the last `hours`)
+        #[arg(long)]
+        current: Option<String>,
+
+        /// Range to compare against, same format (default: the equally long
+        /// range right before `current`)
+        #[arg(long)]
+        previous: Option<String>,
+    },
+
+    /// Export a pair's stored history for analysis in other tools
+    Export {
+        /// Pair name
+        #[arg(short, long)]
+        pair: String,
+
+        /// What to export: snapshots, alerts or volume (default: snapshots)
+        #[arg(short, long, default_value = "snapshots")]
+        table: String,
+
+        /// Output format: csv, jsonl or parquet (default: csv)
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+
+        /// Hours of history to export when no range is given (default: 24)
         #[arg(long, default_value = "24")]
         hours: i64,
+
+        /// Range to export, as START..END dates (YYYY-MM-DD, in `timezone`) or
+        /// RFC 3339 times (default: the last `hours`)
+        #[arg(long)]
+        range: Option<String>,
+
+        /// File to write (default: standard output; required for parquet)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Import snapshots of a pair from a CSV file of timestamped prices or ratios
+    Import {
+        /// Pair name
+        #[arg(short, long)]
+        pair: String,
+
+        /// CSV file with a header row
+        #[arg(short, long)]
+        file: String,
+
+        /// Check the file without storing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Maintain the database
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+
+    /// Show how often the monitor's checks failed or were slow, per pair
+    HealthHistory {
+        /// Optional pair name to filter checks
+        #[arg(short, long)]
+        pair: Option<String>,
+
+        /// Hours of checks to summarize when no range is given (default: 24)
+        #[arg(long, default_value = "24")]
+        hours: i64,
+
+        /// Range to summarize, as START..END dates (YYYY-MM-DD, in `timezone`)
+        /// or RFC 3339 times (default: the last `hours`)
+        #[arg(long)]
+        range: Option<String>,
+
+        /// Count successful checks slower than this many milliseconds as slow
+        /// (default: 1000)
+        #[arg(long, default_value = "1000")]
+        slow_ms: u64,
+    },
+
+    /// Show the bot's audit log of commands and button presses
+    BotLog {
+        /// Optional Telegram user ID to filter entries
+        #[arg(short, long)]
+        user: Option<u64>,
+
+        /// Number of entries to show (default: 50)
+        #[arg(short, long, default_value = "50")]
+        limit: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum PairAction {
+    /// Stop checking and alerting on a pair until it is resumed
+    Pause {
+        /// Pair name
+        name: String,
+    },
+
+    /// Resume monitoring of a paused pair
+    Resume {
+        /// Pair name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbAction {
+    /// Purge old data, reclaim free space and check the database for corruption
+    Maintain {
+        /// Purge data of every kind older than this many days (default: the
+        /// configured retention periods)
+        #[arg(long)]
+        retention_days: Option<i64>,
+    },
+
+    /// Show the size, tables, indexes and stored pairs of the database, and
+    /// how fast it grows
+    Info,
+
+    /// Show how the database runs the history, alert and statistics queries
+    /// of a pair, to check they use the indexes
+    Explain {
+        /// Pair name
+        #[arg(short, long)]
+        pair: String,
+    },
+
+    /// Move a pair's history and state to a new name, merging it with any history there
+    RenamePair {
+        /// Current pair name in the database
+        old: String,
+
+        /// New pair name, as in the config
+        new: String,
+    },
+
+    /// Delete all stored history and state of a pair
+    Purge {
+        /// Pair name
+        #[arg(short, long)]
+        pair: String,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show the raw Binance responses archived for a pair's symbols
+    Responses {
+        /// Pair name
+        #[arg(short, long)]
+        pair: String,
+
+        /// Show responses up to this time, as a date (YYYY-MM-DD, in `timezone`)
+        /// or RFC 3339 time (default: now)
+        #[arg(long)]
+        at: Option<String>,
+
+        /// Number of responses to show (default: 10)
+        #[arg(short, long, default_value = "10")]
+        limit: i64,
     },
 }
 
@@ -144,6 +392,9 @@ async fn main() -> Result<()> {
         } => {
             handle_simple_ratio(&name, &symbol_a, &symbol_b).await?;
         }
+        Commands::Cross { base, quote, via } => {
+            handle_cross_ratio(&base, &quote, &via).await?;
+        }
         Commands::Volume {
             name,
             symbol_a,
@@ -159,14 +410,14 @@ async fn main() -> Result<()> {
         } => {
             handle_slippage(&symbol, volume, &side).await?;
         }
-        Commands::Monitor => {
-            handle_monitor(&cli.config).await?;
+        Commands::Monitor { force } => {
+            handle_monitor(&cli.config, force).await?;
         }
         Commands::Bot => {
             handle_bot(&cli.config).await?;
         }
-        Commands::Start => {
-            handle_start(&cli.config).await?;
+        Commands::Start { force } => {
+            handle_start(&cli.config, force).await?;
         }
         Commands::TestTelegram => {
             handle_test_telegram(&cli.config).await?;
@@ -174,14 +425,68 @@ async fn main() -> Result<()> {
         Commands::ListPairs => {
             handle_list_pairs(&cli.config).await?;
         }
-        Commands::History { pair, limit } => {
-            handle_history(&cli.config, &pair, limit).await?;
+        Commands::Pair { action } => {
+            handle_pair_action(&cli.config, action).await?;
+        }
+        Commands::History { pair, limit, volume } => {
+            if volume {
+                handle_volume_history(&cli.config, &pair, limit).await?;
+            } else {
+                handle_history(&cli.config, &pair, limit).await?;
+            }
+        }
+        Commands::Alerts {
+            pair,
+            limit,
+            summary,
+            days,
+        } => {
+            if summary {
+                handle_alert_summary(&cli.config, pair.as_deref(), days).await?;
+            } else {
+                handle_alerts(&cli.config, pair.as_deref(), limit).await?;
+            }
+        }
+        Commands::Stats { pair, all, hours, volume } => match pair {
+            _ if all => handle_all_stats(&cli.config, hours).await?,
+            Some(pair) if volume => handle_volume_stats(&cli.config, &pair, hours).await?,
+            Some(pair) => handle_stats(&cli.config, &pair, hours).await?,
+            None => unreachable!("clap requires --pair without --all"),
+        },
+        Commands::BotLog { user, limit } => {
+            handle_bot_log(&cli.config, user, limit).await?;
+        }
+        Commands::HealthHistory {
+            pair,
+            hours,
+            range,
+            slow_ms,
+        } => {
+            handle_health_history(&cli.config, pair.as_deref(), hours, range.as_deref(), slow_ms).await?;
+        }
+        Commands::Compare {
+            pair,
+            hours,
+            current,
+            previous,
+        } => {
+            handle_compare(&cli.config, &pair, hours, current.as_deref(), previous.as_deref()).await?;
+        }
+        Commands::Export {
+            pair,
+            table,
+            format,
+            hours,
+            range,
+            output,
+        } => {
+            handle_export(&cli.config, &pair, &table, &format, hours, range.as_deref(), output.as_deref()).await?;
         }
-        Commands::Alerts { pair, limit } => {
-            handle_alerts(&cli.config, pair.as_deref(), limit).await?;
+        Commands::Import { pair, file, dry_run } => {
+            handle_import(&cli.config, &pair, &file, dry_run).await?;
         }
-        Commands::Stats { pair, hours } => {
-            handle_stats(&cli.config, &pair, hours).await?;
+        Commands::Db { action } => {
+            handle_db_action(&cli.config, action).await?;
         }
     }
 
@@ -208,6 +513,28 @@ async fn handle_simple_ratio(name: &str, symbol_a: &str, symbol_b: &str) -> Resu
     Ok(())
 }
 
+async fn handle_cross_ratio(base: &str, quote: &str, via: &str) -> Result<()> {
+    log::info!("Calculating cross ratio for {} / {}", base, quote);
+
+    let client = BinanceClient::new();
+    let calculator = RatioCalculator::new(client);
+
+    let ratio = calculator.calculate_cross_ratio(base, quote, via).await?;
+
+    println!("\n{}", "=".repeat(60));
+    if ratio.is_synthetic() {
+        println!("Synthetic Cross Ratio (no direct market)");
+    } else {
+        println!("Cross Ratio (direct market)");
+    }
+    println!("{}", "=".repeat(60));
+    println!("{}", ratio.format_summary());
+    println!("Timestamp: {}", ratio.timestamp);
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
+
 async fn handle_volume_ratio(
     name: &str,
     symbol_a: &str,
@@ -263,7 +590,7 @@ async fn handle_slippage(symbol: &str, volume: f64, side: &str) -> Result<()> {
     Ok(())
 }
 
-async fn handle_monitor(config_path: &str) -> Result<()> {
+async fn handle_monitor(config_path: &str, force: bool) -> Result<()> {
     log::info!("Loading configuration from {}", config_path);
 
     let config = Config::from_file(config_path)
@@ -275,17 +602,19 @@ async fn handle_monitor(config_path: &str) -> Result<()> {
     log::info!("Monitoring {} ratio pairs", config.ratio_pairs.len());
 
     // Initialize database
-    let db_url = format!("sqlite:{}?mode=rwc", config.database.path);
-    let database = Database::new(&db_url)
+    let database = Database::new(&config.database)
         .await
         .context("Failed to initialize database")?;
-    log::info!("Database initialized at {}", config.database.path);
+    log::info!("Database initialized at {}", config.database.location());
 
-    let client = BinanceClient::new();
-    let calculator = RatioCalculator::new(client);
-    let notifier = TelegramNotifier::new(&config.telegram.token, config.telegram.user_id);
+    let client = BinanceClient::new().with_response_archive(&config, &database)?;
+    let calculator =
+        RatioCalculator::new(client).with_order_book_store(&config.database, &database);
+    let notifier = Notifiers::from_config(&config, &database);
 
-    let mut monitor = RatioMonitor::new(config, calculator, notifier, database);
+    let mut monitor =
+        RatioMonitor::new(config, calculator, notifier, database).with_config_path(config_path);
+    monitor.acquire_lease(force).await?;
 
     monitor.start().await?;
 
@@ -298,7 +627,9 @@ async fn handle_test_telegram(config_path: &str) -> Result<()> {
     let config = Config::from_file(config_path)
         .context("Failed to load config file. Did you create config.toml?")?;
 
-    let notifier = TelegramNotifier::new(&config.telegram.token, config.telegram.user_id);
+    config.require_telegram()?;
+    let notifier = TelegramNotifier::new(&config.telegram.token, config.telegram.user_id)
+        .with_recipients(&config.telegram.recipients);
 
     notifier.test_connection().await?;
 
@@ -314,14 +645,20 @@ async fn handle_bot(config_path: &str) -> Result<()> {
         .context("Failed to load config file. Did you create config.toml?")?;
 
     config.validate()?;
+    config.require_telegram()?;
 
     log::info!("Configuration loaded successfully");
     log::info!("Bot configured with {} ratio pairs", config.ratio_pairs.len());
 
-    let client = BinanceClient::new();
-    let calculator = RatioCalculator::new(client);
+    let database = Database::new(&config.database)
+        .await
+        .context("Failed to initialize database")?;
+
+    let client = BinanceClient::new().with_response_archive(&config, &database)?;
+    let calculator =
+        RatioCalculator::new(client).with_order_book_store(&config.database, &database);
 
-    let bot_handler = BotHandler::new(config, calculator);
+    let bot_handler = BotHandler::new(Arc::new(RwLock::new(config)), calculator, database);
 
     println!("\n{}", "=".repeat(60));
     println!("Interactive Telegram Bot Started");
@@ -335,47 +672,53 @@ async fn handle_bot(config_path: &str) -> Result<()> {
     Ok(())
 }
 
-async fn handle_start(config_path: &str) -> Result<()> {
+async fn handle_start(config_path: &str, force: bool) -> Result<()> {
     log::info!("Starting combined monitoring + interactive bot mode...");
 
     let config = Config::from_file(config_path)
         .context("Failed to load config file. Did you create config.toml?")?;
 
     config.validate()?;
+    config.require_telegram()?;
 
     log::info!("Configuration loaded successfully");
     log::info!("Starting {} ratio pairs", config.ratio_pairs.len());
 
     // Initialize database
-    let db_url = format!("sqlite:{}?mode=rwc", config.database.path);
-    let database = Database::new(&db_url)
+    let database = Database::new(&config.database)
         .await
         .context("Failed to initialize database")?;
-    log::info!("Database initialized at {}", config.database.path);
+    log::info!("Database initialized at {}", config.database.location());
 
     // Create shared components
-    let client = BinanceClient::new();
-    let calculator = RatioCalculator::new(client.clone());
-    let notifier = TelegramNotifier::new(&config.telegram.token, config.telegram.user_id);
+    let client = BinanceClient::new().with_response_archive(&config, &database)?;
+    let calculator =
+        RatioCalculator::new(client).with_order_book_store(&config.database, &database);
+    let notifier = Notifiers::from_config(&config, &database);
+
+    // The bot reads the config the monitor reloads on SIGHUP
+    let shared_config: SharedConfig = Arc::new(RwLock::new(config.clone()));
 
     // Create monitor
     let mut monitor = RatioMonitor::new(
         config.clone(),
         calculator.clone(),
         notifier,
-        database,
-    );
+        database.clone(),
+    )
+    .with_config_path(config_path)
+    .with_shared_config(shared_config.clone());
+    monitor.acquire_lease(force).await?;
 
-    // Create bot handler
-    let bot_calculator = RatioCalculator::new(client);
-    let bot_handler = BotHandler::new(config.clone(), bot_calculator);
+    // Create bot handler, sharing the monitor's calculator
+    let bot_handler = BotHandler::new(shared_config, calculator, database);
 
     println!("\n{}", "=".repeat(60));
     println!("Ratio-Noti: Full Mode Started");
     println!("{}", "=".repeat(60));
     println!("✓ Monitoring: Active (checking every {}s)", config.monitoring.check_interval_secs);
     println!("✓ Interactive Bot: Ready");
-    println!("✓ Database: {}", config.database.path);
+    println!("✓ Database: {}", config.database.location());
     println!();
     println!("Features:");
     println!("  • Automatic alerts on threshold breaches");
@@ -386,13 +729,13 @@ async fn handle_start(config_path: &str) -> Result<()> {
     println!("{}", "=".repeat(60));
 
     // Run both concurrently
-    let monitor_task = tokio::spawn(async move {
+    let mut monitor_task = tokio::spawn(async move {
         if let Err(e) = monitor.start().await {
             log::error!("Monitor error: {}", e);
         }
     });
 
-    let bot_task = tokio::spawn(async move {
+    let mut bot_task = tokio::spawn(async move {
         if let Err(e) = bot_handler.run().await {
             log::error!("Bot error: {}", e);
         }
@@ -400,11 +743,13 @@ async fn handle_start(config_path: &str) -> Result<()> {
 
     // Wait for both tasks (or until one fails/exits)
     tokio::select! {
-        _ = monitor_task => {
+        _ = &mut monitor_task => {
             log::info!("Monitor task ended");
         }
-        _ = bot_task => {
+        _ = &mut bot_task => {
             log::info!("Bot task ended");
+            // The bot stops on Ctrl+C; let the monitor finish its own graceful shutdown
+            let _ = monitor_task.await;
         }
     }
 
@@ -421,8 +766,14 @@ async fn handle_list_pairs(config_path: &str) -> Result<()> {
 
     for (i, pair) in config.ratio_pairs.iter().enumerate() {
         println!("\n{}. {}", i + 1, pair.name);
-        println!("   Symbol A: {}", pair.symbol_a);
-        println!("   Symbol B: {}", pair.symbol_b);
+        match pair.leg_a() {
+            Leg::Symbol(symbol) => println!("   Symbol A: {}", symbol),
+            Leg::Pair(name) => println!("   Pair A: {}", name),
+        }
+        match pair.leg_b() {
+            Leg::Symbol(symbol) => println!("   Symbol B: {}", symbol),
+            Leg::Pair(name) => println!("   Pair B: {}", name),
+        }
         if let Some(vol) = pair.analysis_volume {
             println!("   Analysis Volume: {}", vol);
         }
@@ -435,12 +786,283 @@ async fn handle_list_pairs(config_path: &str) -> Result<()> {
     Ok(())
 }
 
+async fn handle_pair_action(config_path: &str, action: PairAction) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let database = Database::new(&config.database).await?;
+
+    match action {
+        PairAction::Pause { name } => {
+            // Pairs added through the bot can be paused as well
+            let bot_pairs = database.get_bot_pairs().await?;
+            if config.find_pair(&name).is_none() && !bot_pairs.iter().any(|p| p.name == name) {
+                anyhow::bail!("Unknown ratio pair: {}", name);
+            }
+            database.pause_pair(&name).await?;
+            println!("⏸ Paused monitoring of {}", name);
+        }
+        PairAction::Resume { name } => {
+            if database.resume_pair(&name).await? {
+                println!("▶ Resumed monitoring of {}", name);
+            } else {
+                println!("{} is not paused", name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_db_action(config_path: &str, action: DbAction) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let database = Database::new(&config.database).await?;
+
+    match action {
+        DbAction::Maintain { retention_days } => {
+            println!("\n{}", "=".repeat(60));
+            println!("Database Maintenance: {}", config.database.location());
+            println!("{}", "=".repeat(60));
+
+            let retention = match retention_days {
+                Some(days) if days <= 0 => anyhow::bail!("retention_days must be greater than zero"),
+                Some(days) => RetentionConfig::uniform(days),
+                None => config.database.retention_periods(),
+            };
+            if retention.is_empty() {
+                println!("No retention set, nothing purged (pass --retention-days)");
+            } else {
+                let summary = database.cleanup_old_records(&retention).await?;
+                let kinds = [
+                    ("Ratio snapshots", summary.ratio_snapshots, retention.snapshots),
+                    ("Alerts", summary.alerts, retention.alerts),
+                    ("Volume ratios", summary.volume_ratios, retention.volume_ratios),
+                    ("Order books", summary.order_books, retention.order_books),
+                    ("Checks", summary.checks, retention.checks),
+                ];
+                println!("Purged data:");
+                for (kind, count, days) in kinds {
+                    match days {
+                        Some(days) => println!("  {}: {} (older than {} days)", kind, count, days),
+                        None => println!("  {}: kept", kind),
+                    }
+                }
+            }
+
+            let before = database.size_bytes().await?;
+            database.vacuum().await?;
+            let after = database.size_bytes().await?;
+            println!(
+                "Vacuumed: {} -> {} ({} reclaimed)",
+                format_size(before),
+                format_size(after),
+                format_size(before.saturating_sub(after))
+            );
+
+            match database.integrity_check().await? {
+                Some(problems) if problems.is_empty() => println!("Integrity check: ok"),
+                Some(problems) => {
+                    println!("Integrity check found {} problems:", problems.len());
+                    for problem in &problems {
+                        println!("  {}", problem);
+                    }
+                }
+                None => println!("Integrity check: not available for PostgreSQL"),
+            }
+
+            println!("{}", "=".repeat(60));
+        }
+        DbAction::Info => {
+            let info = database.info().await?;
+            let size = |bytes: Option<u64>| bytes.map(format_size).unwrap_or_else(|| "-".to_string());
+
+            println!("\n{}", "=".repeat(60));
+            println!("Database Info: {}", config.database.location());
+            println!("{}", "=".repeat(60));
+            println!("Size: {}", format_size(info.size));
+            println!("Schema version: {}", info.schema_version);
+
+            println!("\n{:<28} {:>10} {:>10} {:>9}", "Table", "Rows", "Size", "Last 7d");
+            for table in &info.tables {
+                println!(
+                    "{:<28} {:>10} {:>10} {:>9}",
+                    table.name,
+                    table.rows,
+                    size(table.bytes),
+                    table.recent_rows.map(|rows| rows.to_string()).unwrap_or_else(|| "-".to_string())
+                );
+            }
+
+            // Only PostgreSQL counts index scans
+            let scans = info.indexes.iter().any(|index| index.scans.is_some());
+            if scans {
+                println!("\n{:<44} {:>10} {:>9}", "Index", "Size", "Scans");
+            } else {
+                println!("\n{:<44} {:>10}", "Index", "Size");
+            }
+            for index in &info.indexes {
+                match index.scans {
+                    Some(count) => println!("{:<44} {:>10} {:>9}", index.name, size(index.bytes), count),
+                    None if scans => println!("{:<44} {:>10} {:>9}", index.name, size(index.bytes), "-"),
+                    None => println!("{:<44} {:>10}", index.name, size(index.bytes)),
+                }
+            }
+
+            if !info.pairs.is_empty() {
+                println!("\n{:<16} {:>9}  {:<20} Newest", "Pair", "Snapshots", "Oldest");
+                for pair in &info.pairs {
+                    println!(
+                        "{:<16} {:>9}  {:<20} {}",
+                        pair.pair_name,
+                        pair.snapshots,
+                        pair.oldest.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M"),
+                        pair.newest.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M")
+                    );
+                }
+            }
+
+            println!();
+            match info.growth_per_day() {
+                Some(growth) => {
+                    println!(
+                        "Growth: {}/day, {}/month at the rate of the last 7 days",
+                        format_size(growth as u64),
+                        format_size((growth * 30.0) as u64)
+                    );
+                    if let Some(days) = config.database.retention_days {
+                        println!(
+                            "With retention_days = {}, history levels off around {}",
+                            days,
+                            format_size((growth * days as f64) as u64)
+                        );
+                    }
+                }
+                None => println!("Growth: unknown, this SQLite build can't report table sizes"),
+            }
+            println!("{}", "=".repeat(60));
+        }
+        DbAction::Explain { pair } => {
+            let plans = database.explain_queries(&pair).await?;
+
+            println!("\n{}", "=".repeat(60));
+            println!("Query Plans: {}", pair);
+            println!("{}", "=".repeat(60));
+            for plan in &plans {
+                println!("\n{}:", plan.name);
+                for line in &plan.lines {
+                    println!("  {}", line);
+                }
+            }
+            println!("{}", "=".repeat(60));
+        }
+        DbAction::RenamePair { old, new } => {
+            if old == new {
+                anyhow::bail!("The old and new pair names are the same");
+            }
+            if config.find_pair(&new).is_none() {
+                log::warn!("{} is not a configured ratio pair", new);
+            }
+
+            let summary = database.rename_pair(&old, &new).await?;
+            if summary.total() == 0 {
+                println!("No history or state found for {}", old);
+            } else {
+                println!("Moved the history and state of {} to {}:", old, new);
+                for (table, rows) in &summary.tables {
+                    println!("  {}: {}", table, rows);
+                }
+            }
+        }
+        DbAction::Responses { pair, at, limit } => {
+            let symbols = config.pair_symbols(&pair)?;
+            let at = match at {
+                Some(at) => parse_time(&at, &config.timezone)?,
+                None => chrono::Utc::now(),
+            };
+
+            let records = database.get_api_responses(&symbols, at, limit).await?;
+            if records.is_empty() {
+                println!("No archived responses of {} ({})", pair, symbols.join(", "));
+                return Ok(());
+            }
+
+            for record in records.iter().rev() {
+                println!(
+                    "\n{} | {} {} | HTTP {}",
+                    record.timestamp.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S%.3f %Z"),
+                    record.symbol,
+                    record.endpoint,
+                    record.status
+                );
+                println!("{}", record.body);
+            }
+        }
+        DbAction::Purge { pair, yes } => {
+            let counts = database.count_pair_rows(&pair).await?;
+            if counts.total() == 0 {
+                println!("Nothing stored for {}", pair);
+                return Ok(());
+            }
+
+            println!("Stored for {}:", pair);
+            for (table, rows) in counts.tables.iter().filter(|(_, rows)| *rows > 0) {
+                println!("  {}: {}", table, rows);
+            }
+            if config.find_pair(&pair).is_some() {
+                println!("{} is still configured; a running monitor keeps recording it", pair);
+            }
+
+            if !yes && !confirm(&format!("Delete these {} rows?", counts.total()))? {
+                println!("Nothing deleted");
+                return Ok(());
+            }
+
+            let purged = database.purge_pair(&pair).await?;
+            println!("🗑 Deleted {} rows of {}", purged.total(), pair);
+        }
+    }
+
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .context("Failed to read confirmation")?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Byte count in the largest unit that keeps it at or above 1
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
 async fn handle_history(config_path: &str, pair_name: &str, limit: i64) -> Result<()> {
     let config = Config::from_file(config_path)
         .context("Failed to load config file")?;
 
-    let db_url = format!("sqlite:{}?mode=rwc", config.database.path);
-    let database = Database::new(&db_url).await?;
+    let database = Database::new(&config.database).await?;
 
     let records = database.get_ratio_history(pair_name, limit).await?;
 
@@ -454,7 +1076,7 @@ async fn handle_history(config_path: &str, pair_name: &str, limit: i64) -> Resul
         for record in &records {
             println!(
                 "{} | Ratio: {:.8} | {} ${:.2} / {} ${:.2}",
-                record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                record.timestamp.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S %Z"),
                 record.ratio,
                 record.symbol_a,
                 record.price_a,
@@ -470,12 +1092,48 @@ async fn handle_history(config_path: &str, pair_name: &str, limit: i64) -> Resul
     Ok(())
 }
 
+async fn handle_volume_history(config_path: &str, pair_name: &str, limit: i64) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let database = Database::new(&config.database).await?;
+
+    let records = database.get_volume_ratio_history(pair_name, limit).await?;
+
+    println!("\n{}", "=".repeat(60));
+    println!("Volume Ratio History: {}", pair_name);
+    println!("{}", "=".repeat(60));
+
+    if records.is_empty() {
+        println!("No volume ratios found for {}", pair_name);
+    } else {
+        for record in &records {
+            println!(
+                "{} | Ratio: {:.8} [Vol: {}] | {} ${:.2} ({:.3}%) / {} ${:.2} ({:.3}%)",
+                record.timestamp.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S %Z"),
+                record.ratio,
+                record.volume,
+                record.symbol_a,
+                record.effective_price_a,
+                record.slippage_a,
+                record.symbol_b,
+                record.effective_price_b,
+                record.slippage_b
+            );
+        }
+        println!("\nTotal records: {}", records.len());
+    }
+
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
+
 async fn handle_alerts(config_path: &str, pair_name: Option<&str>, limit: i64) -> Result<()> {
     let config = Config::from_file(config_path)
         .context("Failed to load config file")?;
 
-    let db_url = format!("sqlite:{}?mode=rwc", config.database.path);
-    let database = Database::new(&db_url).await?;
+    let database = Database::new(&config.database).await?;
 
     let records = if let Some(pair) = pair_name {
         database.get_alert_history(pair, limit).await?
@@ -496,12 +1154,11 @@ async fn handle_alerts(config_path: &str, pair_name: Option<&str>, limit: i64) -
     } else {
         for alert in &records {
             println!(
-                "{} | {} | Ratio: {:.8} | Change: {:+.2}% (threshold: {}%)",
-                alert.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                "{} | {} | Ratio: {:.8} | {}",
+                alert.timestamp.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S %Z"),
                 alert.pair_name,
                 alert.ratio,
-                alert.change_percentage,
-                alert.threshold
+                alert.format_details(&i18n::EN)
             );
         }
         println!("\nTotal alerts: {}", records.len());
@@ -512,12 +1169,134 @@ async fn handle_alerts(config_path: &str, pair_name: Option<&str>, limit: i64) -
     Ok(())
 }
 
+async fn handle_alert_summary(config_path: &str, pair_name: Option<&str>, days: i64) -> Result<()> {
+    if days <= 0 {
+        anyhow::bail!("--days must be greater than zero");
+    }
+
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let database = Database::new(&config.database).await?;
+
+    let mut summaries = database.get_alert_summary(pair_name, days).await?;
+    // Noisiest pairs first, the likeliest to have mis-tuned thresholds
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.total()));
+
+    println!("\n{}", "=".repeat(60));
+    println!("Alert Summary (last {} days, UTC days)", days);
+    println!("{}", "=".repeat(60));
+
+    if summaries.is_empty() {
+        println!("No alerts found");
+    } else {
+        for summary in &summaries {
+            println!("{}\n", summary.format_summary());
+        }
+    }
+
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
+
+async fn handle_bot_log(config_path: &str, user_id: Option<u64>, limit: i64) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let database = Database::new(&config.database).await?;
+
+    let records = database.get_bot_log(user_id, limit).await?;
+
+    println!("\n{}", "=".repeat(60));
+    if let Some(user_id) = user_id {
+        println!("Bot Log: User {}", user_id);
+    } else {
+        println!("Bot Log: All Users");
+    }
+    println!("{}", "=".repeat(60));
+
+    if records.is_empty() {
+        println!("No bot interactions found");
+    } else {
+        for entry in &records {
+            println!(
+                "{} | {} ({}) | Chat {} | {} | {}",
+                entry.timestamp.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S %Z"),
+                entry.user_name,
+                entry.user_id,
+                entry.chat_id,
+                entry.action,
+                entry.result
+            );
+        }
+        println!("\nTotal entries: {}", records.len());
+    }
+
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
+
+async fn handle_health_history(
+    config_path: &str,
+    pair_name: Option<&str>,
+    hours: i64,
+    range: Option<&str>,
+    slow_ms: u64,
+) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let (start, end) = match range {
+        Some(range) => parse_time_range(range, &config.timezone)?,
+        None => {
+            let now = chrono::Utc::now();
+            (now - chrono::Duration::hours(hours), now)
+        }
+    };
+
+    let database = Database::new(&config.database).await?;
+
+    let mut summaries = database.get_check_summary(pair_name, start, end, slow_ms).await?;
+    // Least reliable pairs first
+    summaries.sort_by(|a, b| b.failure_rate().total_cmp(&a.failure_rate()));
+
+    println!("\n{}", "=".repeat(60));
+    println!(
+        "Check Health: {} to {}",
+        start.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M"),
+        end.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M %Z")
+    );
+    println!("{}", "=".repeat(60));
+
+    if summaries.is_empty() {
+        println!("No checks recorded");
+    } else {
+        for summary in &summaries {
+            println!("{}\n", summary.format_summary(&config.timezone));
+        }
+
+        let checks: u64 = summaries.iter().map(|s| s.checks).sum();
+        let failures: u64 = summaries.iter().map(|s| s.failures).sum();
+        println!(
+            "Total: {} checks, {} failed ({:.1}%)",
+            checks,
+            failures,
+            failures as f64 / checks as f64 * 100.0
+        );
+    }
+
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
+
 async fn handle_stats(config_path: &str, pair_name: &str, hours: i64) -> Result<()> {
     let config = Config::from_file(config_path)
         .context("Failed to load config file")?;
 
-    let db_url = format!("sqlite:{}?mode=rwc", config.database.path);
-    let database = Database::new(&db_url).await?;
+    let database = Database::new(&config.database).await?;
 
     let stats = database.get_pair_statistics(pair_name, hours).await?;
 
@@ -529,3 +1308,225 @@ async fn handle_stats(config_path: &str, pair_name: &str, hours: i64) -> Result<
 
     Ok(())
 }
+
+async fn handle_all_stats(config_path: &str, hours: i64) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let database = Database::new(&config.database).await?;
+
+    let names: Vec<String> = config.ratio_pairs.iter().map(|p| p.name.clone()).collect();
+    let all_stats = database.get_all_pair_statistics(&names, hours).await?;
+
+    println!("\n{}", "=".repeat(60));
+    println!("Statistics: all pairs (last {} hours)", hours);
+    println!("{}", "=".repeat(60));
+
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    for stats in &all_stats {
+        if stats.count == 0 {
+            println!("{:<width$} | no data", stats.pair_name);
+            continue;
+        }
+        println!(
+            "{:<width$} | {:+7.2}% | Last: {:.8} | Range: {:.2}% | Std dev: {:.8} | {} samples",
+            stats.pair_name,
+            stats.change_pct(),
+            stats.last_ratio,
+            (stats.max_ratio - stats.min_ratio) / stats.min_ratio * 100.0,
+            stats.stddev,
+            stats.count
+        );
+    }
+
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
+
+async fn handle_compare(
+    config_path: &str,
+    pair_name: &str,
+    hours: i64,
+    current: Option<&str>,
+    previous: Option<&str>,
+) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let now = chrono::Utc::now();
+    let current = match current {
+        Some(text) => parse_time_range(text, &config.timezone)?,
+        None => (now - chrono::Duration::hours(hours), now),
+    };
+    let previous = match previous {
+        Some(text) => parse_time_range(text, &config.timezone)?,
+        None => (current.0 - (current.1 - current.0), current.0),
+    };
+
+    let database = Database::new(&config.database).await?;
+
+    let comparison = database
+        .compare_pair_statistics(pair_name, previous, current)
+        .await?;
+
+    println!("\n{}", "=".repeat(60));
+    println!("Comparison");
+    println!("{}", "=".repeat(60));
+    println!("{}", comparison.format_summary(&config.timezone));
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
+
+async fn handle_export(
+    config_path: &str,
+    pair_name: &str,
+    table: &str,
+    format: &str,
+    hours: i64,
+    range: Option<&str>,
+    output: Option<&str>,
+) -> Result<()> {
+    let format = ExportFormat::parse(format)?;
+    if format == ExportFormat::Parquet && output.is_none() {
+        anyhow::bail!("Parquet is a binary format, pass --output <file>");
+    }
+
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let (start, end) = match range {
+        Some(range) => parse_time_range(range, &config.timezone)?,
+        None => {
+            let now = chrono::Utc::now();
+            (now - chrono::Duration::hours(hours), now)
+        }
+    };
+
+    let database = Database::new(&config.database).await?;
+
+    // Ranges come newest first, exports read oldest first
+    let table = match table {
+        "snapshots" => {
+            let mut records = database.get_ratio_history_range(pair_name, start, end).await?;
+            records.reverse();
+            ExportTable::snapshots(&records)
+        }
+        "alerts" => {
+            let mut records = database.get_alert_history_range(pair_name, start, end).await?;
+            records.reverse();
+            ExportTable::alerts(&records)
+        }
+        "volume" => {
+            let mut records = database.get_volume_ratio_history_range(pair_name, start, end).await?;
+            records.reverse();
+            ExportTable::volume_ratios(&records)
+        }
+        _ => anyhow::bail!("Invalid table: {}. Must be 'snapshots', 'alerts' or 'volume'", table),
+    };
+
+    match output {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("Failed to create {}", path))?;
+            table.write(format, std::io::BufWriter::new(file))?;
+            println!("Exported {} rows of {} to {}", table.len(), pair_name, path);
+        }
+        None => table.write(format, std::io::BufWriter::new(std::io::stdout()))?,
+    }
+
+    Ok(())
+}
+
+async fn handle_import(config_path: &str, pair_name: &str, path: &str, dry_run: bool) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path))?;
+    // Configured pairs default to their legs, as the monitor stores them
+    let default_symbols = config
+        .find_pair(pair_name)
+        .map(|pair| (pair.leg_a().label().to_string(), pair.leg_b().label().to_string()));
+    let parsed = import::parse_snapshots_csv(
+        &text,
+        pair_name,
+        default_symbols.as_ref().map(|(a, b)| (a.as_str(), b.as_str())),
+        &config.timezone,
+    )
+    .with_context(|| format!("Failed to import {}", path))?;
+
+    println!("Read {} rows of {} from {}", parsed.rows, pair_name, path);
+    if parsed.duplicates > 0 {
+        println!("  {} repeat an earlier row's timestamp and were skipped", parsed.duplicates);
+    }
+    if let (Some(first), Some(last)) = (parsed.records.first(), parsed.records.last()) {
+        println!(
+            "  {} to {}",
+            first.timestamp.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S %Z"),
+            last.timestamp.with_timezone(&config.timezone).format("%Y-%m-%d %H:%M:%S %Z")
+        );
+    }
+    if dry_run {
+        println!("Dry run, nothing stored");
+        return Ok(());
+    }
+
+    let database = Database::new(&config.database).await?;
+
+    let imported = database.import_ratio_snapshots(&parsed.records).await?;
+    let skipped = parsed.records.len() as u64 - imported;
+    println!("📥 Imported {} snapshots", imported);
+    if skipped > 0 {
+        println!("  {} were already stored and were skipped", skipped);
+    }
+
+    Ok(())
+}
+
+/// A date (midnight in `tz`) or RFC 3339 time
+fn parse_time(text: &str, tz: &chrono_tz::Tz) -> Result<chrono::DateTime<chrono::Utc>> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&chrono::Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .with_context(|| format!("Invalid time {:?}, expected YYYY-MM-DD or RFC 3339", text))?;
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(*tz).earliest())
+        .map(|midnight| midnight.with_timezone(&chrono::Utc))
+        .with_context(|| format!("{} has no midnight in {}", date, tz))
+}
+
+/// A START..END range of dates (midnight in `tz`) or RFC 3339 times
+fn parse_time_range(
+    text: &str,
+    tz: &chrono_tz::Tz,
+) -> Result<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> {
+    let (start, end) = text
+        .split_once("..")
+        .with_context(|| format!("Invalid range {:?}, expected START..END", text))?;
+    let (start, end) = (parse_time(start, tz)?, parse_time(end, tz)?);
+    if start >= end {
+        anyhow::bail!("Range {:?} ends before it starts", text);
+    }
+
+    Ok((start, end))
+}
+
+async fn handle_volume_stats(config_path: &str, pair_name: &str, hours: i64) -> Result<()> {
+    let config = Config::from_file(config_path)
+        .context("Failed to load config file")?;
+
+    let database = Database::new(&config.database).await?;
+
+    let stats = database.get_volume_statistics(pair_name, hours).await?;
+
+    println!("\n{}", "=".repeat(60));
+    println!("Slippage Statistics");
+    println!("{}", "=".repeat(60));
+    println!("{}", stats.format_summary(&config.timezone));
+    println!("{}", "=".repeat(60));
+
+    Ok(())
+}
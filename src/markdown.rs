@@ -0,0 +1,101 @@
+use std::fmt::{self, Display};
+
+/// Telegram MarkdownV2 text. Everything added through the builder is escaped,
+/// so dynamic content such as a pair named "BTC-ETH" can't break a message.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Markdown(String);
+
+impl Markdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.0.push_str(&escape(text));
+        self
+    }
+
+    pub fn bold(mut self, text: &str) -> Self {
+        self.0.push_str(&format!("*{}*", escape(text)));
+        self
+    }
+
+    pub fn italic(mut self, text: &str) -> Self {
+        self.0.push_str(&format!("_{}_", escape(text)));
+        self
+    }
+
+    pub fn code(mut self, text: &str) -> Self {
+        self.0.push_str(&format!("`{}`", escape_code(text)));
+        self
+    }
+
+    /// A preformatted block on lines of its own
+    pub fn pre(mut self, text: &str) -> Self {
+        self.0.push_str(&format!("```\n{}\n```", escape_code(text)));
+        self
+    }
+
+    pub fn newline(mut self) -> Self {
+        self.0.push('\n');
+        self
+    }
+
+    /// A blank line between paragraphs
+    pub fn paragraph(mut self) -> Self {
+        self.0.push_str("\n\n");
+        self
+    }
+}
+
+impl Display for Markdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Markdown> for String {
+    fn from(markdown: Markdown) -> Self {
+        markdown.0
+    }
+}
+
+/// Fill the `{name}` placeholders of a MarkdownV2 template, escaping the values
+pub fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), &escape(&value.to_string()))
+        })
+}
+
+/// Escape special characters for MarkdownV2 text outside code
+pub fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' | '_' | '*' | '[' | ']' | '(' | ')' | '~' | '`' | '>' | '#' | '+' | '-' | '='
+            | '|' | '{' | '}' | '.' | '!' => {
+                format!("\\{}", c)
+            }
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+/// Escape text inside a MarkdownV2 code span or block
+pub fn escape_code(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+/// MarkdownV2 as plain text: escapes resolved and `*`, `_` and `` ` `` markers dropped
+pub fn strip(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => plain.extend(chars.next()),
+            '*' | '_' | '`' => {}
+            _ => plain.push(c),
+        }
+    }
+    plain
+}
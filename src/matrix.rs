@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::notifier::{Alert, AlertKind, Line, Message, Notifier, Span};
+
+/// How long one request may take, so an unresponsive server can't hold up the monitor
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts notifications to a Matrix room through the client-server API
+pub struct MatrixNotifier {
+    client: Client,
+    homeserver: String,
+    access_token: String,
+    room_id: String,
+    /// Makes transaction ids unique within this process; the start time makes
+    /// them unique across restarts
+    next_txn: AtomicU64,
+}
+
+impl MatrixNotifier {
+    pub fn new(homeserver: &str, access_token: &str, room_id: &str) -> Self {
+        Self {
+            client: Client::new(),
+            homeserver: homeserver.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+            room_id: room_id.to_string(),
+            next_txn: AtomicU64::new(chrono::Utc::now().timestamp_millis() as u64),
+        }
+    }
+
+    /// Send a message event; `m.notice` is for updates clients may not notify about
+    async fn send(&self, message: &Message, msgtype: &str) -> Result<()> {
+        let txn_id = self.next_txn.fetch_add(1, Ordering::Relaxed).to_string();
+        let mut url = Url::parse(&self.homeserver).context("Invalid Matrix homeserver URL")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid Matrix homeserver URL"))?
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+
+        let body = format!("{}\n\n{}", message.title.plain_text(), message.body_text());
+        self.client
+            .put(url)
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(&self.access_token)
+            .json(&json!({
+                "msgtype": msgtype,
+                "body": body.trim_end(),
+                "format": "org.matrix.custom.html",
+                "formatted_body": render_html(message),
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let msgtype = match alert.kind {
+            AlertKind::Alert | AlertKind::Escalation if !alert.silent => "m.text",
+            _ => "m.notice",
+        };
+
+        self.send(&alert.message, msgtype)
+            .await
+            .context("Failed to send Matrix alert")
+    }
+
+    async fn send_periodic(&self, message: &Message) -> Result<()> {
+        self.send(message, "m.notice")
+            .await
+            .context("Failed to send Matrix periodic update")
+    }
+
+    async fn send_report(&self, message: &Message) -> Result<()> {
+        self.send(message, "m.notice")
+            .await
+            .context("Failed to send Matrix report")
+    }
+}
+
+/// HTML body: the title, then one paragraph per section
+fn render_html(message: &Message) -> String {
+    let mut html = format!("<p>{}</p>", render_line(&message.title));
+    for section in &message.sections {
+        let lines: Vec<String> = section.iter().map(render_line).collect();
+        html.push_str(&format!("<p>{}</p>", lines.join("<br>")));
+    }
+    html
+}
+
+fn render_line(line: &Line) -> String {
+    line.0
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) => escape_html(text),
+            Span::Bold(text) => format!("<strong>{}</strong>", escape_html(text)),
+            Span::Code(text) => format!("<code>{}</code>", escape_html(text)),
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
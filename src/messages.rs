@@ -0,0 +1,477 @@
+use crate::config::{RatioPair, SeverityTier};
+use crate::database::CleanupSummary;
+use crate::i18n::{Texts, fill};
+use crate::notifier::{Alert, AlertDetails, AlertKind, Line, Message};
+use crate::ratio::{SimpleRatio, VolumeBasedRatio, format_leg_value};
+
+/// A leg's current value and its own move over the alert period
+pub struct LegQuote {
+    pub label: String,
+    /// Price for symbols, ratio for pair legs, already formatted
+    pub value: String,
+    pub move_pct: Option<f64>,
+}
+
+/// Extra detail shown under an alert so the cause of a move is visible
+pub struct AlertContext {
+    pub legs: [LegQuote; 2],
+    pub change_24h_pct: Option<f64>,
+}
+
+impl AlertContext {
+    /// Each leg's value and move, then the 24h change
+    fn lines(&self, t: &Texts) -> Vec<Line> {
+        let mut lines: Vec<Line> = self
+            .legs
+            .iter()
+            .map(|leg| {
+                let mut line = Line::new()
+                    .text(format!("{}: ", leg.label))
+                    .code(&leg.value);
+                if let Some(move_pct) = leg.move_pct {
+                    line = line.text(format!(" ({:+.2}%)", move_pct));
+                }
+                line
+            })
+            .collect();
+
+        if let Some(change) = self.change_24h_pct {
+            lines.push(
+                Line::new()
+                    .text(t.change_24h)
+                    .code(format!("{:+.2}%", change)),
+            );
+        }
+
+        lines
+    }
+}
+
+/// Alert for a ratio move over a rule's window; `thresholds` are the levels
+/// crossed by this move, lowest first
+#[allow(clippy::too_many_arguments)]
+pub fn ratio_alert(
+    t: &Texts,
+    pair_name: &str,
+    ratio: f64,
+    change_pct: f64,
+    time_window: &str,
+    thresholds: &[f64],
+    context: &AlertContext,
+    tier: Option<&SeverityTier>,
+) -> Alert {
+    let period = fill(t.in_window, &[("window", &time_window)]);
+    let heading = fill(t.ratio_alert, &[("pair", &pair_name)]);
+    let message = Message::new(alert_title(t, tier, change_pct, heading))
+        .section(vec![
+            Line::new()
+                .text(t.current_ratio)
+                .code(format!("{:.8}", ratio)),
+            Line::new()
+                .text(t.change)
+                .code(format!("{:+.2}%", change_pct))
+                .text(format!(" {}", period)),
+            threshold_line(t, thresholds),
+        ])
+        .section(context.lines(t))
+        .now();
+
+    Alert::new(AlertKind::Alert, message)
+        .with_tier(tier)
+        .with_details(AlertDetails {
+            pair: pair_name.to_string(),
+            ratio,
+            change_pct,
+            period: Some(period),
+            thresholds: thresholds.to_vec(),
+        })
+}
+
+/// Alert for a change in the executable (order book) ratio of a pair
+pub fn volume_ratio_alert(
+    t: &Texts,
+    ratio: &VolumeBasedRatio,
+    change_pct: f64,
+    time_window: &str,
+    thresholds: &[f64],
+    tier: Option<&SeverityTier>,
+) -> Alert {
+    let period = fill(t.in_window, &[("window", &time_window)]);
+    let heading = fill(t.executable_ratio_alert, &[("pair", &ratio.pair_name)]);
+    let title = alert_title(t, tier, change_pct, heading);
+    let leg = |symbol: &str, price: f64, slippage: f64| {
+        Line::new()
+            .text(format!("{}: ", symbol))
+            .code(format!("{:.8}", price))
+            .text(fill(
+                t.slippage,
+                &[("slippage", &format!("{:.3}", slippage))],
+            ))
+    };
+
+    let message = Message::new(title)
+        .section(vec![
+            Line::new()
+                .text(t.executable_ratio)
+                .code(format!("{:.8}", ratio.ratio))
+                .text(t.at_volume)
+                .code(ratio.volume.to_string()),
+            Line::new()
+                .text(t.change)
+                .code(format!("{:+.2}%", change_pct))
+                .text(format!(" {}", period)),
+            threshold_line(t, thresholds),
+        ])
+        .section(vec![
+            leg(&ratio.symbol_a, ratio.effective_price_a, ratio.slippage_a),
+            leg(&ratio.symbol_b, ratio.effective_price_b, ratio.slippage_b),
+        ])
+        .now();
+
+    Alert::new(AlertKind::Alert, message)
+        .with_tier(tier)
+        .with_details(AlertDetails {
+            pair: ratio.pair_name.clone(),
+            ratio: ratio.ratio,
+            change_pct,
+            period: Some(format!("{} {}", period, t.executable)),
+            thresholds: thresholds.to_vec(),
+        })
+}
+
+/// Alert for a change measured from the UTC daily open
+#[allow(clippy::too_many_arguments)]
+pub fn daily_change_alert(
+    t: &Texts,
+    pair_name: &str,
+    ratio: f64,
+    open_ratio: f64,
+    change_pct: f64,
+    thresholds: &[f64],
+    context: &AlertContext,
+    tier: Option<&SeverityTier>,
+) -> Alert {
+    let heading = fill(
+        t.daily_change_alert,
+        &[
+            ("pair", &pair_name),
+            ("change", &format!("{:+.2}", change_pct)),
+        ],
+    );
+    let title = alert_title(t, tier, change_pct, heading);
+    let message = Message::new(title)
+        .section(vec![
+            Line::new()
+                .text(t.current_ratio)
+                .code(format!("{:.8}", ratio)),
+            Line::new()
+                .text(t.daily_open)
+                .code(format!("{:.8}", open_ratio)),
+            threshold_line(t, thresholds),
+        ])
+        .section(context.lines(t))
+        .now();
+
+    Alert::new(AlertKind::Alert, message)
+        .with_tier(tier)
+        .with_details(AlertDetails {
+            pair: pair_name.to_string(),
+            ratio,
+            change_pct,
+            period: Some(t.on_the_day.to_string()),
+            thresholds: thresholds.to_vec(),
+        })
+}
+
+/// Follow-up when a ratio that breached a threshold is back inside its band
+pub fn recovery(
+    t: &Texts,
+    pair_name: &str,
+    ratio: f64,
+    change_pct: f64,
+    time_window: &str,
+    excursion: &str,
+) -> Alert {
+    let period = fill(t.in_window, &[("window", &time_window)]);
+    let title = Line::new()
+        .text("✅ ")
+        .bold(fill(t.back_to_normal, &[("pair", &pair_name)]));
+    let message = Message::new(title)
+        .section(vec![
+            Line::new()
+                .text(t.current_ratio)
+                .code(format!("{:.8}", ratio)),
+            Line::new()
+                .text(t.change)
+                .code(format!("{:+.2}%", change_pct))
+                .text(format!(" {}", period)),
+            Line::new().text(fill(t.excursion_lasted, &[("duration", &excursion)])),
+        ])
+        .now();
+
+    let mut alert = Alert::new(AlertKind::Alert, message).with_details(AlertDetails {
+        pair: pair_name.to_string(),
+        ratio,
+        change_pct,
+        period: Some(period),
+        thresholds: Vec::new(),
+    });
+    alert.follow_up = true;
+    alert
+}
+
+/// Reminder for a breach that has not recovered
+pub fn escalation(
+    t: &Texts,
+    pair_name: &str,
+    ratio: f64,
+    change_pct: f64,
+    breached_for: &str,
+) -> Alert {
+    let title = Line::new()
+        .text("🚨🚨 ")
+        .bold(fill(t.still_breached, &[("pair", &pair_name)]))
+        .text(" 🚨🚨");
+    let message = Message::new(title)
+        .section(vec![
+            Line::new()
+                .text(t.current_ratio)
+                .code(format!("{:.8}", ratio)),
+            Line::new()
+                .text(t.change)
+                .code(format!("{:+.2}%", change_pct)),
+            Line::new().text(t.breached_for).bold(breached_for),
+        ])
+        .now();
+
+    let mut alert = Alert::new(AlertKind::Escalation, message).with_details(AlertDetails {
+        pair: pair_name.to_string(),
+        ratio,
+        change_pct,
+        period: None,
+        thresholds: Vec::new(),
+    });
+    alert.follow_up = true;
+    alert
+}
+
+/// Digest of queued alerts, one section per pair
+pub fn alert_digest(
+    t: &Texts,
+    alert_count: usize,
+    sections: Vec<Vec<Line>>,
+    silent: bool,
+) -> Alert {
+    let title = Line::new()
+        .text("📬 ")
+        .bold(t.alert_digest)
+        .text(fill(t.alert_count, &[("count", &alert_count)]));
+    let mut message = Message::new(title);
+    for section in sections {
+        message = message.section(section);
+    }
+
+    let mut alert = Alert::new(AlertKind::Alert, message.now());
+    alert.silent = silent;
+    alert
+}
+
+/// Count of alerts dropped by the hourly alert cap
+pub fn suppressed_alerts(t: &Texts, suppressed: usize, max_per_hour: u32) -> Alert {
+    let title = Line::new().text(fill(
+        t.alerts_suppressed,
+        &[("count", &suppressed), ("limit", &max_per_hour)],
+    ));
+    Alert::new(AlertKind::Notice, Message::new(title))
+}
+
+/// Periodic ratio update, led by any monitoring gaps found at startup
+pub fn periodic_update(
+    t: &Texts,
+    updates: Vec<Vec<Line>>,
+    ratios: Vec<SimpleRatio>,
+    gaps: &[String],
+) -> Message {
+    let mut message = Message::new(Line::new().text("📊 ").bold(t.periodic_update));
+    if !gaps.is_empty() {
+        let mut lines = vec![Line::new().text("⚠️ ").bold(t.monitoring_gaps)];
+        lines.extend(gaps.iter().map(|g| Line::new().text(format!("• {}", g))));
+        message = message.section(lines);
+    }
+    for update in updates {
+        message = message.section(update);
+    }
+
+    message.with_ratios(ratios).now()
+}
+
+/// Pinned status of the latest ratios, one section per pair
+pub fn live_status(t: &Texts, updates: Vec<Vec<Line>>, ratios: Vec<SimpleRatio>) -> Message {
+    let mut message = Message::new(Line::new().text("📡 ").bold(t.live_status));
+    for update in updates {
+        message = message.section(update);
+    }
+
+    message.with_ratios(ratios).now()
+}
+
+/// A pair's section of the periodic update and live status: its ratio, the
+/// 24h sparkline if there is one, and its legs
+pub fn ratio_lines(
+    t: &Texts,
+    pair: &RatioPair,
+    ratio: &SimpleRatio,
+    sparkline: Option<&str>,
+) -> Vec<Line> {
+    let mut lines = vec![
+        Line::new().bold(&pair.name),
+        Line::new().code(format!("{:.8}", ratio.ratio)),
+    ];
+    if let Some(sparkline) = sparkline {
+        lines.push(Line::new().code(sparkline).text(t.last_24h));
+    }
+    lines.push(
+        Line::new()
+            .text(format!("{} ", ratio.symbol_a))
+            .code(format_leg_value(pair.leg_a(), ratio.price_a))
+            .text(format!(" / {} ", ratio.symbol_b))
+            .code(format_leg_value(pair.leg_b(), ratio.price_b)),
+    );
+    lines
+}
+
+/// Daily report, one section per pair
+pub fn daily_summary(t: &Texts, sections: Vec<Vec<Line>>) -> Message {
+    let mut message = Message::new(
+        Line::new()
+            .text("🗓 ")
+            .bold(t.daily_summary)
+            .text(t.last_24h),
+    );
+    for section in sections {
+        message = message.section(section);
+    }
+    message
+}
+
+/// Weekly report: pairs that broke out of last week's range, then one section per pair
+pub fn weekly_report(t: &Texts, breakouts: Vec<Line>, sections: Vec<Vec<Line>>) -> Message {
+    let mut message = Message::new(Line::new().text("📅 ").bold(t.weekly_report));
+    if !breakouts.is_empty() {
+        let mut lines = vec![Line::new().text("🚀 ").bold(t.broke_out)];
+        lines.extend(breakouts);
+        message = message.section(lines);
+    }
+    for section in sections {
+        message = message.section(section);
+    }
+    message
+}
+
+/// Warning that a pair has stopped producing data
+pub fn stale_data_warning(
+    t: &Texts,
+    pair_name: &str,
+    failures: u32,
+    stale_for: &str,
+    error: &str,
+) -> Alert {
+    let title = Line::new().text("⚠️ ").bold(fill(
+        t.no_data_for,
+        &[("pair", &pair_name), ("duration", &stale_for)],
+    ));
+    let message = Message::new(title).section(vec![
+        Line::new().text(t.failed_checks).code(failures.to_string()),
+        Line::new().text(fill(t.last_error, &[("error", &error)])),
+    ]);
+
+    Alert::new(AlertKind::Notice, message)
+}
+
+/// Note that data for a previously stale pair is flowing again
+pub fn data_resumed(t: &Texts, pair_name: &str, stale_for: &str) -> Alert {
+    let title = Line::new()
+        .text("✅ ")
+        .bold(fill(t.data_resumed, &[("pair", &pair_name)]))
+        .text(fill(t.resumed_after, &[("duration", &stale_for)]));
+    Alert::new(AlertKind::Notice, Message::new(title))
+}
+
+/// How many rows of each kind the retention cleanup purged, and after how many days
+pub fn cleanup_summary(t: &Texts, summary: &CleanupSummary) -> Alert {
+    let title = Line::new().text(t.purged);
+    let retention = &summary.retention;
+    let kinds = [
+        (
+            t.purged_snapshots,
+            summary.ratio_snapshots,
+            retention.snapshots,
+        ),
+        (t.purged_alerts, summary.alerts, retention.alerts),
+        (
+            t.purged_volume_ratios,
+            summary.volume_ratios,
+            retention.volume_ratios,
+        ),
+        (
+            t.purged_order_books,
+            summary.order_books,
+            retention.order_books,
+        ),
+        (t.purged_checks, summary.checks, retention.checks),
+    ];
+    let lines: Vec<Line> = kinds
+        .into_iter()
+        .filter(|(_, count, _)| *count > 0)
+        .filter_map(|(text, count, days)| {
+            days.map(|days| Line::new().text(fill(text, &[("count", &count), ("days", &days)])))
+        })
+        .collect();
+    let message = Message::new(title).section(lines);
+
+    Alert::new(AlertKind::Notice, message)
+}
+
+/// Announcement that the monitor has started, with an optional configuration summary
+pub fn startup_notice(t: &Texts, text: Option<&str>, summary: Option<&str>) -> Alert {
+    let title = Line::new().text(text.unwrap_or(t.monitor_started));
+    let lines = summary
+        .map(|s| s.lines().map(Line::from).collect())
+        .unwrap_or_default();
+
+    Alert::new(AlertKind::Notice, Message::new(title).section(lines))
+}
+
+/// Announcement that the monitor is shutting down
+pub fn shutdown_notice(t: &Texts, text: Option<&str>) -> Alert {
+    let title = Line::new().text(text.unwrap_or(t.monitor_stopping));
+    Alert::new(AlertKind::Notice, Message::new(title))
+}
+
+/// Title led by the severity tier and a direction emoji
+fn alert_title(t: &Texts, tier: Option<&SeverityTier>, change_pct: f64, heading: String) -> Line {
+    let mut title = Line::new();
+    if let Some(tier) = tier {
+        title = title
+            .text(format!("{} ", tier.severity.emoji()))
+            .bold(t.severity(tier.severity))
+            .text(" ");
+    }
+
+    let emoji = if change_pct > 0.0 { "📈" } else { "📉" };
+    title.text(format!("{} ", emoji)).bold(heading)
+}
+
+/// "Threshold: 15%", listing every level when a move crossed several at once
+fn threshold_line(t: &Texts, thresholds: &[f64]) -> Line {
+    let Some(highest) = thresholds.last() else {
+        return Line::new();
+    };
+
+    let mut line = Line::new().text(t.threshold).code(format!("{}%", highest));
+    if thresholds.len() > 1 {
+        let levels: Vec<String> = thresholds.iter().map(|t| format!("{}%", t)).collect();
+        line = line.text(fill(t.crossed, &[("levels", &levels.join(", "))]));
+    }
+    line
+}
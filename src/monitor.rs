@@ -1,273 +1,2399 @@
 use anyhow::Result;
-use std::collections::HashMap;
+use chrono_tz::Tz;
+use futures::stream::{self, StreamExt};
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::time::Duration;
-use tokio::time::{interval, Instant};
+use tokio::time::{sleep_until, Instant};
 
-use crate::config::{Config, RatioPair};
-use crate::database::Database;
-use crate::ratio::{RatioCalculator, SimpleRatio};
-use crate::telegram::TelegramNotifier;
+use crate::binance;
+use crate::chart;
+use crate::config::{BaselineMode, ChangeRule, Config, RatioPair, Severity, SharedConfig};
+use crate::database::{Database, RatioRecord};
+use crate::heartbeat::Heartbeat;
+use crate::i18n::{Texts, fill};
+use crate::messages::{self, AlertContext, LegQuote};
+use crate::mqtt::MqttPublisher;
+use crate::notifier::{Line, Notifiers};
+use crate::ratio::{format_leg_value, RatioCalculator, SimpleRatio, VolumeBasedRatio};
+use crate::report::{next_occurrence, sparkline, PairDaySummary, PairWeekComparison, WeekStats};
+use crate::systemd::{self, Watchdog};
+use crate::templates::{
+    AlertVars, DailySummaryVars, DaySummaryVars, PeriodicPairVars, PeriodicVars, Templates,
+};
 
+/// Maximum number of pairs fetched from Binance at the same time
+const MAX_CONCURRENT_CHECKS: usize = 8;
+
+/// Extra time a lease heartbeat may be late before another monitor can take over
+const LEASE_GRACE: Duration = Duration::from_secs(60);
+
+/// Period over which `max_alerts_per_hour` is counted
+const ALERT_RATE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// How often old data is condensed and purged when `rollup_after_days` or a
+/// retention period is set
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Binance kline intervals usable for backfilling, shortest first
+const BACKFILL_INTERVALS: [(&str, u64); 6] = [
+    ("1m", 60),
+    ("5m", 5 * 60),
+    ("15m", 15 * 60),
+    ("1h", 60 * 60),
+    ("4h", 4 * 60 * 60),
+    ("1d", 24 * 60 * 60),
+];
+
+/// Most klines Binance returns per request
+const MAX_BACKFILL_CANDLES: u64 = 1000;
+
+/// Hours covered by the sparkline in periodic updates, one character each
+const SPARKLINE_HOURS: usize = 24;
+
+/// Metric name under which daily-open triggered thresholds are persisted
+const DAILY_OPEN_METRIC: &str = "daily_open";
+
+/// Metric name under which a change window's triggered thresholds are persisted
+fn window_metric(window_secs: u64) -> String {
+    format!("window:{}", window_secs)
+}
+
+/// Metric name under which executable-ratio alerts for a change window are fingerprinted
+fn volume_metric(window_secs: u64) -> String {
+    format!("volume_window:{}", window_secs)
+}
+
+/// Identity of an alert for deduplication: same pair, window, threshold and direction
+fn alert_fingerprint(pair_name: &str, metric: &str, threshold: f64, change_pct: f64) -> String {
+    let direction = if change_pct > 0.0 { "up" } else { "down" };
+    format!("{}|{}|{}|{}", pair_name, metric, threshold, direction)
+}
+
+/// Alert state is tracked per pair and change window
+type RuleKey = (String, u64);
+
+/// A threshold that has alerted and is waiting to re-arm
 #[derive(Debug, Clone)]
-struct RatioSnapshot {
+struct TriggeredThreshold {
+    threshold: f64,
+    /// When the change first dropped below the re-arm level, if it is there now
+    below_since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// An alert held back for the next digest message
+#[derive(Debug, Clone)]
+struct PendingAlert {
+    pair_name: String,
     ratio: f64,
-    timestamp: chrono::DateTime<chrono::Utc>,
+    change_pct: f64,
+    /// What the change is measured over, e.g. "in 5m" or "on the day"
+    period: String,
+    severity: Option<Severity>,
+}
+
+/// Consecutive failed checks of a pair
+#[derive(Debug, Clone)]
+struct DataOutage {
+    failures: u32,
+    since: chrono::DateTime<chrono::Utc>,
+    warned: bool,
+}
+
+/// Ratio at the UTC daily open and the daily thresholds already alerted today
+#[derive(Debug, Clone)]
+struct DailyOpen {
+    date: chrono::NaiveDate,
+    ratio: f64,
+    triggered: Vec<f64>,
+}
+
+pub struct RatioMonitor {
+    config: Config,
+    /// Where the config is re-read from on SIGHUP
+    config_path: Option<String>,
+    /// Handed the reloaded config, for the bot running alongside
+    shared_config: Option<SharedConfig>,
+    /// Identity under which this process holds the single-instance lease
+    lease_owner: Option<String>,
+    calculator: RatioCalculator,
+    notifier: Notifiers,
+    database: Database,
+    /// Pinged after each cycle that got data, when `heartbeat_url` is set
+    heartbeat: Option<Heartbeat>,
+    /// Receives each pair's latest ratio, when `[mqtt]` is set
+    mqtt: Option<MqttPublisher>,
+    /// User templates for alerts, periodic updates and daily summaries
+    templates: Templates,
+    last_periodic_notification: Instant,
+    /// Next periodic update when they follow `periodic_notification_cron`
+    next_scheduled_notification: Option<chrono::DateTime<Tz>>,
+    next_daily_summary: Option<chrono::DateTime<Tz>>,
+    next_weekly_report: Option<chrono::DateTime<Tz>>,
+    last_cleanup: Option<Instant>,
+    triggered_thresholds: HashMap<RuleKey, Vec<TriggeredThreshold>>,
+    /// When each rule's current threshold excursion started
+    excursions: HashMap<RuleKey, chrono::DateTime<chrono::Utc>>,
+    /// When each rule in an excursion was last escalated
+    last_escalations: HashMap<RuleKey, chrono::DateTime<chrono::Utc>>,
+    daily_opens: HashMap<String, DailyOpen>,
+    next_checks: HashMap<String, Instant>,
+    /// When each pair with `analysis_volume` is next due for an order book check
+    next_volume_checks: HashMap<String, Instant>,
+    /// Executable-ratio thresholds that have alerted, re-armed once the change
+    /// drops below `threshold - rearm_margin_pct`
+    volume_triggered: HashMap<RuleKey, Vec<f64>>,
+    outages: HashMap<String, DataOutage>,
+    /// Fingerprints of recently sent alerts and when they were sent
+    recent_alerts: HashMap<String, chrono::DateTime<chrono::Utc>>,
+    /// When alerts counted against the hourly cap were sent
+    sent_alerts: VecDeque<Instant>,
+    /// Alerts dropped by the hourly cap, and when the first of them was dropped
+    suppressed_alerts: usize,
+    suppressed_since: Option<Instant>,
+    /// Label of the maintenance window currently in effect
+    maintenance: Option<String>,
+    /// Alerts waiting for the digest, and when the first of them was queued
+    pending_digest: Vec<PendingAlert>,
+    digest_started: Option<Instant>,
+    /// Downtime gaps found at startup, reported in the next periodic update
+    gap_notes: Vec<String>,
+    /// Each pair's most recent ratio, for the live status
+    latest_ratios: HashMap<String, SimpleRatio>,
+    /// Pairs whose alerts are snoozed, refreshed each cycle
+    snoozed: HashSet<String>,
+    /// Pairs added through the bot rather than the config file, refreshed each cycle
+    bot_pairs: HashSet<String>,
 }
 
-pub struct RatioMonitor {
-    config: Config,
-    calculator: RatioCalculator,
-    notifier: TelegramNotifier,
-    database: Database,
-    history: HashMap<String, Vec<RatioSnapshot>>,
-    last_periodic_notification: Instant,
-    triggered_thresholds: HashMap<String, Vec<f64>>,
-}
+impl RatioMonitor {
+    pub fn new(
+        config: Config,
+        calculator: RatioCalculator,
+        notifier: Notifiers,
+        database: Database,
+    ) -> Self {
+        let heartbeat = config.monitoring.heartbeat_url.as_deref().map(Heartbeat::new);
+        let mqtt = config.mqtt.as_ref().map(|mqtt| MqttPublisher::new(mqtt, &config));
+        // Checked by Config::validate
+        let templates = Templates::new(&config.templates).unwrap_or_default();
+
+        Self {
+            config,
+            config_path: None,
+            shared_config: None,
+            lease_owner: None,
+            calculator,
+            notifier: notifier.with_queue(database.clone()),
+            database,
+            heartbeat,
+            mqtt,
+            templates,
+            last_periodic_notification: Instant::now(),
+            next_scheduled_notification: None,
+            next_daily_summary: None,
+            next_weekly_report: None,
+            last_cleanup: None,
+            triggered_thresholds: HashMap::new(),
+            excursions: HashMap::new(),
+            last_escalations: HashMap::new(),
+            daily_opens: HashMap::new(),
+            next_checks: HashMap::new(),
+            next_volume_checks: HashMap::new(),
+            volume_triggered: HashMap::new(),
+            outages: HashMap::new(),
+            recent_alerts: HashMap::new(),
+            sent_alerts: VecDeque::new(),
+            suppressed_alerts: 0,
+            suppressed_since: None,
+            maintenance: None,
+            pending_digest: Vec::new(),
+            digest_started: None,
+            gap_notes: Vec::new(),
+            latest_ratios: HashMap::new(),
+            snoozed: HashSet::new(),
+            bot_pairs: HashSet::new(),
+        }
+    }
+
+    /// Re-read the config from this path on SIGHUP
+    pub fn with_config_path(mut self, path: &str) -> Self {
+        self.config_path = Some(path.to_string());
+        self
+    }
+
+    /// Publish each reloaded config here
+    pub fn with_shared_config(mut self, shared: SharedConfig) -> Self {
+        self.shared_config = Some(shared);
+        self
+    }
+
+    /// Claim the single-instance lease so a second monitor on the same database
+    /// fails fast instead of double-sending alerts. `force` takes over a live lease.
+    pub async fn acquire_lease(&mut self, force: bool) -> Result<()> {
+        let owner = format!(
+            "pid {} started {}",
+            std::process::id(),
+            chrono::Utc::now().to_rfc3339()
+        );
+
+        if force {
+            log::warn!("Forcing the monitor lease; any other running monitor will stop");
+            self.database.force_acquire_lease(&owner).await?;
+        } else {
+            let ttl = chrono::Duration::from_std(self.lease_ttl())?;
+            let stale_before = chrono::Utc::now() - ttl;
+            if let Some(holder) = self.database.try_acquire_lease(&owner, stale_before).await? {
+                anyhow::bail!(
+                    "Another monitor is already running against {} ({}, last seen {}). \
+                    Stop it first, or pass --force to take over.",
+                    self.config.database.location(),
+                    holder.owner,
+                    holder.heartbeat_at.format("%Y-%m-%d %H:%M:%S UTC")
+                );
+            }
+        }
+
+        log::info!("Acquired monitor lease as {}", owner);
+        self.lease_owner = Some(owner);
+
+        Ok(())
+    }
+
+    /// How long a lease survives without a heartbeat: the monitor renews it every
+    /// cycle, so allow two of the longest check intervals plus some slack
+    fn lease_ttl(&self) -> Duration {
+        let longest = self
+            .config
+            .ratio_pairs
+            .iter()
+            .map(|p| self.config.check_interval_secs(p))
+            .max()
+            .unwrap_or(self.config.monitoring.check_interval_secs);
+
+        Duration::from_secs(longest * 2) + LEASE_GRACE
+    }
+
+    /// Refresh the lease; fails if another monitor has taken it over
+    async fn renew_lease(&self) -> Result<()> {
+        if let Some(owner) = &self.lease_owner
+            && !self.database.renew_lease(owner).await?
+        {
+            anyhow::bail!("Monitor lease was taken over by another instance");
+        }
+
+        Ok(())
+    }
+
+    /// Give up the lease so the next monitor can start immediately
+    async fn release_lease(&mut self) {
+        if let Some(owner) = self.lease_owner.take()
+            && let Err(e) = self.database.release_lease(&owner).await
+        {
+            log::error!("Error releasing monitor lease: {}", e);
+        }
+    }
+
+    /// Start monitoring ratios
+    pub async fn start(&mut self) -> Result<()> {
+        log::info!("Starting ratio monitor...");
+        log::info!("Monitoring {} pairs", self.config.ratio_pairs.len());
+
+        if self.config.monitoring.notify_on_startup {
+            let summary = self
+                .config
+                .monitoring
+                .startup_config_summary
+                .then(|| self.config.summary());
+            let notice = messages::startup_notice(
+                self.texts(),
+                self.config.monitoring.startup_message.as_deref(),
+                summary.as_deref(),
+            );
+            if let Err(e) = self.notifier.send_alert(&notice).await {
+                self.release_lease().await;
+                return Err(e);
+            }
+        }
+
+        self.update_bot_pairs().await;
+        self.update_rule_overrides().await;
+
+        if let Err(e) = self.restore_state().await {
+            log::error!("Failed to restore monitor state: {}", e);
+        }
+
+        if self.config.monitoring.backfill_gaps {
+            self.backfill_gaps().await;
+        }
+
+        // Every pair is due immediately, then follows its own interval
+        let now = Instant::now();
+        for pair in &self.config.ratio_pairs {
+            self.next_checks.insert(pair.name.clone(), now);
+        }
+
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        let mut reload = ReloadSignal::new();
+        let mut watchdog = Watchdog::new();
+        systemd::notify_ready();
+
+        loop {
+            // Signals are only observed between cycles, so an in-flight cycle completes
+            tokio::select! {
+                _ = sleep_until(self.next_check_due()) => {}
+                _ = watchdog.due() => {
+                    watchdog.ping();
+                    continue;
+                }
+                _ = reload.recv() => {
+                    self.reload_config();
+                    continue;
+                }
+                _ = &mut shutdown => break,
+            }
+
+            if let Err(e) = self.renew_lease().await {
+                log::error!("Stopping: {}", e);
+                self.lease_owner = None;
+                break;
+            }
+
+            // Older alerts that couldn't be delivered go out before new ones
+            self.notifier.flush_queue().await;
+
+            match self.check_ratios().await {
+                Ok(true) => {
+                    self.ping_heartbeat().await;
+                    self.update_live_status().await;
+                }
+                Ok(false) => log::warn!("No pair returned data this cycle; skipping heartbeat"),
+                Err(e) => log::error!("Error checking ratios: {}", e),
+            }
+
+            if let Err(e) = self.check_volume_ratios().await {
+                log::error!("Error checking volume ratios: {}", e);
+            }
+
+            if let Err(e) = self.flush_alert_digest().await {
+                log::error!("Error sending alert digest: {}", e);
+            }
+
+            if let Err(e) = self.flush_suppressed_alerts().await {
+                log::error!("Error sending suppressed alerts notice: {}", e);
+            }
+
+            if let Err(e) = self.check_periodic_notification().await {
+                log::error!("Error sending periodic notification: {}", e);
+            }
+
+            if let Err(e) = self.check_daily_summary().await {
+                log::error!("Error sending daily summary: {}", e);
+            }
+
+            if let Err(e) = self.check_weekly_report().await {
+                log::error!("Error sending weekly report: {}", e);
+            }
+
+            if let Err(e) = self.check_retention().await {
+                log::error!("Error cleaning up old records: {}", e);
+            }
+
+            // A cycle that hangs never gets here, so systemd restarts the service
+            watchdog.ping();
+        }
+
+        systemd::notify_stopping();
+
+        self.shutdown().await;
+
+        Ok(())
+    }
+
+    /// Flush pending notifications and close the database before exiting
+    async fn shutdown(&mut self) {
+        log::info!("Shutting down ratio monitor...");
+
+        if let Err(e) = self.send_alert_digest().await {
+            log::error!("Error sending alert digest: {}", e);
+        }
+
+        if self.config.monitoring.notify_on_shutdown
+            && let Err(e) = self
+                .notifier
+                .send_alert(&messages::shutdown_notice(
+                    self.texts(),
+                    self.config.monitoring.shutdown_message.as_deref(),
+                ))
+                .await
+        {
+            log::error!("Error sending shutdown notice: {}", e);
+        }
+
+        if let Some(mqtt) = &mut self.mqtt {
+            mqtt.shutdown().await;
+        }
+
+        self.release_lease().await;
+        self.database.close().await;
+        log::info!("Ratio monitor stopped");
+    }
+
+    /// Re-read and validate the config file, keeping the current config if it is invalid
+    fn reload_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            log::warn!("Received SIGHUP but no config path is known; ignoring");
+            return;
+        };
+
+        log::info!("Received SIGHUP, reloading config from {}", path);
+
+        let mut config = match Config::from_file(&path).and_then(|c| c.validate().map(|_| c)) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("Rejected reloaded config, keeping the current one: {:#}", e);
+                return;
+            }
+        };
+
+        // The bot adds pairs and rules from the database itself
+        if let Some(shared) = &self.shared_config {
+            *shared.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+        }
+
+        // Pairs added through the bot carry over unless the file now defines them
+        self.bot_pairs.retain(|name| config.find_pair(name).is_none());
+        config.add_pairs(
+            self.config
+                .ratio_pairs
+                .iter()
+                .filter(|p| self.bot_pairs.contains(&p.name))
+                .cloned(),
+        );
+        config.rule_overrides = self.config.rule_overrides.clone();
+
+        let changes = self.config.diff(&config);
+        if changes.is_empty() {
+            log::info!("Config reloaded, no changes");
+            return;
+        }
+        for change in &changes {
+            log::info!("Config change: {}", change);
+        }
+
+        if config.database.path != self.config.database.path {
+            log::warn!("database.path changed; the new path takes effect after a restart");
+        }
+
+        if config.telegram != self.config.telegram
+            || config.notifications != self.config.notifications
+            || config.timezone != self.config.timezone
+            || config.language != self.config.language
+            || config.ratio_pairs.iter().map(|p| &p.route).ne(self.config.ratio_pairs.iter().map(|p| &p.route))
+        {
+            self.notifier = Notifiers::from_config(&config, &self.database).with_queue(self.database.clone());
+        }
+
+        if config.monitoring.heartbeat_url != self.config.monitoring.heartbeat_url {
+            self.heartbeat = config.monitoring.heartbeat_url.as_deref().map(Heartbeat::new);
+        }
+
+        // Reconnect when the broker settings or the announced sensors change
+        if config.mqtt != self.config.mqtt
+            || self.mqtt.as_ref().is_some_and(|mqtt| !mqtt.matches(&config))
+        {
+            self.mqtt = config.mqtt.as_ref().map(|mqtt| MqttPublisher::new(mqtt, &config));
+        }
+
+        if config.templates != self.config.templates {
+            self.templates = Templates::new(&config.templates).unwrap_or_default();
+        }
+
+        // Drop schedules and outages of removed pairs; new pairs are due immediately
+        self.next_checks.retain(|name, _| config.find_pair(name).is_some());
+        self.next_volume_checks.retain(|name, _| config.find_pair(name).is_some());
+        self.outages.retain(|name, _| config.find_pair(name).is_some());
+
+        let timezone_changed = config.timezone != self.config.timezone;
+        if timezone_changed
+            || config.monitoring.periodic_notification_cron
+                != self.config.monitoring.periodic_notification_cron
+        {
+            self.next_scheduled_notification = None;
+        }
+        if timezone_changed
+            || config.monitoring.daily_summary_time != self.config.monitoring.daily_summary_time
+        {
+            self.next_daily_summary = None;
+        }
+        if timezone_changed || config.monitoring.weekly_report != self.config.monitoring.weekly_report {
+            self.next_weekly_report = None;
+        }
+
+        self.config = config;
+        log::info!("Config reloaded with {} changes", changes.len());
+    }
+
+    /// Reload triggered thresholds saved by a previous run
+    async fn restore_state(&mut self) -> Result<()> {
+        let mut restored = 0;
+
+        for pair in self.config.ratio_pairs.clone() {
+            for rule in self.config.change_rules(&pair) {
+                let triggered = self
+                    .database
+                    .get_triggered_thresholds(
+                        &window_metric(rule.window_secs),
+                        chrono::DateTime::UNIX_EPOCH,
+                    )
+                    .await?;
+
+                let key: RuleKey = (pair.name.clone(), rule.window_secs);
+                for record in triggered.iter().filter(|r| r.pair_name == pair.name) {
+                    log::debug!(
+                        "Restored triggered threshold {}% for {} (triggered at {})",
+                        record.threshold,
+                        record.pair_name,
+                        record.triggered_at
+                    );
+                    self.mark_threshold_triggered(&key, record.threshold);
+
+                    let started = self
+                        .excursions
+                        .entry(key.clone())
+                        .or_insert(record.triggered_at);
+                    *started = (*started).min(record.triggered_at);
+                    restored += 1;
+                }
+            }
+        }
+
+        log::info!("Restored {} triggered thresholds", restored);
+
+        let dedup_window = self.dedup_window();
+        if !dedup_window.is_zero() {
+            self.recent_alerts = self
+                .database
+                .get_alert_fingerprints(chrono::Utc::now() - dedup_window)
+                .await?;
+            log::info!("Restored {} recent alert fingerprints", self.recent_alerts.len());
+        }
+
+        Ok(())
+    }
+
+    /// Current time in the configured timezone, for schedules
+    fn local_now(&self) -> chrono::DateTime<Tz> {
+        chrono::Utc::now().with_timezone(&self.config.timezone)
+    }
+
+    /// Earliest time at which any pair is due for a check, or a scheduled
+    /// periodic update is due
+    fn next_check_due(&self) -> Instant {
+        let next_check = self
+            .next_checks
+            .values()
+            .chain(self.next_volume_checks.values())
+            .min()
+            .copied()
+            .unwrap_or_else(|| {
+                Instant::now() + Duration::from_secs(self.config.monitoring.check_interval_secs)
+            });
+
+        [
+            self.next_scheduled_notification,
+            self.next_daily_summary,
+            self.next_weekly_report,
+        ]
+            .into_iter()
+            .flatten()
+            .map(|next| {
+                let wait = (next - self.local_now()).to_std().unwrap_or_default();
+                Instant::now() + wait
+            })
+            .fold(next_check, Instant::min)
+    }
+
+    /// Fill the gap between each pair's last stored snapshot and now from kline
+    /// closes, so history and stats don't silently miss the downtime
+    async fn backfill_gaps(&mut self) {
+        let now = chrono::Utc::now();
+        let paused = self.paused_pairs().await;
+
+        // Composite pairs have no klines of their own
+        for pair in self.config.ratio_pairs.clone() {
+            if pair.is_composite() || paused.contains(&pair.name) {
+                continue;
+            }
+
+            let last = match self.database.get_ratio_history(&pair.name, 1).await {
+                Ok(records) => match records.into_iter().next() {
+                    Some(last) => last,
+                    None => continue,
+                },
+                Err(e) => {
+                    log::error!("Failed to read last snapshot of {}: {}", pair.name, e);
+                    continue;
+                }
+            };
+
+            // Anything up to two missed checks is normal scheduling jitter
+            let check_secs = self.config.check_interval_secs(&pair);
+            let gap_secs = (now - last.timestamp).num_seconds().max(0) as u64;
+            if gap_secs < (check_secs * 2).max(BACKFILL_INTERVALS[0].1) {
+                continue;
+            }
+
+            // Finest candles no denser than the check interval that cover the gap
+            // in one request; for very long gaps only the most recent part is filled
+            let (interval, interval_secs) = BACKFILL_INTERVALS
+                .iter()
+                .copied()
+                .find(|(_, secs)| *secs >= check_secs && gap_secs / secs <= MAX_BACKFILL_CANDLES)
+                .unwrap_or(BACKFILL_INTERVALS[BACKFILL_INTERVALS.len() - 1]);
+            let span = chrono::Duration::seconds((interval_secs * MAX_BACKFILL_CANDLES) as i64);
+            let start = last.timestamp.max(now - span);
+
+            let since = last
+                .timestamp
+                .with_timezone(&self.config.timezone)
+                .format("%Y-%m-%d %H:%M %Z");
+            log::warn!(
+                "No snapshots of {} for {} since {}; backfilling from {} klines",
+                pair.name,
+                format_duration(gap_secs),
+                since,
+                interval
+            );
+
+            let outcome = match self.backfill_pair(&pair, interval, start, now).await {
+                Ok(count) => {
+                    log::info!("Backfilled {} snapshots of {}", count, pair.name);
+                    fill(self.texts().backfilled, &[("count", &count), ("interval", &interval)])
+                }
+                Err(e) => {
+                    log::error!("Failed to backfill {}: {}", pair.name, e);
+                    self.texts().backfill_failed.to_string()
+                }
+            };
+
+            let note = fill(
+                self.texts().gap_note,
+                &[
+                    ("pair", &pair.name),
+                    ("duration", &format_duration(gap_secs)),
+                    ("since", &since),
+                    ("outcome", &outcome),
+                ],
+            );
+            self.gap_notes.push(note);
+        }
+    }
+
+    /// Store one snapshot per kline close of both legs; returns how many were stored
+    async fn backfill_pair(
+        &self,
+        pair: &RatioPair,
+        interval: &str,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<usize> {
+        let ratios = self
+            .calculator
+            .calculate_historical_ratios(&pair.name, &pair.symbol_a, &pair.symbol_b, interval, start, end)
+            .await?;
+
+        let mut stored = 0;
+        for ratio in ratios.iter().filter(|r| r.timestamp > start) {
+            self.database
+                .insert_ratio_snapshot(
+                    &ratio.pair_name,
+                    &ratio.symbol_a,
+                    &ratio.symbol_b,
+                    ratio.price_a,
+                    ratio.price_b,
+                    ratio.ratio,
+                    ratio.timestamp,
+                )
+                .await?;
+            stored += 1;
+        }
+
+        Ok(stored)
+    }
+
+    /// Check the configured ratio pairs that are due; `false` if every pair
+    /// fetched this cycle failed
+    async fn check_ratios(&mut self) -> Result<bool> {
+        self.update_maintenance();
+        self.update_snoozed().await;
+        self.update_bot_pairs().await;
+        self.update_rule_overrides().await;
+
+        let now = Instant::now();
+        let paused = self.paused_pairs().await;
+        let (due, skipped): (Vec<RatioPair>, Vec<RatioPair>) = self
+            .config
+            .ratio_pairs
+            .iter()
+            .filter(|p| self.next_checks.get(&p.name).is_none_or(|t| *t <= now))
+            .cloned()
+            .partition(|p| !paused.contains(&p.name));
+
+        // Paused pairs keep their schedule but are neither fetched nor evaluated
+        for pair in &skipped {
+            log::debug!("Skipping paused pair {}", pair.name);
+            self.outages.remove(&pair.name);
+            let interval = Duration::from_secs(self.config.check_interval_secs(pair));
+            self.next_checks.insert(pair.name.clone(), now + interval);
+        }
+
+        // Ratios resolved this cycle, shared by composite pairs referencing them
+        let mut resolved = HashMap::new();
+        let mut failed = HashSet::new();
+
+        // Fetch symbol pairs concurrently so timestamps within a cycle stay close;
+        // composite pairs are resolved from these afterwards
+        let symbol_pairs: Vec<RatioPair> =
+            due.iter().filter(|p| !p.is_composite()).cloned().collect();
+        let fetch_count = symbol_pairs.len();
+        let paced = self.request_offsets(symbol_pairs);
+        let this = &*self;
+        let fetched: Vec<(String, Duration, Result<SimpleRatio>)> = stream::iter(paced)
+            .map(|(offset, pair)| async move {
+                sleep_until(now + offset).await;
+                this.fetch_ratio(pair).await
+            })
+            .buffer_unordered(MAX_CONCURRENT_CHECKS)
+            .collect()
+            .await;
+
+        for (name, latency, ratio) in fetched {
+            self.record_check(&name, latency, ratio.as_ref().err()).await;
+            match ratio {
+                Ok(ratio) => {
+                    resolved.insert(name, ratio);
+                }
+                Err(e) => {
+                    log::error!("Error checking pair {}: {}", name, e);
+                    self.record_data_failure(&name, &e).await;
+                    failed.insert(name);
+                }
+            }
+        }
+
+        // Evaluate sequentially: alert state is shared across pairs
+        for pair in &due {
+            if !failed.contains(&pair.name)
+                && let Err(e) = self.check_ratio_pair(pair, &mut resolved).await
+            {
+                log::error!("Error checking pair {}: {}", pair.name, e);
+            }
+
+            let interval = Duration::from_secs(self.config.check_interval_secs(pair));
+            self.next_checks.insert(pair.name.clone(), now + interval);
+        }
+
+        Ok(fetch_count == 0 || failed.len() < fetch_count)
+    }
+
+    /// Tell the dead man's switch the monitor is alive
+    async fn ping_heartbeat(&self) {
+        if let Some(heartbeat) = &self.heartbeat
+            && let Err(e) = heartbeat.ping().await
+        {
+            log::warn!("{:#}", e);
+        }
+    }
+
+    /// Random start offsets within `request_spread_secs`, earliest first so the
+    /// concurrency limit doesn't hold back requests that are already due
+    fn request_offsets(&self, pairs: Vec<RatioPair>) -> Vec<(Duration, RatioPair)> {
+        let spread_ms = self.config.monitoring.request_spread_secs.unwrap_or(0) * 1000;
+        let mut rng = rand::thread_rng();
+        let mut paced: Vec<(Duration, RatioPair)> = pairs
+            .into_iter()
+            .map(|pair| {
+                let offset = if spread_ms > 0 { rng.gen_range(0..spread_ms) } else { 0 };
+                (Duration::from_millis(offset), pair)
+            })
+            .collect();
+        paced.sort_by_key(|(offset, _)| *offset);
+        paced
+    }
+
+    /// Compute and store the executable ratio of due pairs with `analysis_volume`,
+    /// alerting when it moves past the pair's thresholds
+    async fn check_volume_ratios(&mut self) -> Result<()> {
+        let Some(interval) = self.config.monitoring.volume_check_interval_secs else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let paused = self.paused_pairs().await;
+        let due: Vec<(RatioPair, f64)> = self
+            .config
+            .ratio_pairs
+            .iter()
+            .filter(|p| !p.is_composite() && !paused.contains(&p.name))
+            .filter(|p| self.next_volume_checks.get(&p.name).is_none_or(|t| *t <= now))
+            .filter_map(|p| Some((p.clone(), p.analysis_volume?)))
+            .collect();
+
+        for (pair, volume) in due {
+            self.next_volume_checks
+                .insert(pair.name.clone(), now + Duration::from_secs(interval));
+
+            let current = match self
+                .calculator
+                .calculate_volume_based_ratio(&pair.name, &pair.symbol_a, &pair.symbol_b, volume)
+                .await
+            {
+                Ok(ratio) => ratio,
+                Err(e) => {
+                    log::error!("Error checking volume ratio of {}: {}", pair.name, e);
+                    continue;
+                }
+            };
+
+            log::debug!(
+                "Checked {} at volume {}: executable ratio = {:.8}",
+                pair.name,
+                volume,
+                current.ratio
+            );
+
+            if let Err(e) = self
+                .database
+                .insert_volume_ratio(
+                    &pair.name,
+                    &current.symbol_a,
+                    &current.symbol_b,
+                    current.volume,
+                    current.effective_price_a,
+                    current.effective_price_b,
+                    current.ratio,
+                    current.slippage_a,
+                    current.slippage_b,
+                    current.timestamp,
+                )
+                .await
+            {
+                log::error!("Failed to save volume ratio to database: {}", e);
+            }
+
+            if self.maintenance.is_some() || self.snoozed.contains(&pair.name) {
+                continue;
+            }
+
+            for rule in self.config.change_rules(&pair) {
+                if let Err(e) = self.check_volume_rule(&pair, &rule, &current).await {
+                    log::error!("Error checking volume ratio of {}: {}", pair.name, e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compare the executable ratio with the oldest one stored in the rule's window
+    async fn check_volume_rule(
+        &mut self,
+        pair: &RatioPair,
+        rule: &ChangeRule,
+        current: &VolumeBasedRatio,
+    ) -> Result<()> {
+        let window_start = current.timestamp - chrono::Duration::seconds(rule.window_secs as i64);
+        let window = self
+            .database
+            .get_volume_ratio_history_range(&pair.name, window_start, current.timestamp)
+            .await?;
+
+        // Oldest record in the window (range query is newest first)
+        let Some(baseline) = window.last().map(|r| r.ratio) else {
+            return Ok(());
+        };
+
+        let change_pct = ((current.ratio - baseline) / baseline) * 100.0;
+        let abs_change = change_pct.abs();
+        let key: RuleKey = (pair.name.clone(), rule.window_secs);
+
+        let margin = self.config.monitoring.rearm_margin_pct;
+        let triggered = self.volume_triggered.entry(key.clone()).or_default();
+        triggered.retain(|t| abs_change >= t - margin);
+
+        if !self.config.alert_direction(pair).allows(change_pct) {
+            return Ok(());
+        }
+
+        let metric = volume_metric(rule.window_secs);
+        let mut crossed = Vec::new();
+        for &threshold in &rule.thresholds {
+            if abs_change < threshold
+                || self.volume_triggered[&key].contains(&threshold)
+                || self.in_cooldown(pair, threshold, Some(&metric), current.timestamp).await
+            {
+                continue;
+            }
+
+            let fingerprint = alert_fingerprint(&pair.name, &metric, threshold, change_pct);
+            if self.is_duplicate_alert(&fingerprint, current.timestamp) {
+                log::info!("Suppressing duplicate alert {}", fingerprint);
+                self.volume_triggered.entry(key.clone()).or_default().push(threshold);
+                continue;
+            }
+
+            crossed.push(threshold);
+        }
+        crossed.sort_by(f64::total_cmp);
+
+        let Some(&highest) = crossed.last() else {
+            return Ok(());
+        };
+
+        let time_window = format_duration(rule.window_secs);
+        log::info!(
+            "Executable ratio breach for {}: {:.2}% change in {} at volume {} (thresholds: {:?})",
+            pair.name,
+            change_pct,
+            time_window,
+            current.volume,
+            crossed
+        );
+
+        // Over the hourly cap the alert counts as handled but isn't sent
+        let allowed = self.allow_alert(&pair.name);
+
+        // Saved before notifying, like simple ratio alerts, with effective prices
+        for &threshold in &crossed {
+            if let Err(e) = self
+                .database
+                .insert_alert(
+                    &pair.name,
+                    current.ratio,
+                    change_pct,
+                    threshold,
+                    current.effective_price_a,
+                    current.effective_price_b,
+                    Some(rule.window_secs),
+                    self.severity_label(threshold).as_deref(),
+                    Some(&metric),
+                    current.timestamp,
+                )
+                .await
+            {
+                log::error!("Failed to save alert to database: {}", e);
+            }
+        }
+
+        if !allowed {
+            // Reported in the next suppressed alerts notice
+        } else if self.config.monitoring.digest_window_secs.is_some() {
+            let period = fill(self.texts().in_window, &[("window", &time_window)]);
+            let period = format!("{} {}", period, self.texts().executable);
+            self.queue_for_digest(&pair.name, current.ratio, change_pct, highest, &period);
+        } else {
+            let alert = messages::volume_ratio_alert(
+                self.texts(),
+                current,
+                change_pct,
+                &time_window,
+                &crossed,
+                self.config.severity_for(highest),
+            );
+            self.notifier.send_alert(&alert).await?;
+        }
+
+        for &threshold in &crossed {
+            let fingerprint = alert_fingerprint(&pair.name, &metric, threshold, change_pct);
+            self.remember_alert(fingerprint, current.timestamp).await;
+            self.volume_triggered.entry(key.clone()).or_default().push(threshold);
+        }
+
+        Ok(())
+    }
+
+    /// Track entering and leaving configured maintenance windows
+    fn update_maintenance(&mut self) {
+        let current = self
+            .config
+            .maintenance_window(chrono::Utc::now())
+            .map(|w| w.label());
+
+        if current != self.maintenance {
+            match &current {
+                Some(label) => log::info!("Entering maintenance window {}; alerts suppressed", label),
+                None => log::info!("Maintenance window over; alerts resumed"),
+            }
+        }
+
+        self.maintenance = current;
+    }
+
+    /// Strings in the configured language
+    fn texts(&self) -> &'static Texts {
+        self.config.language.texts()
+    }
+
+    /// Refresh the pairs snoozed from alert buttons, logging when a snooze
+    /// starts or ends; a read failure keeps the previous set
+    async fn update_snoozed(&mut self) {
+        let snoozed = match self.database.get_snoozed_pairs(chrono::Utc::now()).await {
+            Ok(snoozed) => snoozed,
+            Err(e) => {
+                log::error!("Failed to read snoozed pairs: {}", e);
+                return;
+            }
+        };
+
+        for pair in snoozed.difference(&self.snoozed) {
+            log::info!("Alerts for {} snoozed", pair);
+        }
+        for pair in self.snoozed.difference(&snoozed) {
+            log::info!("Snooze of {} over; alerts resumed", pair);
+        }
+        self.snoozed = snoozed;
+    }
+
+    /// Add and drop the pairs added and removed through the bot since the last
+    /// cycle. Pairs the config file defines take precedence over stored ones.
+    async fn update_bot_pairs(&mut self) {
+        let stored = match self.database.get_bot_pairs().await {
+            Ok(stored) => stored,
+            Err(e) => {
+                log::error!("Failed to read pairs added through the bot: {}", e);
+                return;
+            }
+        };
+
+        let mut bot_pairs = HashSet::new();
+        for record in stored {
+            if self.bot_pairs.contains(&record.name) {
+                bot_pairs.insert(record.name);
+            } else if self.config.find_pair(&record.name).is_none() {
+                log::info!(
+                    "Monitoring {} ({}/{}), added through the bot",
+                    record.name,
+                    record.symbol_a,
+                    record.symbol_b
+                );
+                self.config
+                    .ratio_pairs
+                    .push(RatioPair::new(&record.name, &record.symbol_a, &record.symbol_b));
+                bot_pairs.insert(record.name);
+            }
+        }
+
+        for name in self.bot_pairs.difference(&bot_pairs) {
+            log::info!("Stopped monitoring {}, removed through the bot", name);
+            self.next_checks.remove(name);
+            self.next_volume_checks.remove(name);
+            self.outages.remove(name);
+        }
+        let removed: HashSet<&String> = self.bot_pairs.difference(&bot_pairs).collect();
+        self.config.ratio_pairs.retain(|p| !removed.contains(&p.name));
+        self.bot_pairs = bot_pairs;
+    }
+
+    /// Apply the change rules set and reset through the bot since the last cycle
+    async fn update_rule_overrides(&mut self) {
+        let overrides: BTreeMap<String, ChangeRule> =
+            match self.database.get_rule_overrides().await {
+                Ok(overrides) => overrides
+                    .into_iter()
+                    .map(|r| {
+                        let rule = ChangeRule {
+                            window_secs: r.window_secs,
+                            thresholds: r.thresholds,
+                        };
+                        (r.pair_name, rule)
+                    })
+                    .collect(),
+                Err(e) => {
+                    log::error!("Failed to read change rules set through the bot: {}", e);
+                    return;
+                }
+            };
+
+        for (name, rule) in &overrides {
+            if self.config.rule_overrides.get(name) != Some(rule) {
+                log::info!(
+                    "Alerting on {} at {:?}% over {}s, set through the bot",
+                    name,
+                    rule.thresholds,
+                    rule.window_secs
+                );
+            }
+        }
+        for name in self.config.rule_overrides.keys() {
+            if !overrides.contains_key(name) {
+                log::info!("Alerting on {} with its configured change rules again", name);
+            }
+        }
+        self.config.rule_overrides = overrides;
+    }
+
+    /// Pairs paused from the CLI or bot; a read failure pauses nothing
+    async fn paused_pairs(&self) -> HashSet<String> {
+        match self.database.get_paused_pairs().await {
+            Ok(paused) => paused,
+            Err(e) => {
+                log::error!("Failed to read paused pairs: {}", e);
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Calculate a symbol pair's ratio, tagged with the pair name
+    async fn fetch_ratio(&self, pair: RatioPair) -> (String, Duration, Result<SimpleRatio>) {
+        let started = Instant::now();
+        let ratio = self
+            .calculator
+            .calculate_pair_ratio(&self.config, &pair, &mut HashMap::new())
+            .await;
+        (pair.name, started.elapsed(), ratio)
+    }
+
+    /// Log a check's latency and error for `health-history`. A failed write
+    /// is logged, the check goes on
+    async fn record_check(&self, pair_name: &str, latency: Duration, error: Option<&anyhow::Error>) {
+        let message = error.map(|e| format!("{:#}", e));
+        let error = error.map(binance::error_class).zip(message.as_deref());
+        if let Err(e) = self
+            .database
+            .insert_check(pair_name, latency.as_millis() as u64, error, chrono::Utc::now())
+            .await
+        {
+            log::warn!("Failed to record check of {}: {}", pair_name, e);
+        }
+    }
+
+    /// Check a single ratio pair
+    async fn check_ratio_pair(
+        &mut self,
+        pair: &RatioPair,
+        resolved: &mut HashMap<String, SimpleRatio>,
+    ) -> Result<()> {
+        // Calculate current ratio; symbol pairs were fetched and recorded
+        // already, composite pairs are checked here
+        let started = Instant::now();
+        let ratio_data = self
+            .calculator
+            .calculate_pair_ratio(&self.config, pair, resolved)
+            .await;
+        if pair.is_composite() {
+            self.record_check(&pair.name, started.elapsed(), ratio_data.as_ref().err()).await;
+        }
+        let ratio_data = match ratio_data {
+            Ok(ratio) => ratio,
+            Err(e) => {
+                self.record_data_failure(&pair.name, &e).await;
+                return Err(e);
+            }
+        };
+
+        self.record_data_success(&pair.name).await;
+
+        log::debug!("Checked {}: ratio = {:.8}", pair.name, ratio_data.ratio);
+
+        // Persist to database
+        if let Err(e) = self
+            .database
+            .insert_ratio_snapshot(
+                &pair.name,
+                &ratio_data.symbol_a,
+                &ratio_data.symbol_b,
+                ratio_data.price_a,
+                ratio_data.price_b,
+                ratio_data.ratio,
+                ratio_data.timestamp,
+            )
+            .await
+        {
+            log::error!("Failed to save ratio to database: {}", e);
+        }
+
+        self.publish_mqtt_state(pair, &ratio_data).await;
+        self.latest_ratios.insert(pair.name.clone(), ratio_data.clone());
+
+        // Keep recording through maintenance and snoozes, but don't alert on them
+        if self.maintenance.is_some() || self.snoozed.contains(&pair.name) {
+            return Ok(());
+        }
+
+        // Check for threshold breaches (the window is read back from the database)
+        self.check_thresholds(pair, &ratio_data).await?;
+
+        if !self.config.monitoring.daily_open_thresholds.is_empty() {
+            self.check_daily_open(pair, &ratio_data).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Publish the ratio and its change over the pair's first change window to MQTT
+    async fn publish_mqtt_state(&self, pair: &RatioPair, current: &SimpleRatio) {
+        let Some(mqtt) = &self.mqtt else {
+            return;
+        };
+
+        let window_secs = self.config.primary_window_secs(pair);
+        let since = current.timestamp - chrono::Duration::seconds(window_secs as i64);
+
+        let change_pct = match self.database.get_first_snapshot_since(&pair.name, since).await {
+            Ok(record) => record.map(|r| (current.ratio - r.ratio) / r.ratio * 100.0),
+            Err(e) => {
+                log::error!("Failed to read change window for {}: {}", pair.name, e);
+                None
+            }
+        };
+
+        mqtt.publish_state(current, change_pct);
+    }
+
+    /// Count a failed check and warn once the pair has been without data for too long
+    async fn record_data_failure(&mut self, pair_name: &str, error: &anyhow::Error) {
+        let limit = self.config.monitoring.stale_after_failures;
+        let outage = self
+            .outages
+            .entry(pair_name.to_string())
+            .or_insert_with(|| DataOutage {
+                failures: 0,
+                since: chrono::Utc::now(),
+                warned: false,
+            });
+        outage.failures += 1;
+
+        // Outages during maintenance are expected; warn if they outlast it
+        if limit == 0 || outage.warned || outage.failures < limit || self.maintenance.is_some() {
+            return;
+        }
+
+        outage.warned = true;
+        let failures = outage.failures;
+        let stale_secs = (chrono::Utc::now() - outage.since).num_seconds().max(0) as u64;
+
+        log::warn!(
+            "No data for {} after {} failed checks",
+            pair_name,
+            failures
+        );
+
+        let warning = messages::stale_data_warning(
+            self.texts(),
+            pair_name,
+            failures,
+            &format_duration(stale_secs),
+            &error.to_string(),
+        );
+        if let Err(e) = self.notifier.send_alert(&warning).await {
+            log::error!("Failed to send stale data warning: {}", e);
+        }
+    }
+
+    /// Clear a pair's outage, noting the recovery if a warning was sent
+    async fn record_data_success(&mut self, pair_name: &str) {
+        let Some(outage) = self.outages.remove(pair_name) else {
+            return;
+        };
+
+        if !outage.warned {
+            return;
+        }
+
+        let stale_secs = (chrono::Utc::now() - outage.since).num_seconds().max(0) as u64;
+        log::info!("Data resumed for {}", pair_name);
+
+        if let Err(e) = self
+            .notifier
+            .send_alert(&messages::data_resumed(self.texts(), pair_name, &format_duration(stale_secs)))
+            .await
+        {
+            log::error!("Failed to send data resumed notice: {}", e);
+        }
+    }
+
+    /// Check change since the UTC daily open against the daily thresholds
+    async fn check_daily_open(&mut self, pair: &RatioPair, current: &SimpleRatio) -> Result<()> {
+        let today = current.timestamp.date_naive();
+
+        let needs_open = self
+            .daily_opens
+            .get(&pair.name)
+            .is_none_or(|open| open.date != today);
+
+        if needs_open {
+            let ratio = self.fetch_daily_open(pair, current).await?;
+
+            // Thresholds already alerted today by a previous run
+            let day_start = today.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let triggered = self
+                .database
+                .get_triggered_thresholds(DAILY_OPEN_METRIC, day_start)
+                .await?
+                .into_iter()
+                .filter(|r| r.pair_name == pair.name)
+                .map(|r| r.threshold)
+                .collect();
+
+            self.daily_opens.insert(
+                pair.name.clone(),
+                DailyOpen {
+                    date: today,
+                    ratio,
+                    triggered,
+                },
+            );
+        }
+
+        let daily_open = &self.daily_opens[&pair.name];
+        let change_pct = ((current.ratio - daily_open.ratio) / daily_open.ratio) * 100.0;
+        let open_ratio = daily_open.ratio;
+
+        if !self.config.alert_direction(pair).allows(change_pct) {
+            return Ok(());
+        }
+
+        let breached: Vec<f64> = self
+            .config
+            .monitoring
+            .daily_open_thresholds
+            .iter()
+            .copied()
+            .filter(|t| change_pct.abs() >= *t && !daily_open.triggered.contains(t))
+            .collect();
+
+        let mut crossed = Vec::new();
+        for threshold in breached {
+            if !self.in_cooldown(pair, threshold, None, current.timestamp).await {
+                crossed.push(threshold);
+            }
+        }
+        crossed.sort_by(f64::total_cmp);
+
+        // Thresholds crossed together are reported in one alert
+        let Some(&highest) = crossed.last() else {
+            return Ok(());
+        };
+
+        log::info!(
+            "Daily open threshold breach for {}: {:.2}% on the day (thresholds: {:?})",
+            pair.name,
+            change_pct,
+            crossed
+        );
+
+        for &threshold in &crossed {
+            if let Err(e) = self
+                .database
+                .insert_alert(
+                    &pair.name,
+                    current.ratio,
+                    change_pct,
+                    threshold,
+                    current.price_a,
+                    current.price_b,
+                    None,
+                    self.severity_label(threshold).as_deref(),
+                    None,
+                    current.timestamp,
+                )
+                .await
+            {
+                log::error!("Failed to save alert to database: {}", e);
+            }
+        }
+
+        if !self.allow_alert(&pair.name) {
+            // Reported in the next suppressed alerts notice
+        } else if self.config.monitoring.digest_window_secs.is_some() {
+            self.queue_for_digest(&pair.name, current.ratio, change_pct, highest, self.texts().on_the_day);
+        } else {
+            let context = self.alert_context(pair, current, None).await;
+            let alert = messages::daily_change_alert(
+                self.texts(),
+                &pair.name,
+                current.ratio,
+                open_ratio,
+                change_pct,
+                &crossed,
+                &context,
+                self.config.severity_for(highest),
+            )
+            .with_chart(self.alert_chart(pair, current).await);
+            let vars = self.alert_vars(current, change_pct, "day".to_string(), &crossed);
+            let alert = self.templates.alert(alert, &vars);
+            self.notifier.send_alert(&alert).await?;
+        }
+
+        for &threshold in &crossed {
+            if let Some(daily_open) = self.daily_opens.get_mut(&pair.name) {
+                daily_open.triggered.push(threshold);
+            }
+
+            if let Err(e) = self
+                .database
+                .save_triggered_threshold(&pair.name, DAILY_OPEN_METRIC, threshold, current.timestamp)
+                .await
+            {
+                log::error!("Failed to save triggered threshold: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Determine the ratio at today's UTC open: from daily klines for symbol
+    /// pairs, from the first stored snapshot of the day for composite pairs
+    async fn fetch_daily_open(&self, pair: &RatioPair, current: &SimpleRatio) -> Result<f64> {
+        if !pair.is_composite() {
+            return self
+                .calculator
+                .calculate_daily_open_ratio(&pair.symbol_a, &pair.symbol_b)
+                .await;
+        }
+
+        let day_start = current
+            .timestamp
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        // Range query is ordered newest first
+        let records = self
+            .database
+            .get_ratio_history_range(&pair.name, day_start, current.timestamp)
+            .await?;
+
+        Ok(records.last().map(|r| r.ratio).unwrap_or(current.ratio))
+    }
+
+    /// Check every change rule of a pair for threshold breaches
+    async fn check_thresholds(&mut self, pair: &RatioPair, current: &SimpleRatio) -> Result<()> {
+        for rule in self.config.change_rules(pair) {
+            self.check_rule(pair, &rule, current).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if any thresholds of one change rule are breached
+    async fn check_rule(
+        &mut self,
+        pair: &RatioPair,
+        rule: &ChangeRule,
+        current: &SimpleRatio,
+    ) -> Result<()> {
+        let window_start = current.timestamp - chrono::Duration::seconds(rule.window_secs as i64);
+
+        // Oldest snapshot within the time window, and the baseline taken from it
+        let (first, baseline) = match self.config.monitoring.baseline {
+            BaselineMode::Oldest => {
+                let first = self
+                    .database
+                    .get_first_snapshot_since(&pair.name, window_start)
+                    .await?;
+                let baseline = first.as_ref().map(|r| r.ratio);
+                (first, baseline)
+            }
+            BaselineMode::Ema => {
+                // Snapshots in the change window, oldest first (range query is newest first)
+                let mut window = self
+                    .database
+                    .get_ratio_history_range(&pair.name, window_start, current.timestamp)
+                    .await?;
+                window.reverse();
+                let baseline = ema_baseline(&window);
+                (window.into_iter().next(), baseline)
+            }
+        };
+
+        let baseline = match baseline {
+            Some(b) => b,
+            None => return Ok(()),
+        };
+
+        // Calculate percentage change
+        let change_pct = ((current.ratio - baseline) / baseline) * 100.0;
+        let abs_change = change_pct.abs();
+
+        let pair_name = pair.name.as_str();
+        let key: RuleKey = (pair.name.clone(), rule.window_secs);
+
+        self.update_rearm_state(&key, abs_change, current.timestamp).await;
+
+        // Back inside the smallest threshold, or moved against the alerted direction:
+        // close out any open excursion
+        let recovered = rule.thresholds.iter().all(|t| abs_change < *t)
+            || !self.config.alert_direction(pair).allows(change_pct);
+
+        if recovered {
+            self.last_escalations.remove(&key);
+            if let Some(started) = self.excursions.remove(&key) {
+                self.send_recovery(&key, current, change_pct, started).await?;
+            }
+            return Ok(());
+        }
+
+        self.check_escalation(&key, current, change_pct).await?;
 
-impl RatioMonitor {
-    pub fn new(
-        config: Config,
-        calculator: RatioCalculator,
-        notifier: TelegramNotifier,
-        database: Database,
-    ) -> Self {
-        Self {
-            config,
-            calculator,
-            notifier,
-            database,
-            history: HashMap::new(),
-            last_periodic_notification: Instant::now(),
-            triggered_thresholds: HashMap::new(),
+        // Thresholds newly crossed this cycle, reported together in one alert
+        let time_window = format_duration(rule.window_secs);
+        let metric = window_metric(rule.window_secs);
+        let mut crossed = Vec::new();
+        for &threshold in &rule.thresholds {
+            // Skip thresholds already alerted recently
+            if abs_change < threshold
+                || self.was_threshold_recently_triggered(&key, threshold)
+                || self.in_cooldown(pair, threshold, Some(&metric), current.timestamp).await
+            {
+                continue;
+            }
+
+            let fingerprint = alert_fingerprint(pair_name, &metric, threshold, change_pct);
+            if self.is_duplicate_alert(&fingerprint, current.timestamp) {
+                log::info!("Suppressing duplicate alert {}", fingerprint);
+                self.mark_threshold_triggered(&key, threshold);
+                continue;
+            }
+
+            crossed.push(threshold);
+        }
+        crossed.sort_by(f64::total_cmp);
+
+        let Some(&highest) = crossed.last() else {
+            return Ok(());
+        };
+
+        log::info!(
+            "Threshold breach for {}: {:.2}% change in {} (thresholds: {:?})",
+            pair_name,
+            change_pct,
+            time_window,
+            crossed
+        );
+
+        // Over the hourly cap the alert counts as handled but isn't sent
+        let allowed = self.allow_alert(pair_name);
+
+        // Save alerts to database before notifying, so a Telegram failure
+        // doesn't lose the record; each crossed level keeps its own row
+        for &threshold in &crossed {
+            if let Err(e) = self
+                .database
+                .insert_alert(
+                    pair_name,
+                    current.ratio,
+                    change_pct,
+                    threshold,
+                    current.price_a,
+                    current.price_b,
+                    Some(rule.window_secs),
+                    self.severity_label(threshold).as_deref(),
+                    Some(&metric),
+                    current.timestamp,
+                )
+                .await
+            {
+                log::error!("Failed to save alert to database: {}", e);
+            }
         }
+
+        if !allowed {
+            // Reported in the next suppressed alerts notice
+        } else if self.config.monitoring.digest_window_secs.is_some() {
+            let period = fill(self.texts().in_window, &[("window", &time_window)]);
+            self.queue_for_digest(pair_name, current.ratio, change_pct, highest, &period);
+        } else {
+            let context = self.alert_context(pair, current, first.as_ref()).await;
+            let alert = messages::ratio_alert(
+                self.texts(),
+                pair_name,
+                current.ratio,
+                change_pct,
+                &time_window,
+                &crossed,
+                &context,
+                self.config.severity_for(highest),
+            )
+            .with_chart(self.alert_chart(pair, current).await);
+            let vars = self.alert_vars(current, change_pct, time_window.clone(), &crossed);
+            let alert = self.templates.alert(alert, &vars);
+            self.notifier.send_alert(&alert).await?;
+        }
+
+        self.excursions.entry(key.clone()).or_insert(current.timestamp);
+
+        for &threshold in &crossed {
+            let fingerprint = alert_fingerprint(pair_name, &metric, threshold, change_pct);
+            self.remember_alert(fingerprint, current.timestamp).await;
+            self.mark_threshold_triggered(&key, threshold);
+
+            if let Err(e) = self
+                .database
+                .save_triggered_threshold(pair_name, &metric, threshold, current.timestamp)
+                .await
+            {
+                log::error!("Failed to save triggered threshold: {}", e);
+            }
+        }
+
+        Ok(())
     }
 
-    /// Start monitoring ratios
-    pub async fn start(&mut self) -> Result<()> {
-        log::info!("Starting ratio monitor...");
-        log::info!("Monitoring {} pairs", self.config.ratio_pairs.len());
+    /// Variables for the `alert` template; `crossed` is sorted lowest first
+    fn alert_vars<'a>(
+        &self,
+        current: &'a SimpleRatio,
+        change_pct: f64,
+        window: String,
+        crossed: &'a [f64],
+    ) -> AlertVars<'a> {
+        let threshold = crossed.last().copied().unwrap_or_default();
+        let severity = self.config.severity_for(threshold).map(|tier| tier.severity);
 
-        // Send initial connection test
-        self.notifier.test_connection().await?;
+        AlertVars {
+            pair: &current.pair_name,
+            ratio: current.ratio,
+            symbol_a: &current.symbol_a,
+            symbol_b: &current.symbol_b,
+            price_a: current.price_a,
+            price_b: current.price_b,
+            change: change_pct,
+            direction: if change_pct >= 0.0 { "up" } else { "down" },
+            window,
+            threshold,
+            thresholds: crossed,
+            severity,
+            severity_emoji: severity.map(|s| s.emoji()),
+        }
+    }
 
-        let mut check_interval = interval(Duration::from_secs(
-            self.config.monitoring.check_interval_secs,
-        ));
+    /// Lowercase severity recorded with an alert, if a tier applies to its threshold
+    fn severity_label(&self, threshold: f64) -> Option<String> {
+        self.config
+            .severity_for(threshold)
+            .map(|tier| tier.severity.label().to_lowercase())
+    }
 
-        loop {
-            check_interval.tick().await;
+    /// Leg values, each leg's own move since `since` and the ratio's 24h change
+    async fn alert_context(
+        &self,
+        pair: &RatioPair,
+        current: &SimpleRatio,
+        since: Option<&RatioRecord>,
+    ) -> AlertContext {
+        let move_pct = |from: f64, to: f64| (to - from) / from * 100.0;
 
-            if let Err(e) = self.check_ratios().await {
-                log::error!("Error checking ratios: {}", e);
+        // Only report a 24h change when the history actually reaches back that far
+        let day_ago = current.timestamp - chrono::Duration::hours(24);
+        let change_24h_pct = match self.database.get_first_snapshot_since(&pair.name, day_ago).await {
+            Ok(record) => record
+                .filter(|r| r.timestamp - day_ago < chrono::Duration::hours(1))
+                .map(|r| move_pct(r.ratio, current.ratio)),
+            Err(e) => {
+                log::error!("Failed to read 24h snapshot for {}: {}", pair.name, e);
+                None
             }
+        };
 
-            if let Err(e) = self.check_periodic_notification().await {
-                log::error!("Error sending periodic notification: {}", e);
+        AlertContext {
+            legs: [
+                LegQuote {
+                    label: current.symbol_a.clone(),
+                    value: format_leg_value(pair.leg_a(), current.price_a),
+                    move_pct: since.map(|r| move_pct(r.price_a, current.price_a)),
+                },
+                LegQuote {
+                    label: current.symbol_b.clone(),
+                    value: format_leg_value(pair.leg_b(), current.price_b),
+                    move_pct: since.map(|r| move_pct(r.price_b, current.price_b)),
+                },
+            ],
+            change_24h_pct,
+        }
+    }
+
+    /// PNG chart of the pair's ratio over the last `alert_chart_hours`, if
+    /// configured; a failure only costs the chart
+    async fn alert_chart(&self, pair: &RatioPair, current: &SimpleRatio) -> Option<Vec<u8>> {
+        let hours = self.config.monitoring.alert_chart_hours?;
+        let start = current.timestamp - chrono::Duration::hours(hours as i64);
+
+        let chart = self
+            .database
+            .get_ratio_history_range(&pair.name, start, current.timestamp)
+            .await
+            .and_then(|mut records| {
+                records.reverse();
+                chart::ratio_chart(&records)
+            });
+        match chart {
+            Ok(png) => Some(png),
+            Err(e) => {
+                log::warn!("No chart for {} alert: {}", pair.name, e);
+                None
             }
         }
     }
 
-    /// Check all configured ratio pairs
-    async fn check_ratios(&mut self) -> Result<()> {
-        let pairs = self.config.ratio_pairs.clone();
-        for pair in &pairs {
-            if let Err(e) = self.check_ratio_pair(pair).await {
-                log::error!("Error checking pair {}: {}", pair.name, e);
+    /// Hourly sparkline of the pair's last 24 hours, for the periodic update
+    async fn sparkline(&self, pair_name: &str, now: chrono::DateTime<chrono::Utc>) -> Option<String> {
+        let start = now - chrono::Duration::hours(SPARKLINE_HOURS as i64);
+        match self.database.get_ratio_history_range(pair_name, start, now).await {
+            Ok(mut records) => {
+                records.reverse();
+                sparkline(&records, now, SPARKLINE_HOURS, chrono::Duration::hours(1))
+            }
+            Err(e) => {
+                log::error!("Failed to read history of {} for its sparkline: {}", pair_name, e);
+                None
             }
         }
-        Ok(())
     }
 
-    /// Check a single ratio pair
-    async fn check_ratio_pair(&mut self, pair: &RatioPair) -> Result<()> {
-        // Calculate current ratio
-        let ratio_data = self
-            .calculator
-            .calculate_simple_ratio(&pair.name, &pair.symbol_a, &pair.symbol_b)
-            .await?;
+    fn dedup_window(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.config.monitoring.dedup_window_secs as i64)
+    }
 
-        log::debug!("Checked {}: ratio = {:.8}", pair.name, ratio_data.ratio);
+    /// Whether an identical alert was sent within the dedup window
+    fn is_duplicate_alert(&self, fingerprint: &str, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.recent_alerts
+            .get(fingerprint)
+            .is_some_and(|sent_at| now - *sent_at < self.dedup_window())
+    }
+
+    /// Record a sent alert so restarts within the dedup window don't repeat it
+    async fn remember_alert(&mut self, fingerprint: String, now: chrono::DateTime<chrono::Utc>) {
+        let dedup_window = self.dedup_window();
+        if dedup_window.is_zero() {
+            return;
+        }
 
-        // Store in history (in-memory)
-        self.add_to_history(&pair.name, &ratio_data);
+        let prune_before = now - dedup_window;
+        self.recent_alerts.retain(|_, sent_at| *sent_at >= prune_before);
 
-        // Persist to database
         if let Err(e) = self
             .database
-            .insert_ratio_snapshot(
-                &pair.name,
-                &pair.symbol_a,
-                &pair.symbol_b,
-                ratio_data.price_a,
-                ratio_data.price_b,
-                ratio_data.ratio,
-                ratio_data.timestamp,
-            )
+            .save_alert_fingerprint(&fingerprint, now, prune_before)
             .await
         {
-            log::error!("Failed to save ratio to database: {}", e);
+            log::error!("Failed to save alert fingerprint: {}", e);
         }
+        self.recent_alerts.insert(fingerprint, now);
+    }
 
-        // Check for threshold breaches
-        self.check_thresholds(&pair.name, &ratio_data).await?;
+    /// Re-arm triggered thresholds whose change has stayed below
+    /// (threshold - margin) for the configured time
+    async fn update_rearm_state(
+        &mut self,
+        key: &RuleKey,
+        abs_change: f64,
+        now: chrono::DateTime<chrono::Utc>,
+    ) {
+        let margin = self.config.monitoring.rearm_margin_pct;
+        let rearm_after = chrono::Duration::seconds(self.config.monitoring.rearm_after_secs as i64);
 
-        Ok(())
+        let Some(triggered) = self.triggered_thresholds.get_mut(key) else {
+            return;
+        };
+
+        let mut rearmed = Vec::new();
+        triggered.retain_mut(|t| {
+            if abs_change >= t.threshold - margin {
+                t.below_since = None;
+                return true;
+            }
+
+            let since = *t.below_since.get_or_insert(now);
+            if now - since >= rearm_after {
+                rearmed.push(t.threshold);
+                return false;
+            }
+            true
+        });
+
+        let (pair_name, window_secs) = key;
+        for threshold in rearmed {
+            log::info!(
+                "Re-armed {}% threshold for {} ({} window)",
+                threshold,
+                pair_name,
+                format_duration(*window_secs)
+            );
+
+            if let Err(e) = self
+                .database
+                .delete_triggered_threshold(pair_name, &window_metric(*window_secs), threshold)
+                .await
+            {
+                log::error!("Failed to delete triggered threshold: {}", e);
+            }
+        }
     }
 
-    /// Add ratio to history
-    fn add_to_history(&mut self, pair_name: &str, ratio_data: &SimpleRatio) {
-        let snapshot = RatioSnapshot {
-            ratio: ratio_data.ratio,
-            timestamp: ratio_data.timestamp,
+    /// Send an escalated reminder if a breach has persisted past the escalation interval
+    async fn check_escalation(
+        &mut self,
+        key: &RuleKey,
+        current: &SimpleRatio,
+        change_pct: f64,
+    ) -> Result<()> {
+        let Some(escalate_after) = self.config.monitoring.escalation_after_secs else {
+            return Ok(());
+        };
+        let Some(started) = self.excursions.get(key).copied() else {
+            return Ok(());
         };
 
-        let history = self.history.entry(pair_name.to_string()).or_insert_with(Vec::new);
-        history.push(snapshot);
+        let last = self.last_escalations.get(key).copied().unwrap_or(started);
 
-        // Keep history within the time window (plus some buffer)
-        let cutoff_time = chrono::Utc::now()
-            - chrono::Duration::seconds((self.config.monitoring.change_window_secs * 2) as i64);
+        if current.timestamp - last < chrono::Duration::seconds(escalate_after as i64) {
+            return Ok(());
+        }
+
+        let pair_name = &key.0;
+
+        // Someone acknowledged an alert of this breach: no more reminders until it recovers
+        if self
+            .database
+            .get_last_acknowledgement_time(pair_name)
+            .await?
+            .is_some_and(|acknowledged| acknowledged >= started)
+        {
+            return Ok(());
+        }
+
+        let breached_secs = (current.timestamp - started).num_seconds().max(0) as u64;
+        log::warn!(
+            "Escalating {}: breached for {}",
+            pair_name,
+            format_duration(breached_secs)
+        );
+
+        self.last_escalations.insert(key.clone(), current.timestamp);
+        if !self.allow_alert(pair_name) {
+            return Ok(());
+        }
 
-        history.retain(|s| s.timestamp > cutoff_time);
+        let alert = messages::escalation(
+            self.texts(),
+            pair_name,
+            current.ratio,
+            change_pct,
+            &format_duration(breached_secs),
+        );
+        self.notifier.send_alert(&alert).await
     }
 
-    /// Check if any thresholds are breached
-    async fn check_thresholds(&mut self, pair_name: &str, current: &SimpleRatio) -> Result<()> {
-        let history = match self.history.get(pair_name) {
-            Some(h) => h,
-            None => return Ok(()),
+    /// Notify that a pair is back to normal
+    async fn send_recovery(
+        &mut self,
+        key: &RuleKey,
+        current: &SimpleRatio,
+        change_pct: f64,
+        started: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let (pair_name, window_secs) = key;
+        let excursion_secs = (current.timestamp - started).num_seconds().max(0) as u64;
+
+        log::info!(
+            "{} back to normal after {}: {:.2}% change",
+            pair_name,
+            format_duration(excursion_secs),
+            change_pct
+        );
+
+        if let Err(e) = self
+            .database
+            .insert_recovery(pair_name, *window_secs, change_pct, started, current.timestamp)
+            .await
+        {
+            log::error!("Failed to save recovery of {}: {}", pair_name, e);
+        }
+
+        let time_window = format_duration(*window_secs);
+        let alert = messages::recovery(
+            self.texts(),
+            pair_name,
+            current.ratio,
+            change_pct,
+            &time_window,
+            &format_duration(excursion_secs),
+        );
+        self.notifier.send_alert(&alert).await
+    }
+
+    /// Hold an alert back for the next digest message
+    fn queue_for_digest(
+        &mut self,
+        pair_name: &str,
+        ratio: f64,
+        change_pct: f64,
+        threshold: f64,
+        period: &str,
+    ) {
+        self.digest_started.get_or_insert_with(Instant::now);
+        self.pending_digest.push(PendingAlert {
+            pair_name: pair_name.to_string(),
+            ratio,
+            change_pct,
+            period: period.to_string(),
+            severity: self.config.severity_for(threshold).map(|t| t.severity),
+        });
+    }
+
+    /// Take a slot under the global hourly alert cap, counting the alert as
+    /// suppressed when the cap is reached
+    fn allow_alert(&mut self, pair_name: &str) -> bool {
+        let Some(max_per_hour) = self.config.monitoring.max_alerts_per_hour else {
+            return true;
         };
 
-        let window_start = chrono::Utc::now()
-            - chrono::Duration::seconds(self.config.monitoring.change_window_secs as i64);
+        let now = Instant::now();
+        while self
+            .sent_alerts
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= ALERT_RATE_PERIOD)
+        {
+            self.sent_alerts.pop_front();
+        }
+
+        if self.sent_alerts.len() < max_per_hour as usize {
+            self.sent_alerts.push_back(now);
+            return true;
+        }
+
+        log::warn!(
+            "Alert limit of {} per hour reached, suppressing alert for {}",
+            max_per_hour,
+            pair_name
+        );
+        self.suppressed_alerts += 1;
+        self.suppressed_since.get_or_insert(now);
+        false
+    }
+
+    /// Report suppressed alerts once the cap frees up, or after an hour of flooding
+    async fn flush_suppressed_alerts(&mut self) -> Result<()> {
+        let (Some(max_per_hour), Some(since)) =
+            (self.config.monitoring.max_alerts_per_hour, self.suppressed_since)
+        else {
+            return Ok(());
+        };
 
-        // Find the oldest snapshot within the time window
-        let baseline = history
+        let now = Instant::now();
+        let slot_free = self
+            .sent_alerts
             .iter()
-            .find(|s| s.timestamp >= window_start)
-            .or_else(|| history.first());
+            .filter(|sent| now.duration_since(**sent) < ALERT_RATE_PERIOD)
+            .count()
+            < max_per_hour as usize;
 
-        let baseline = match baseline {
-            Some(b) => b,
-            None => return Ok(()),
+        if !slot_free && since.elapsed() < ALERT_RATE_PERIOD {
+            return Ok(());
+        }
+
+        let suppressed = std::mem::take(&mut self.suppressed_alerts);
+        self.suppressed_since = None;
+
+        self.notifier
+            .send_alert(&messages::suppressed_alerts(self.texts(), suppressed, max_per_hour))
+            .await
+    }
+
+    /// Send queued alerts once the digest window has passed
+    async fn flush_alert_digest(&mut self) -> Result<()> {
+        let (Some(window), Some(started)) =
+            (self.config.monitoring.digest_window_secs, self.digest_started)
+        else {
+            return Ok(());
         };
 
-        // Calculate percentage change
-        let change_pct = ((current.ratio - baseline.ratio) / baseline.ratio) * 100.0;
-        let abs_change = change_pct.abs();
+        if started.elapsed() < Duration::from_secs(window) {
+            return Ok(());
+        }
 
-        // Check each threshold
-        let thresholds = self.config.monitoring.change_thresholds.clone();
-        for threshold in thresholds {
-            if abs_change >= threshold {
-                // Check if we've already alerted for this threshold recently
-                if !self.was_threshold_recently_triggered(pair_name, threshold) {
-                    log::info!(
-                        "Threshold breach for {}: {:.2}% change (threshold: {}%)",
-                        pair_name,
-                        change_pct,
-                        threshold
-                    );
+        self.send_alert_digest().await
+    }
 
-                    let time_window = format_duration(self.config.monitoring.change_window_secs);
-
-                    self.notifier
-                        .send_ratio_alert(pair_name, current.ratio, change_pct, &time_window)
-                        .await?;
-
-                    // Save alert to database
-                    if let Err(e) = self
-                        .database
-                        .insert_alert(
-                            pair_name,
-                            current.ratio,
-                            change_pct,
-                            threshold,
-                            current.timestamp,
-                        )
-                        .await
-                    {
-                        log::error!("Failed to save alert to database: {}", e);
-                    }
+    /// Send all queued alerts as one message grouped by pair
+    async fn send_alert_digest(&mut self) -> Result<()> {
+        if self.pending_digest.is_empty() {
+            return Ok(());
+        }
 
-                    self.mark_threshold_triggered(pair_name, threshold);
-                }
+        // Group by pair, keeping the order in which pairs first alerted
+        let mut groups: Vec<(&str, Vec<&PendingAlert>)> = Vec::new();
+        for alert in &self.pending_digest {
+            match groups.iter_mut().find(|(name, _)| *name == alert.pair_name) {
+                Some((_, alerts)) => alerts.push(alert),
+                None => groups.push((&alert.pair_name, vec![alert])),
             }
         }
 
+        let sections: Vec<Vec<Line>> = groups
+            .iter()
+            .map(|(pair_name, alerts)| {
+                let mut lines = vec![Line::new().bold(*pair_name)];
+                lines.extend(alerts.iter().map(|a| {
+                    Line::new()
+                        .text(format!(
+                            "{}{} ",
+                            a.severity.map(|s| s.emoji()).unwrap_or_default(),
+                            if a.change_pct > 0.0 { "📈" } else { "📉" }
+                        ))
+                        .code(format!("{:+.2}%", a.change_pct))
+                        .text(format!(" {} → ", a.period))
+                        .code(format!("{:.8}", a.ratio))
+                }));
+                lines
+            })
+            .collect();
+
+        log::info!(
+            "Sending alert digest with {} alerts for {} pairs",
+            self.pending_digest.len(),
+            sections.len()
+        );
+
+        // Only fully silent when every alert in it is
+        let silent = self
+            .pending_digest
+            .iter()
+            .all(|a| a.severity.is_some_and(|s| self.config.notifications.silent.is_silent(s)));
+
+        // Keep the queue if sending fails so the next cycle retries
+        let digest = messages::alert_digest(self.texts(), self.pending_digest.len(), sections, silent);
+        self.notifier.send_alert(&digest).await?;
+
+        self.pending_digest.clear();
+        self.digest_started = None;
+
         Ok(())
     }
 
+    /// Whether the pair alerted for this threshold and metric within its
+    /// cooldown; `metric` is `None` for daily open thresholds
+    async fn in_cooldown(
+        &self,
+        pair: &RatioPair,
+        threshold: f64,
+        metric: Option<&str>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        let cooldown = self.config.alert_cooldown_secs(pair);
+        if cooldown == 0 {
+            return false;
+        }
+
+        match self
+            .database
+            .get_last_alert_time(&pair.name, threshold, metric)
+            .await
+        {
+            Ok(Some(last)) => {
+                let in_cooldown = now - last < chrono::Duration::seconds(cooldown as i64);
+                if in_cooldown {
+                    log::debug!(
+                        "Skipping {}% alert for {}: in cooldown since {}",
+                        threshold,
+                        pair.name,
+                        last
+                    );
+                }
+                in_cooldown
+            }
+            Ok(None) => false,
+            Err(e) => {
+                log::error!("Failed to check alert cooldown: {}", e);
+                false
+            }
+        }
+    }
+
     /// Check if threshold was recently triggered
-    fn was_threshold_recently_triggered(&self, pair_name: &str, threshold: f64) -> bool {
+    fn was_threshold_recently_triggered(&self, key: &RuleKey, threshold: f64) -> bool {
         self.triggered_thresholds
-            .get(pair_name)
-            .map(|thresholds| thresholds.contains(&threshold))
+            .get(key)
+            .map(|thresholds| thresholds.iter().any(|t| t.threshold == threshold))
             .unwrap_or(false)
     }
 
     /// Mark threshold as triggered
-    fn mark_threshold_triggered(&mut self, pair_name: &str, threshold: f64) {
-        let thresholds = self
-            .triggered_thresholds
-            .entry(pair_name.to_string())
-            .or_insert_with(Vec::new);
+    fn mark_threshold_triggered(&mut self, key: &RuleKey, threshold: f64) {
+        let thresholds = self.triggered_thresholds.entry(key.clone()).or_default();
 
-        if !thresholds.contains(&threshold) {
-            thresholds.push(threshold);
+        if !thresholds.iter().any(|t| t.threshold == threshold) {
+            thresholds.push(TriggeredThreshold {
+                threshold,
+                below_since: None,
+            });
         }
 
-        // Reset triggered thresholds after 2x the change window
-        // This is handled by clearing old history
+        // Triggered thresholds re-arm with hysteresis, see update_rearm_state
     }
 
-    /// Reset triggered thresholds for a pair (called when ratio stabilizes)
-    fn reset_triggered_thresholds(&mut self, pair_name: &str) {
-        self.triggered_thresholds.remove(pair_name);
+    /// Condense snapshots older than `rollup_after_days` and purge data older
+    /// than its retention period on the first cycle and once a day after
+    async fn check_retention(&mut self) -> Result<()> {
+        let rollup_days = self.config.database.rollup_after_days;
+        let retention = self.config.database.retention_periods();
+        if rollup_days.is_none() && retention.is_empty() {
+            return Ok(());
+        }
+
+        if self
+            .last_cleanup
+            .is_some_and(|last| last.elapsed() < CLEANUP_INTERVAL)
+        {
+            return Ok(());
+        }
+
+        // Retry tomorrow rather than every cycle if the cleanup fails
+        self.last_cleanup = Some(Instant::now());
+
+        if let Some(days) = rollup_days {
+            self.database.rollup_snapshots(days).await?;
+        }
+
+        if retention.is_empty() {
+            return Ok(());
+        }
+
+        let summary = self.database.cleanup_old_records(&retention).await?;
+
+        if self.config.database.notify_on_cleanup && summary.total() > 0 {
+            self.notifier
+                .send_alert(&messages::cleanup_summary(self.texts(), &summary))
+                .await?;
+        }
+
+        Ok(())
     }
 
     /// Check if it's time for periodic notification
     async fn check_periodic_notification(&mut self) -> Result<()> {
+        if let Some(schedule) = self.config.periodic_schedule()? {
+            let now = self.local_now();
+            let next = match self.next_scheduled_notification {
+                Some(next) => next,
+                None => schedule.find_next_occurrence(&now, false)?,
+            };
+
+            if now >= next {
+                // Schedule the next run first so a failed send isn't retried every cycle
+                self.next_scheduled_notification = Some(schedule.find_next_occurrence(&now, false)?);
+                self.send_periodic_notification().await?;
+            } else {
+                self.next_scheduled_notification = Some(next);
+            }
+
+            return Ok(());
+        }
+
         let elapsed = self.last_periodic_notification.elapsed();
         let period = Duration::from_secs(self.config.monitoring.periodic_notification_secs);
 
         if elapsed >= period {
             self.send_periodic_notification().await?;
             self.last_periodic_notification = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    /// Send the daily report once its configured local time has passed
+    async fn check_daily_summary(&mut self) -> Result<()> {
+        let Some(time) = self.config.monitoring.daily_summary_time else {
+            return Ok(());
+        };
+
+        let now = self.local_now();
+        let Some(next) = self.next_daily_summary.or_else(|| next_occurrence(&[], time, now)) else {
+            return Ok(());
+        };
+
+        if now < next {
+            self.next_daily_summary = Some(next);
+            return Ok(());
+        }
 
-            // Reset triggered thresholds on periodic notifications
-            let pairs = self.config.ratio_pairs.clone();
-            for pair in &pairs {
-                self.reset_triggered_thresholds(&pair.name);
+        // Schedule the next run first so a failed send isn't retried every cycle
+        self.next_daily_summary = next_occurrence(&[], time, now);
+        self.send_daily_summary().await
+    }
+
+    /// Report each pair's open, close, range, swing and alert count over the last 24 hours
+    async fn send_daily_summary(&self) -> Result<()> {
+        log::info!("Sending daily summary");
+
+        let end = chrono::Utc::now();
+        let start = end - chrono::Duration::hours(24);
+        let mut sections = Vec::new();
+        let mut summaries = Vec::new();
+
+        for pair in &self.config.ratio_pairs {
+            let mut records = self
+                .database
+                .get_ratio_history_range(&pair.name, start, end)
+                .await?;
+            records.reverse();
+            let alerts = self.database.count_alerts(&pair.name, start, end).await?;
+
+            let summary = PairDaySummary::from_snapshots(&pair.name, &records, alerts);
+            sections.push(match &summary {
+                Some(summary) => summary.lines(self.texts()),
+                None => no_data_section(self.texts(), &pair.name),
+            });
+            summaries.push((pair, summary));
+        }
+
+        let vars = DailySummaryVars {
+            pairs: summaries
+                .iter()
+                .map(|(pair, summary)| match summary {
+                    Some(s) => DaySummaryVars {
+                        pair: &pair.name,
+                        open: Some(s.open),
+                        close: Some(s.close),
+                        min: Some(s.min),
+                        max: Some(s.max),
+                        change: Some(s.change_pct()),
+                        swing: Some(s.swing_pct),
+                        alerts: Some(s.alerts),
+                    },
+                    None => DaySummaryVars {
+                        pair: &pair.name,
+                        ..Default::default()
+                    },
+                })
+                .collect(),
+        };
+        let message = self.templates.daily_summary(messages::daily_summary(self.texts(), sections), &vars);
+
+        self.notifier.send_report(&message).await
+    }
+
+    /// Send the weekly report once its configured weekday and local time have passed
+    async fn check_weekly_report(&mut self) -> Result<()> {
+        let Some(schedule) = self.config.monitoring.weekly_report.clone() else {
+            return Ok(());
+        };
+
+        let now = self.local_now();
+        let days = [schedule.day];
+        let Some(next) = self
+            .next_weekly_report
+            .or_else(|| next_occurrence(&days, schedule.time, now))
+        else {
+            return Ok(());
+        };
+
+        if now < next {
+            self.next_weekly_report = Some(next);
+            return Ok(());
+        }
+
+        // Schedule the next run first so a failed send isn't retried every cycle
+        self.next_weekly_report = next_occurrence(&days, schedule.time, now);
+        self.send_weekly_report().await
+    }
+
+    /// Compare each pair's last 7 days with the 7 days before
+    async fn send_weekly_report(&self) -> Result<()> {
+        log::info!("Sending weekly report");
+
+        let end = chrono::Utc::now();
+        let week_start = end - chrono::Duration::days(7);
+        let prior_start = week_start - chrono::Duration::days(7);
+        let mut breakouts = Vec::new();
+        let mut sections = Vec::new();
+
+        for pair in &self.config.ratio_pairs {
+            let current = self
+                .database
+                .get_ratio_history_range(&pair.name, week_start, end)
+                .await?;
+            let previous = self
+                .database
+                .get_ratio_history_range(&pair.name, prior_start, week_start)
+                .await?;
+
+            let Some(current) = WeekStats::from_snapshots(&current) else {
+                sections.push(no_data_section(self.texts(), &pair.name));
+                continue;
+            };
+
+            let comparison = PairWeekComparison {
+                pair_name: pair.name.clone(),
+                current,
+                previous: WeekStats::from_snapshots(&previous),
+            };
+
+            if let Some(direction) = comparison.breakout(self.texts()) {
+                let line = fill(self.texts().pair_broke, &[("pair", &pair.name), ("direction", &direction)]);
+                breakouts.push(Line::new().text(line));
             }
+            sections.push(comparison.lines(self.texts()));
         }
 
-        Ok(())
+        self.notifier
+            .send_report(&messages::weekly_report(self.texts(), breakouts, sections))
+            .await
+    }
+
+    /// Refresh the pinned live status with the latest ratio of every active pair
+    async fn update_live_status(&self) {
+        if !self.config.telegram.live_status {
+            return;
+        }
+
+        let paused = self.paused_pairs().await;
+        let (updates, ratios): (Vec<_>, Vec<_>) = self
+            .config
+            .ratio_pairs
+            .iter()
+            .filter(|pair| !paused.contains(&pair.name))
+            .filter_map(|pair| {
+                let ratio = self.latest_ratios.get(&pair.name)?;
+                Some((messages::ratio_lines(self.texts(), pair, ratio, None), ratio.clone()))
+            })
+            .unzip();
+
+        let status = messages::live_status(self.texts(), updates, ratios);
+        if let Err(e) = self.notifier.send_status(&status).await {
+            log::error!("Error updating live status: {:#}", e);
+        }
     }
 
     /// Send periodic notification with all current ratios
-    async fn send_periodic_notification(&self) -> Result<()> {
+    async fn send_periodic_notification(&mut self) -> Result<()> {
         log::info!("Sending periodic notification");
 
         let mut updates = Vec::new();
+        let mut ratios = Vec::new();
+        let mut sparklines = Vec::new();
+        let mut resolved = HashMap::new();
+        let paused = self.paused_pairs().await;
 
         for pair in &self.config.ratio_pairs {
+            if paused.contains(&pair.name) {
+                continue;
+            }
+
             match self
                 .calculator
-                .calculate_simple_ratio(&pair.name, &pair.symbol_a, &pair.symbol_b)
+                .calculate_pair_ratio(&self.config, pair, &mut resolved)
                 .await
             {
                 Ok(ratio) => {
-                    let update = format!(
-                        "*{}*\n`{:.8}`\n{} `${:.2}` / {} `${:.2}`",
-                        escape_markdown(&pair.name),
-                        ratio.ratio,
-                        escape_markdown(&pair.symbol_a),
-                        ratio.price_a,
-                        escape_markdown(&pair.symbol_b),
-                        ratio.price_b
-                    );
-                    updates.push(update);
+                    let sparkline = self.sparkline(&pair.name, ratio.timestamp).await;
+                    updates.push(messages::ratio_lines(self.texts(), pair, &ratio, sparkline.as_deref()));
+                    ratios.push(ratio);
+                    sparklines.push(sparkline);
                 }
                 Err(e) => {
                     log::error!("Failed to calculate ratio for {}: {}", pair.name, e);
@@ -275,16 +2401,117 @@ impl RatioMonitor {
             }
         }
 
-        if !updates.is_empty() {
-            self.notifier.send_periodic_update(&updates).await?;
+        if !updates.is_empty() || !self.gap_notes.is_empty() {
+            let vars = PeriodicVars {
+                pairs: ratios
+                    .iter()
+                    .zip(&sparklines)
+                    .map(|(ratio, sparkline)| PeriodicPairVars {
+                        pair: &ratio.pair_name,
+                        ratio: ratio.ratio,
+                        symbol_a: &ratio.symbol_a,
+                        symbol_b: &ratio.symbol_b,
+                        price_a: ratio.price_a,
+                        price_b: ratio.price_b,
+                        sparkline: sparkline.as_deref(),
+                    })
+                    .collect(),
+                gaps: &self.gap_notes,
+            };
+            let message = self.templates.periodic(
+                messages::periodic_update(self.texts(), updates, ratios.clone(), &self.gap_notes),
+                &vars,
+            );
+            self.notifier.send_periodic(&message).await?;
+            self.gap_notes.clear();
         }
 
         Ok(())
     }
 }
 
+/// Resolve when the process receives SIGINT or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                log::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => log::info!("Received SIGINT"),
+        _ = terminate => log::info!("Received SIGTERM"),
+    }
+}
+
+/// SIGHUP listener that never fires where the signal is unavailable
+struct ReloadSignal {
+    #[cfg(unix)]
+    hangup: Option<tokio::signal::unix::Signal>,
+}
+
+impl ReloadSignal {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            let hangup =
+                match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                    Ok(signal) => Some(signal),
+                    Err(e) => {
+                        log::error!("Failed to listen for SIGHUP: {}", e);
+                        None
+                    }
+                };
+            Self { hangup }
+        }
+
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(hangup) = &mut self.hangup {
+            hangup.recv().await;
+            return;
+        }
+
+        std::future::pending::<()>().await
+    }
+}
+
+/// Exponential moving average of the snapshots in the window, excluding the
+/// latest one (the ratio being compared). Smoothing follows the window size.
+fn ema_baseline(window: &[RatioRecord]) -> Option<f64> {
+    let (_, previous) = window.split_last()?;
+    let (first, rest) = previous.split_first()?;
+    let alpha = 2.0 / (previous.len() as f64 + 1.0);
+
+    Some(
+        rest.iter()
+            .fold(first.ratio, |ema, r| alpha * r.ratio + (1.0 - alpha) * ema),
+    )
+}
+
 /// Format duration in seconds to human-readable string
-fn format_duration(seconds: u64) -> String {
+pub fn format_duration(seconds: u64) -> String {
     if seconds < 60 {
         format!("{}s", seconds)
     } else if seconds < 3600 {
@@ -294,15 +2521,7 @@ fn format_duration(seconds: u64) -> String {
     }
 }
 
-/// Escape special characters for Telegram MarkdownV2
-fn escape_markdown(text: &str) -> String {
-    text.chars()
-        .map(|c| match c {
-            '_' | '*' | '[' | ']' | '(' | ')' | '~' | '`' | '>' | '#' | '+' | '-' | '=' | '|'
-            | '{' | '}' | '.' | '!' => {
-                format!("\\{}", c)
-            }
-            _ => c.to_string(),
-        })
-        .collect()
+/// Report section for a pair without snapshots in the period
+fn no_data_section(t: &Texts, pair_name: &str) -> Vec<Line> {
+    vec![Line::new().bold(pair_name), Line::new().text(t.no_data_recorded)]
 }
@@ -0,0 +1,238 @@
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Outgoing, Packet, QoS};
+use serde_json::json;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::config::{Config, MqttConfig};
+use crate::monitor::format_duration;
+use crate::ratio::SimpleRatio;
+
+/// Messages that may wait for the broker before new state is dropped
+const QUEUE_CAPACITY: usize = 100;
+
+/// Wait before reconnecting after the broker connection fails
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How long shutdown waits for the "offline" status to go out
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A pair's sensors: its name and the change window its change % is measured over
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairSensor {
+    pub pair_name: String,
+    pub window_secs: u64,
+}
+
+/// Publishes each pair's latest ratio and change to an MQTT broker, announcing
+/// them as Home Assistant sensors through MQTT discovery
+pub struct MqttPublisher {
+    client: AsyncClient,
+    topic_prefix: String,
+    sensors: Vec<PairSensor>,
+    event_loop: JoinHandle<()>,
+}
+
+impl MqttPublisher {
+    pub fn new(mqtt: &MqttConfig, config: &Config) -> Self {
+        let topic_prefix = mqtt.topic_prefix.trim_end_matches('/').to_string();
+        let status_topic = format!("{}/status", topic_prefix);
+
+        let mut options = MqttOptions::new(&mqtt.client_id, &mqtt.host, mqtt.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        options.set_last_will(LastWill::new(
+            &status_topic,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let (Some(username), Some(password)) = (&mqtt.username, &mqtt.password) {
+            options.set_credentials(username, password);
+        }
+
+        let sensors = pair_sensors(config);
+
+        // Announced again on every (re)connect, since the broker may have lost them
+        let mut announcements = vec![(status_topic, "online".to_string())];
+        if mqtt.discovery {
+            for sensor in &sensors {
+                announcements.extend(discovery_messages(mqtt, &topic_prefix, sensor));
+            }
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, QUEUE_CAPACITY);
+        let announcer = client.clone();
+        let event_loop = tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        log::info!("Connected to MQTT broker");
+                        for (topic, payload) in &announcements {
+                            if let Err(e) = announcer.try_publish(
+                                topic,
+                                QoS::AtLeastOnce,
+                                true,
+                                payload.clone(),
+                            ) {
+                                log::warn!("Failed to queue MQTT message for {}: {}", topic, e);
+                            }
+                        }
+                    }
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    Ok(_) => {}
+                    Err(e) => {
+                        log::warn!(
+                            "MQTT connection error, retrying in {:?}: {}",
+                            RECONNECT_DELAY,
+                            e
+                        );
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        });
+
+        Self {
+            client,
+            topic_prefix,
+            sensors,
+            event_loop,
+        }
+    }
+
+    /// Whether `config` announces the same sensors, so the publisher can be kept on reload
+    pub fn matches(&self, config: &Config) -> bool {
+        self.sensors == pair_sensors(config)
+    }
+
+    /// Publish a pair's latest ratio and its change over the first change window.
+    /// State is retained so Home Assistant shows it immediately after a restart.
+    pub fn publish_state(&self, ratio: &SimpleRatio, change_pct: Option<f64>) {
+        let topic = state_topic(&self.topic_prefix, &ratio.pair_name);
+        let payload = json!({
+            "ratio": ratio.ratio,
+            "change_pct": change_pct,
+            "price_a": ratio.price_a,
+            "price_b": ratio.price_b,
+            "timestamp": ratio.timestamp,
+        });
+
+        // Never block a check on the broker; drop the update if the queue is full
+        if let Err(e) = self
+            .client
+            .try_publish(&topic, QoS::AtMostOnce, true, payload.to_string())
+        {
+            log::warn!("Failed to queue MQTT state for {}: {}", ratio.pair_name, e);
+        }
+    }
+
+    /// Mark the sensors unavailable and disconnect
+    pub async fn shutdown(&mut self) {
+        let status_topic = format!("{}/status", self.topic_prefix);
+        let _ = self
+            .client
+            .try_publish(status_topic, QoS::AtLeastOnce, true, "offline");
+        let _ = self.client.try_disconnect();
+
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut self.event_loop)
+            .await
+            .is_err()
+        {
+            self.event_loop.abort();
+        }
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        self.event_loop.abort();
+    }
+}
+
+/// Each configured pair with the window of its first change rule
+fn pair_sensors(config: &Config) -> Vec<PairSensor> {
+    config
+        .ratio_pairs
+        .iter()
+        .map(|pair| PairSensor {
+            pair_name: pair.name.clone(),
+            window_secs: config.primary_window_secs(pair),
+        })
+        .collect()
+}
+
+fn state_topic(prefix: &str, pair_name: &str) -> String {
+    format!("{}/{}/state", prefix, slug(pair_name))
+}
+
+/// Retained Home Assistant discovery configs for a pair's ratio and change sensors,
+/// grouped under one device per pair
+fn discovery_messages(
+    mqtt: &MqttConfig,
+    prefix: &str,
+    sensor: &PairSensor,
+) -> Vec<(String, String)> {
+    let node = slug(&mqtt.client_id);
+    let pair = slug(&sensor.pair_name);
+    let device = json!({
+        "identifiers": [format!("{}_{}", node, pair)],
+        "name": sensor.pair_name,
+        "manufacturer": "ratio-noti",
+    });
+
+    let sensors = [
+        (
+            "ratio",
+            json!({
+                "name": "Ratio",
+                "value_template": "{{ value_json.ratio }}",
+                "suggested_display_precision": 8,
+                "icon": "mdi:scale-balance",
+            }),
+        ),
+        (
+            "change",
+            json!({
+                "name": format!("Change {}", format_duration(sensor.window_secs)),
+                "value_template": "{{ value_json.change_pct }}",
+                "unit_of_measurement": "%",
+                "suggested_display_precision": 2,
+                "icon": "mdi:percent",
+            }),
+        ),
+    ];
+
+    sensors
+        .into_iter()
+        .map(|(kind, mut config)| {
+            let unique_id = format!("{}_{}_{}", node, pair, kind);
+            config["unique_id"] = json!(unique_id);
+            config["object_id"] = json!(format!("{}_{}", pair, kind));
+            config["state_topic"] = json!(state_topic(prefix, &sensor.pair_name));
+            config["state_class"] = json!("measurement");
+            config["availability_topic"] = json!(format!("{}/status", prefix));
+            config["device"] = device.clone();
+
+            let topic = format!(
+                "{}/sensor/{}/{}_{}/config",
+                mqtt.discovery_prefix.trim_end_matches('/'),
+                node,
+                pair,
+                kind
+            );
+            (topic, config.to_string())
+        })
+        .collect()
+}
+
+/// Topic- and id-safe form of a name: "BTC/ETH" -> "btc_eth"
+fn slug(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,669 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use futures::future::{BoxFuture, join_all};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::{Channel, Config, SeverityTier, SilentConfig};
+use crate::database::Database;
+use crate::desktop::DesktopNotifier;
+use crate::discord::DiscordNotifier;
+use crate::gotify::GotifyNotifier;
+use crate::i18n::Texts;
+use crate::matrix::MatrixNotifier;
+use crate::ntfy::NtfyNotifier;
+use crate::ratio::SimpleRatio;
+use crate::telegram::TelegramNotifier;
+use crate::webhook::WebhookNotifier;
+
+/// Queued notifications older than this are dropped instead of delivered late
+const QUEUE_MAX_AGE_HOURS: i64 = 24;
+
+/// A run of text with the emphasis a channel may render or drop
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Span {
+    Text(String),
+    Bold(String),
+    /// Numbers and identifiers shown verbatim, e.g. a ratio
+    Code(String),
+}
+
+/// One line of a message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Line(pub Vec<Span>);
+
+impl Line {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.0.push(Span::Text(text.into()));
+        self
+    }
+
+    pub fn bold(mut self, text: impl Into<String>) -> Self {
+        self.0.push(Span::Bold(text.into()));
+        self
+    }
+
+    pub fn code(mut self, text: impl Into<String>) -> Self {
+        self.0.push(Span::Code(text.into()));
+        self
+    }
+
+    /// The line without any emphasis
+    pub fn plain_text(&self) -> String {
+        self.0
+            .iter()
+            .map(|span| match span {
+                Span::Text(text) | Span::Bold(text) | Span::Code(text) => text.as_str(),
+            })
+            .collect()
+    }
+}
+
+impl From<&str> for Line {
+    fn from(text: &str) -> Self {
+        Line::new().text(text)
+    }
+}
+
+/// A notification in channel-neutral form; each channel renders it its own way
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Message {
+    pub title: Line,
+    /// Groups of lines, shown separated by a blank line in text channels
+    pub sections: Vec<Vec<Line>>,
+    /// When the event happened; channels show it in their own format
+    pub time: Option<DateTime<Utc>>,
+    /// Ratios the message reports, for channels that deliver structured data
+    pub ratios: Vec<SimpleRatio>,
+}
+
+impl Message {
+    pub fn new(title: Line) -> Self {
+        Self {
+            title,
+            ..Self::default()
+        }
+    }
+
+    /// Append a section; empty sections are skipped
+    pub fn section(mut self, lines: Vec<Line>) -> Self {
+        if !lines.is_empty() {
+            self.sections.push(lines);
+        }
+        self
+    }
+
+    pub fn with_ratios(mut self, ratios: Vec<SimpleRatio>) -> Self {
+        self.ratios = ratios;
+        self
+    }
+
+    /// The sections without emphasis, separated by blank lines
+    pub fn body_text(&self) -> String {
+        self.sections
+            .iter()
+            .map(|section| {
+                section
+                    .iter()
+                    .map(Line::plain_text)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Stamp the message with the current time
+    pub fn now(mut self) -> Self {
+        self.time = Some(Utc::now());
+        self
+    }
+
+    /// Mark a message delivered late because its channel was unreachable
+    fn delayed(mut self, texts: &Texts) -> Self {
+        self.title
+            .0
+            .insert(0, Span::Text(texts.delayed.to_string()));
+        self
+    }
+}
+
+/// What an alert is, for channels that route or prioritize by it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlertKind {
+    /// A threshold breach, recovery or digest of those
+    Alert,
+    /// A louder reminder for a breach that persists
+    Escalation,
+    /// Monitor status: startup, shutdown, data outages, cleanup
+    Notice,
+}
+
+/// An event about the monitored pairs or the monitor itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub message: Message,
+    /// Severity tier of the breached threshold, if tiers are configured
+    pub tier: Option<SeverityTier>,
+    /// Deliver without a notification sound where the channel supports it.
+    /// Set from `notifications.silent` when the alert is sent.
+    pub silent: bool,
+    /// What the alert is about, for channels that deliver structured data
+    pub details: Option<AlertDetails>,
+    /// Continues an earlier alert about the same pair (escalation, recovery),
+    /// threaded under it where the channel supports it
+    #[serde(default)]
+    pub follow_up: bool,
+    /// PNG chart of the pair's recent ratio, for channels that show images.
+    /// Not kept when the alert is queued for later delivery.
+    #[serde(skip)]
+    pub chart: Option<Vec<u8>>,
+}
+
+/// The pair and move behind an alert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertDetails {
+    pub pair: String,
+    pub ratio: f64,
+    pub change_pct: f64,
+    /// What the change is measured over, e.g. "in 5m" or "on the day"
+    pub period: Option<String>,
+    /// Thresholds crossed by the move, lowest first
+    pub thresholds: Vec<f64>,
+}
+
+impl Alert {
+    pub fn new(kind: AlertKind, message: Message) -> Self {
+        Self {
+            kind,
+            message,
+            tier: None,
+            silent: false,
+            details: None,
+            follow_up: false,
+            chart: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: AlertDetails) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn with_chart(mut self, chart: Option<Vec<u8>>) -> Self {
+        self.chart = chart;
+        self
+    }
+
+    pub fn with_tier(mut self, tier: Option<&SeverityTier>) -> Self {
+        self.tier = tier.cloned();
+        self
+    }
+}
+
+/// A destination for monitor notifications
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Channel name for logs
+    fn name(&self) -> &'static str;
+
+    /// Alerts, escalations and status notices
+    async fn send_alert(&self, alert: &Alert) -> Result<()>;
+
+    /// Periodic ratio updates
+    async fn send_periodic(&self, message: &Message) -> Result<()>;
+
+    /// Daily and weekly reports
+    async fn send_report(&self, message: &Message) -> Result<()>;
+
+    /// The latest ratios, refreshed every cycle, for channels that keep a
+    /// live status message; others ignore it
+    async fn send_status(&self, _message: &Message) -> Result<()> {
+        Ok(())
+    }
+
+    /// Retry an alert on one destination named by a `PartialFailure`.
+    /// Channels with a single destination never report one.
+    async fn resend_alert(&self, alert: &Alert, _destination: &str) -> Result<()> {
+        self.send_alert(alert).await
+    }
+
+    /// Retry a report on one destination named by a `PartialFailure`
+    async fn resend_report(&self, message: &Message, _destination: &str) -> Result<()> {
+        self.send_report(message).await
+    }
+}
+
+/// A channel with several destinations reached some but not all of them.
+/// Only the failed ones are queued, so the others don't get the message twice.
+#[derive(Debug)]
+pub struct PartialFailure {
+    /// Keys of the failed destinations, as the channel's `resend_*` methods take them
+    pub failed: Vec<String>,
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for PartialFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+impl std::error::Error for PartialFailure {}
+
+/// A notification as stored while its channel is unreachable. Periodic
+/// updates aren't queued, since the next one supersedes them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Queued {
+    Alert(Alert),
+    Report(Message),
+}
+
+impl Queued {
+    fn delayed(self, texts: &Texts) -> Self {
+        match self {
+            Queued::Alert(mut alert) => {
+                alert.message = alert.message.delayed(texts);
+                Queued::Alert(alert)
+            }
+            Queued::Report(message) => Queued::Report(message.delayed(texts)),
+        }
+    }
+
+    async fn send(&self, channel: &dyn Notifier, destination: Option<&str>) -> Result<()> {
+        match (self, destination) {
+            (Queued::Alert(alert), None) => channel.send_alert(alert).await,
+            (Queued::Alert(alert), Some(destination)) => {
+                channel.resend_alert(alert, destination).await
+            }
+            (Queued::Report(message), None) => channel.send_report(message).await,
+            (Queued::Report(message), Some(destination)) => {
+                channel.resend_report(message, destination).await
+            }
+        }
+    }
+}
+
+/// Queue key of a channel, or of one of its destinations
+fn queue_key(channel: &dyn Notifier, destination: Option<&str>) -> String {
+    match destination {
+        Some(destination) => format!("{}:{}", channel.name(), destination),
+        None => channel.name().to_string(),
+    }
+}
+
+/// What became of a message on one channel
+enum Delivery {
+    Sent,
+    /// Stored to be retried once the channel is reachable again
+    Queued,
+}
+
+/// The configured notification channels; every message goes to all of them
+pub struct Notifiers {
+    channels: Vec<Box<dyn Notifier>>,
+    /// Where messages a channel couldn't deliver wait to be retried
+    queue: Option<Database>,
+    /// Channels of pairs whose alerts only go to some of them
+    pair_channels: HashMap<String, Vec<Channel>>,
+    silent: SilentConfig,
+    /// Language of what the notifiers add themselves, like the delayed mark
+    texts: &'static Texts,
+}
+
+impl Notifiers {
+    /// Build the channels listed in `notifications.channels`; each listed
+    /// channel's settings are checked by `Config::validate`
+    pub fn from_config(config: &Config, database: &Database) -> Self {
+        let notifications = &config.notifications;
+        let mut channels: Vec<Box<dyn Notifier>> = Vec::new();
+
+        for channel in &notifications.channels {
+            match channel {
+                Channel::Telegram => channels.push(Box::new(
+                    TelegramNotifier::new(&config.telegram.token, config.telegram.user_id)
+                        .with_recipients(&config.telegram.recipients)
+                        .with_escalation_chat(
+                            config.telegram.escalation_chat_id,
+                            config.telegram.escalation_thread_id,
+                        )
+                        .with_pair_routes(&config.ratio_pairs)
+                        .with_timezone(config.timezone)
+                        .with_language(config.language)
+                        .with_live_status(config.telegram.live_status)
+                        .with_plain_text(config.telegram.plain_text)
+                        .with_subscriptions(config.telegram.subscriptions.then(|| database.clone()))
+                        .with_silent(&notifications.silent),
+                )),
+                Channel::Discord => {
+                    if let Some(discord) = &notifications.discord {
+                        channels.push(Box::new(DiscordNotifier::new(&discord.webhook_url)));
+                    }
+                }
+                Channel::Webhook => {
+                    if let Some(webhook) = &notifications.webhook {
+                        channels.push(Box::new(WebhookNotifier::new(
+                            &webhook.url,
+                            webhook.secret.as_deref(),
+                            webhook.max_attempts,
+                        )));
+                    }
+                }
+                Channel::Ntfy => {
+                    if let Some(ntfy) = &notifications.ntfy {
+                        channels.push(Box::new(
+                            NtfyNotifier::new(&ntfy.server, &ntfy.topic)
+                                .with_token(ntfy.token.as_deref())
+                                .with_login(ntfy.username.as_deref(), ntfy.password.as_deref()),
+                        ));
+                    }
+                }
+                Channel::Gotify => {
+                    if let Some(gotify) = &notifications.gotify {
+                        channels.push(Box::new(GotifyNotifier::new(
+                            &gotify.url,
+                            &gotify.app_token,
+                        )));
+                    }
+                }
+                Channel::Matrix => {
+                    if let Some(matrix) = &notifications.matrix {
+                        channels.push(Box::new(MatrixNotifier::new(
+                            &matrix.homeserver,
+                            &matrix.access_token,
+                            &matrix.room_id,
+                        )));
+                    }
+                }
+                Channel::Desktop => channels.push(Box::new(DesktopNotifier::new())),
+            }
+        }
+
+        let pair_channels = config
+            .ratio_pairs
+            .iter()
+            .filter_map(|pair| {
+                let route = pair.route.as_ref().filter(|r| !r.channels.is_empty())?;
+                Some((pair.name.clone(), route.channels.clone()))
+            })
+            .collect();
+
+        Self {
+            channels,
+            queue: None,
+            pair_channels,
+            silent: notifications.silent.clone(),
+            texts: config.language.texts(),
+        }
+    }
+
+    /// Keep alerts and reports a channel fails to deliver in the database,
+    /// and retry them in order once it is reachable again
+    pub fn with_queue(mut self, database: Database) -> Self {
+        self.queue = Some(database);
+        self
+    }
+
+    /// Send an alert through its pair's channels, or every channel when the
+    /// pair has no route or the alert isn't about a single pair
+    pub async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let routed = alert
+            .details
+            .as_ref()
+            .and_then(|details| self.pair_channels.get(&details.pair));
+        let channels = self
+            .all_channels()
+            .filter(|channel| {
+                routed.is_none_or(|routed| routed.iter().any(|c| c.name() == channel.name()))
+            })
+            .collect();
+
+        let mut alert = alert.clone();
+        alert.silent = match (alert.kind, &alert.tier) {
+            (AlertKind::Notice, _) => self.silent.notices,
+            (_, Some(tier)) => self.silent.is_silent(tier.severity),
+            // Digests arrive already decided, the rest are loud
+            (_, None) => alert.silent,
+        };
+
+        let queued = Queued::Alert(alert.clone());
+        let alert = &alert;
+        self.fan_out("alert", channels, Some(&queued), |channel| {
+            channel.send_alert(alert)
+        })
+        .await
+    }
+
+    pub async fn send_periodic(&self, message: &Message) -> Result<()> {
+        self.fan_out(
+            "periodic update",
+            self.all_channels().collect(),
+            None,
+            |channel| channel.send_periodic(message),
+        )
+        .await
+    }
+
+    pub async fn send_report(&self, message: &Message) -> Result<()> {
+        let queued = Queued::Report(message.clone());
+        self.fan_out(
+            "report",
+            self.all_channels().collect(),
+            Some(&queued),
+            |channel| channel.send_report(message),
+        )
+        .await
+    }
+
+    pub async fn send_status(&self, message: &Message) -> Result<()> {
+        self.fan_out(
+            "live status",
+            self.all_channels().collect(),
+            None,
+            |channel| channel.send_status(message),
+        )
+        .await
+    }
+
+    fn all_channels(&self) -> impl Iterator<Item = &dyn Notifier> {
+        self.channels.iter().map(|channel| channel.as_ref())
+    }
+
+    /// Retry queued messages on every channel, dropping those that are too old
+    pub async fn flush_queue(&self) {
+        let Some(database) = &self.queue else {
+            return;
+        };
+
+        let cutoff = Utc::now() - Duration::hours(QUEUE_MAX_AGE_HOURS);
+        match database.expire_queued_notifications(cutoff).await {
+            Ok(0) => {}
+            Ok(count) => log::warn!(
+                "Dropped {} queued notifications older than {}h",
+                count,
+                QUEUE_MAX_AGE_HOURS
+            ),
+            Err(e) => log::error!("{:#}", e),
+        }
+
+        join_all(self.channels.iter().map(|channel| async move {
+            if self.flush_channel(database, channel.as_ref(), None).await {
+                self.flush_destinations(database, channel.as_ref()).await;
+            }
+        }))
+        .await;
+    }
+
+    /// Send a channel's queued messages oldest first, stopping at the first
+    /// failure; with `destination`, those queued for just that destination.
+    /// Returns whether the queue is now empty.
+    async fn flush_channel(
+        &self,
+        database: &Database,
+        channel: &dyn Notifier,
+        destination: Option<&str>,
+    ) -> bool {
+        let key = queue_key(channel, destination);
+        let records = match database.get_queued_notifications(&key).await {
+            Ok(records) => records,
+            Err(e) => {
+                log::error!("{:#}", e);
+                return false;
+            }
+        };
+
+        for record in records {
+            match serde_json::from_str::<Queued>(&record.payload) {
+                Ok(queued) => {
+                    if let Err(e) = queued.delayed(self.texts).send(channel, destination).await {
+                        log::warn!("{} is still unreachable: {:#}", key, e);
+                        return false;
+                    }
+                    log::info!(
+                        "Delivered notification queued at {} via {}",
+                        record.queued_at,
+                        key
+                    );
+                }
+                Err(e) => log::error!(
+                    "Dropping unreadable queued notification {}: {}",
+                    record.id,
+                    e
+                ),
+            }
+
+            if let Err(e) = database.delete_queued_notification(record.id).await {
+                log::error!("{:#}", e);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Flush the queues of a channel's destinations that failed on their own.
+    /// Each waits only for itself, so one unreachable chat doesn't hold up the rest.
+    async fn flush_destinations(&self, database: &Database, channel: &dyn Notifier) {
+        let destinations = match database.get_queued_destinations(channel.name()).await {
+            Ok(destinations) => destinations,
+            Err(e) => {
+                log::error!("{:#}", e);
+                return;
+            }
+        };
+
+        join_all(
+            destinations
+                .iter()
+                .map(|destination| self.flush_channel(database, channel, Some(destination))),
+        )
+        .await;
+    }
+
+    /// Deliver through one channel, or queue the message if the channel fails
+    /// or still has older messages waiting, so they arrive in order. When only
+    /// some of the channel's destinations fail, it's queued for just those.
+    async fn deliver<'a>(
+        &'a self,
+        channel: &'a dyn Notifier,
+        queued: Option<&Queued>,
+        send: BoxFuture<'a, Result<()>>,
+    ) -> Result<Delivery> {
+        let (Some(database), Some(queued)) = (&self.queue, queued) else {
+            return send.await.map(|()| Delivery::Sent);
+        };
+
+        let error = if self.flush_channel(database, channel, None).await {
+            self.flush_destinations(database, channel).await;
+            match send.await {
+                Ok(()) => return Ok(Delivery::Sent),
+                Err(e) => Some(e),
+            }
+        } else {
+            None
+        };
+
+        let failed = match error
+            .as_ref()
+            .and_then(|e| e.downcast_ref::<PartialFailure>())
+        {
+            Some(partial) => partial.failed.iter().map(|d| Some(d.as_str())).collect(),
+            None => vec![None],
+        };
+        let payload = serde_json::to_string(queued)?;
+        let mut result = Ok(());
+        for destination in failed {
+            let key = queue_key(channel, destination);
+            if let Err(e) = database
+                .queue_notification(&key, &payload, Utc::now())
+                .await
+            {
+                result = Err(e);
+            }
+        }
+        if let Some(e) = error {
+            log::warn!("{} failed: {:#}", channel.name(), e);
+        }
+        result?;
+
+        Ok(Delivery::Queued)
+    }
+
+    /// Send through several channels at once. A channel failing doesn't stop
+    /// the others; it's only an error when no channel delivered or queued the message.
+    async fn fan_out<'a, F>(
+        &'a self,
+        what: &str,
+        channels: Vec<&'a dyn Notifier>,
+        queued: Option<&Queued>,
+        send: F,
+    ) -> Result<()>
+    where
+        F: Fn(&'a dyn Notifier) -> BoxFuture<'a, Result<()>>,
+    {
+        let results = join_all(
+            channels
+                .iter()
+                .map(|&channel| self.deliver(channel, queued, send(channel))),
+        )
+        .await;
+
+        let mut delivered = false;
+        let mut last_error = None;
+        for (channel, result) in channels.iter().zip(results) {
+            match result {
+                Ok(Delivery::Sent) => delivered = true,
+                Ok(Delivery::Queued) => {
+                    log::warn!(
+                        "{} via {} queued until the channel is reachable",
+                        what,
+                        channel.name()
+                    );
+                    delivered = true;
+                }
+                Err(e) => {
+                    let e = e.context(format!("{} via {} failed", what, channel.name()));
+                    if let Some(previous) = last_error.replace(e) {
+                        log::error!("{:#}", previous);
+                    }
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !delivered => Err(e),
+            Some(e) => {
+                log::error!("{:#}", e);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::config::Severity;
+use crate::notifier::{Alert, AlertKind, Message, Notifier};
+
+/// How long one request may take, so an unresponsive server can't hold up the monitor
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ntfy priorities, 1 (min) to 5 (max)
+const PRIORITY_LOW: u8 = 2;
+const PRIORITY_DEFAULT: u8 = 3;
+const PRIORITY_HIGH: u8 = 4;
+const PRIORITY_URGENT: u8 = 5;
+
+/// How a topic on a protected ntfy server is accessed
+enum Auth {
+    None,
+    Token(String),
+    Basic { username: String, password: String },
+}
+
+/// Publishes notifications to an ntfy topic
+pub struct NtfyNotifier {
+    client: Client,
+    server: String,
+    topic: String,
+    auth: Auth,
+}
+
+impl NtfyNotifier {
+    pub fn new(server: &str, topic: &str) -> Self {
+        Self {
+            client: Client::new(),
+            server: server.trim_end_matches('/').to_string(),
+            topic: topic.to_string(),
+            auth: Auth::None,
+        }
+    }
+
+    /// Authenticate with an access token
+    pub fn with_token(mut self, token: Option<&str>) -> Self {
+        if let Some(token) = token {
+            self.auth = Auth::Token(token.to_string());
+        }
+        self
+    }
+
+    /// Authenticate with a username and password
+    pub fn with_login(mut self, username: Option<&str>, password: Option<&str>) -> Self {
+        if let (Some(username), Some(password)) = (username, password) {
+            self.auth = Auth::Basic {
+                username: username.to_string(),
+                password: password.to_string(),
+            };
+        }
+        self
+    }
+
+    /// Publish as JSON, which unlike the header API allows emoji in the title
+    async fn publish(&self, message: &Message, priority: u8) -> Result<()> {
+        let mut request = self
+            .client
+            .post(&self.server)
+            .timeout(REQUEST_TIMEOUT)
+            .json(&json!({
+                "topic": self.topic,
+                "title": message.title.plain_text(),
+                "message": message.body_text(),
+                "priority": priority,
+            }));
+
+        request = match &self.auth {
+            Auth::None => request,
+            Auth::Token(token) => request.bearer_auth(token),
+            Auth::Basic { username, password } => request.basic_auth(username, Some(password)),
+        };
+
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let priority = match (&alert.tier, alert.kind) {
+            (Some(tier), _) => match tier.severity {
+                Severity::Info => PRIORITY_LOW,
+                Severity::Warning => PRIORITY_HIGH,
+                Severity::Critical => PRIORITY_URGENT,
+            },
+            (None, AlertKind::Escalation) => PRIORITY_URGENT,
+            (None, _) if alert.silent => PRIORITY_LOW,
+            (None, AlertKind::Alert) => PRIORITY_HIGH,
+            (None, AlertKind::Notice) => PRIORITY_DEFAULT,
+        };
+
+        self.publish(&alert.message, priority)
+            .await
+            .context("Failed to send ntfy alert")
+    }
+
+    async fn send_periodic(&self, message: &Message) -> Result<()> {
+        self.publish(message, PRIORITY_LOW)
+            .await
+            .context("Failed to send ntfy periodic update")
+    }
+
+    async fn send_report(&self, message: &Message) -> Result<()> {
+        self.publish(message, PRIORITY_LOW)
+            .await
+            .context("Failed to send ntfy report")
+    }
+}
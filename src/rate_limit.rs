@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::TelegramConfig;
+
+/// Token bucket per Telegram user for bot actions that fetch order books.
+/// Each user may use `burst` of them at once, after which one becomes
+/// available again every `refill`. Clones share the buckets.
+#[derive(Clone)]
+pub struct RateLimiter {
+    burst: f64,
+    /// `None` when the limit is disabled
+    refill: Option<Duration>,
+    buckets: Arc<Mutex<HashMap<u64, Bucket>>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: &TelegramConfig) -> Self {
+        Self {
+            burst: f64::from(config.rate_limit_burst),
+            refill: (config.rate_limit_refill_secs > 0)
+                .then(|| Duration::from_secs(config.rate_limit_refill_secs)),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Take a token for a user, or get how long until one is available
+    pub fn try_acquire(&self, user_id: u64) -> Result<(), Duration> {
+        let Some(refill) = self.refill else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(user_id).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed / refill.as_secs_f64()).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(refill.mul_f64(1.0 - bucket.tokens))
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use anyhow::Result;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use crate::binance::{BinanceClient, OrderBookInfo};
+use crate::config::{Config, DatabaseConfig, Leg, RatioPair};
+use crate::database::Database;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimpleRatio {
     pub pair_name: String,
     pub symbol_a: String,
@@ -39,19 +42,96 @@ pub struct SlippageAnalysis {
     pub total_cost: f64,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossRatio {
+    pub pair_name: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub ratio: f64,
+    pub route: CrossRoute,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// How a cross ratio was obtained
+#[derive(Debug, Clone, Serialize)]
+pub enum CrossRoute {
+    /// Binance lists the pair directly
+    Direct { symbol: String, price: f64 },
+    /// Both legs routed through a common quote asset
+    Synthetic {
+        via: String,
+        symbol_a: String,
+        price_a: f64,
+        symbol_b: String,
+        price_b: f64,
+    },
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
+#[derive(Clone)]
 pub struct RatioCalculator {
     client: BinanceClient,
+    /// Where fetched order books are recorded, when `order_book_levels` is set
+    order_book_store: Option<OrderBookStore>,
+}
+
+/// Records the top levels of order books fetched for analysis
+#[derive(Clone)]
+struct OrderBookStore {
+    database: Database,
+    levels: usize,
+    compress: bool,
 }
 
 impl RatioCalculator {
     pub fn new(client: BinanceClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            order_book_store: None,
+        }
+    }
+
+    /// Record order books fetched for volume and slippage analysis in the
+    /// database, if `order_book_levels` is set
+    pub fn with_order_book_store(mut self, config: &DatabaseConfig, database: &Database) -> Self {
+        self.order_book_store = config.order_book_levels.map(|levels| OrderBookStore {
+            database: database.clone(),
+            levels,
+            compress: config.compress_order_books,
+        });
+        self
+    }
+
+    /// Fetch an order book for analysis, recording it when configured. A failed
+    /// write is logged rather than failing the analysis
+    async fn get_order_book(&self, symbol: &str) -> Result<OrderBookInfo> {
+        let order_book = self.client.get_order_book(symbol, 100).await?;
+
+        if let Some(store) = &self.order_book_store
+            && let Err(e) = store
+                .database
+                .insert_order_book(&order_book, store.levels, store.compress, chrono::Utc::now())
+                .await
+        {
+            log::warn!("Failed to record order book of {}: {}", symbol, e);
+        }
+
+        Ok(order_book)
+    }
+
+    /// Whether a symbol can be monitored, i.e. Binance lists it and it is trading
+    pub async fn is_trading(&self, symbol: &str) -> Result<bool> {
+        self.client.is_trading(symbol).await
+    }
+
+    /// Current price of a symbol
+    pub async fn price(&self, symbol: &str) -> Result<f64> {
+        Ok(self.client.get_price(symbol).await?.price)
     }
 
     /// Calculate simple ratio using current market prices
@@ -77,6 +157,155 @@ impl RatioCalculator {
         })
     }
 
+    /// Calculate the ratio for a configured pair, resolving composite legs first.
+    ///
+    /// `resolved` caches ratios already computed this cycle so shared legs are
+    /// only fetched once.
+    pub async fn calculate_pair_ratio(
+        &self,
+        config: &Config,
+        pair: &RatioPair,
+        resolved: &mut HashMap<String, SimpleRatio>,
+    ) -> Result<SimpleRatio> {
+        for dep in config.dependency_order(&pair.name)? {
+            if resolved.contains_key(&dep.name) {
+                continue;
+            }
+
+            let price_a = self.leg_value(dep.leg_a(), resolved).await?;
+            let price_b = self.leg_value(dep.leg_b(), resolved).await?;
+
+            resolved.insert(
+                dep.name.clone(),
+                SimpleRatio {
+                    pair_name: dep.name.clone(),
+                    symbol_a: dep.leg_a().label().to_string(),
+                    symbol_b: dep.leg_b().label().to_string(),
+                    price_a,
+                    price_b,
+                    ratio: price_a / price_b,
+                    timestamp: chrono::Utc::now(),
+                },
+            );
+        }
+
+        Ok(resolved[&pair.name].clone())
+    }
+
+    /// Current value of a leg: market price for symbols, ratio for referenced pairs
+    async fn leg_value(&self, leg: Leg<'_>, resolved: &HashMap<String, SimpleRatio>) -> Result<f64> {
+        match leg {
+            Leg::Symbol(symbol) => Ok(self.client.get_price(symbol).await?.price),
+            Leg::Pair(name) => resolved
+                .get(name)
+                .map(|r| r.ratio)
+                .ok_or_else(|| anyhow::anyhow!("Leg pair {} was not resolved", name)),
+        }
+    }
+
+    /// Ratios from the two symbols' `interval` kline closes over `start..end`,
+    /// one per candle both symbols have, oldest first
+    pub async fn calculate_historical_ratios(
+        &self,
+        pair_name: &str,
+        symbol_a: &str,
+        symbol_b: &str,
+        interval: &str,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<SimpleRatio>> {
+        let closes_a = self.client.get_closes(symbol_a, interval, start, end).await?;
+        let closes_b: HashMap<i64, f64> = self
+            .client
+            .get_closes(symbol_b, interval, start, end)
+            .await?
+            .into_iter()
+            .map(|k| (k.open_time, k.close))
+            .collect();
+
+        Ok(closes_a
+            .into_iter()
+            .filter_map(|a| {
+                let price_b = *closes_b.get(&a.open_time)?;
+                Some(SimpleRatio {
+                    pair_name: pair_name.to_string(),
+                    symbol_a: symbol_a.to_string(),
+                    symbol_b: symbol_b.to_string(),
+                    price_a: a.close,
+                    price_b,
+                    ratio: a.close / price_b,
+                    timestamp: a.close_time,
+                })
+            })
+            .collect())
+    }
+
+    /// Ratio of the two symbols' UTC daily open prices
+    pub async fn calculate_daily_open_ratio(&self, symbol_a: &str, symbol_b: &str) -> Result<f64> {
+        let open_a = self.client.get_daily_open(symbol_a).await?;
+        let open_b = self.client.get_daily_open(symbol_b).await?;
+
+        Ok(open_a / open_b)
+    }
+
+    /// Calculate the cross ratio between two assets, using the direct market when
+    /// Binance lists one and otherwise routing both legs through `via` (e.g. USDT)
+    pub async fn calculate_cross_ratio(
+        &self,
+        base_asset: &str,
+        quote_asset: &str,
+        via: &str,
+    ) -> Result<CrossRatio> {
+        let base_asset = base_asset.to_uppercase();
+        let quote_asset = quote_asset.to_uppercase();
+        let via = via.to_uppercase();
+        let pair_name = format!("{}/{}", base_asset, quote_asset);
+
+        let direct_symbol = format!("{}{}", base_asset, quote_asset);
+        if let Some(direct) = self.client.try_get_price(&direct_symbol).await? {
+            return Ok(CrossRatio {
+                pair_name,
+                base_asset,
+                quote_asset,
+                ratio: direct.price,
+                route: CrossRoute::Direct {
+                    symbol: direct.symbol,
+                    price: direct.price,
+                },
+                timestamp: chrono::Utc::now(),
+            });
+        }
+
+        log::debug!(
+            "No direct market for {}, routing through {}",
+            direct_symbol,
+            via
+        );
+
+        let symbol_a = format!("{}{}", base_asset, via);
+        let symbol_b = format!("{}{}", quote_asset, via);
+        let prices = self
+            .client
+            .get_prices(&[symbol_a.clone(), symbol_b.clone()])
+            .await?;
+        let (price_a, price_b) = (prices[0].price, prices[1].price);
+
+        Ok(CrossRatio {
+            pair_name,
+            base_asset,
+            quote_asset,
+            ratio: price_a / price_b,
+            route: CrossRoute::Synthetic {
+                via,
+                symbol_a,
+                price_a,
+                symbol_b,
+                price_b,
+            },
+            timestamp: chrono::Utc::now(),
+        })
+    }
+
     /// Calculate volume-based ratio considering order book depth
     pub async fn calculate_volume_based_ratio(
         &self,
@@ -86,8 +315,8 @@ impl RatioCalculator {
         volume: f64,
     ) -> Result<VolumeBasedRatio> {
         // Fetch order books
-        let order_book_a = self.client.get_order_book(symbol_a, 100).await?;
-        let order_book_b = self.client.get_order_book(symbol_b, 100).await?;
+        let order_book_a = self.get_order_book(symbol_a).await?;
+        let order_book_b = self.get_order_book(symbol_b).await?;
 
         // Calculate effective prices with slippage
         let (effective_price_a, slippage_a) =
@@ -118,7 +347,7 @@ impl RatioCalculator {
         volume: f64,
         side: OrderSide,
     ) -> Result<SlippageAnalysis> {
-        let order_book = self.client.get_order_book(symbol, 100).await?;
+        let order_book = self.get_order_book(symbol).await?;
 
         let mid_price = (order_book.best_bid + order_book.best_ask) / 2.0;
         let (effective_price, slippage_pct, depth_consumed, total_cost) =
@@ -208,6 +437,14 @@ impl RatioCalculator {
     }
 }
 
+/// Format a leg value: dollar price for symbols, raw ratio for referenced pairs
+pub fn format_leg_value(leg: Leg<'_>, value: f64) -> String {
+    match leg {
+        Leg::Symbol(_) => format!("${:.2}", value),
+        Leg::Pair(_) => format!("{:.8}", value),
+    }
+}
+
 impl SimpleRatio {
     pub fn format_summary(&self) -> String {
         format!(
@@ -222,6 +459,31 @@ impl SimpleRatio {
     }
 }
 
+impl CrossRatio {
+    pub fn is_synthetic(&self) -> bool {
+        matches!(self.route, CrossRoute::Synthetic { .. })
+    }
+
+    pub fn format_summary(&self) -> String {
+        match &self.route {
+            CrossRoute::Direct { symbol, .. } => format!(
+                "{}: {:.8} (direct market {})",
+                self.pair_name, self.ratio, symbol
+            ),
+            CrossRoute::Synthetic {
+                via,
+                symbol_a,
+                price_a,
+                symbol_b,
+                price_b,
+            } => format!(
+                "{}: {:.8} (synthetic via {}: {}=${:.2} / {}=${:.2})",
+                self.pair_name, self.ratio, via, symbol_a, price_a, symbol_b, price_b
+            ),
+        }
+    }
+}
+
 impl VolumeBasedRatio {
     pub fn format_summary(&self) -> String {
         format!(
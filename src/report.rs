@@ -0,0 +1,247 @@
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc, Weekday};
+
+use crate::database::RatioRecord;
+use crate::i18n::{Texts, fill};
+use crate::notifier::Line;
+
+/// One pair's activity over a report period
+#[derive(Debug, Clone)]
+pub struct PairDaySummary {
+    pub pair_name: String,
+    pub open: f64,
+    pub close: f64,
+    pub min: f64,
+    pub max: f64,
+    pub alerts: i64,
+    /// Largest move between two snapshots in the period, in either direction
+    pub swing_pct: f64,
+}
+
+impl PairDaySummary {
+    /// Summarize snapshots ordered oldest first; `None` if there are none
+    pub fn from_snapshots(pair_name: &str, records: &[RatioRecord], alerts: i64) -> Option<Self> {
+        let first = records.first()?;
+        let last = records.last()?;
+
+        let mut min = first.ratio;
+        let mut max = first.ratio;
+        let mut swing_pct: f64 = 0.0;
+
+        for record in records {
+            // Run-up from the lowest point so far, or drawdown from the highest
+            let up = (record.ratio - min) / min * 100.0;
+            let down = (record.ratio - max) / max * 100.0;
+            for swing in [up, down] {
+                if swing.abs() > swing_pct.abs() {
+                    swing_pct = swing;
+                }
+            }
+
+            min = min.min(record.ratio);
+            max = max.max(record.ratio);
+        }
+
+        Some(Self {
+            pair_name: pair_name.to_string(),
+            open: first.ratio,
+            close: last.ratio,
+            min,
+            max,
+            alerts,
+            swing_pct,
+        })
+    }
+
+    pub fn change_pct(&self) -> f64 {
+        (self.close - self.open) / self.open * 100.0
+    }
+
+    /// Section for the daily report
+    pub fn lines(&self, t: &Texts) -> Vec<Line> {
+        vec![
+            Line::new().bold(&self.pair_name),
+            Line::new()
+                .text(t.open)
+                .code(format!("{:.8}", self.open))
+                .text(t.close)
+                .code(format!("{:.8}", self.close))
+                .text(format!(" ({:+.2}%)", self.change_pct())),
+            Line::new()
+                .text(t.min)
+                .code(format!("{:.8}", self.min))
+                .text(t.max)
+                .code(format!("{:.8}", self.max)),
+            Line::new()
+                .text(t.biggest_swing)
+                .code(format!("{:+.2}%", self.swing_pct))
+                .text(fill(t.alerts, &[("count", &self.alerts)])),
+        ]
+    }
+}
+
+/// Average, dispersion and range of a pair's ratio over a week
+#[derive(Debug, Clone, Copy)]
+pub struct WeekStats {
+    pub avg: f64,
+    /// Standard deviation of the ratio as a percentage of its average
+    pub volatility_pct: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl WeekStats {
+    /// `None` if there are no snapshots
+    pub fn from_snapshots(records: &[RatioRecord]) -> Option<Self> {
+        if records.is_empty() {
+            return None;
+        }
+
+        let n = records.len() as f64;
+        let avg = records.iter().map(|r| r.ratio).sum::<f64>() / n;
+        let variance = records.iter().map(|r| (r.ratio - avg).powi(2)).sum::<f64>() / n;
+        let min = records
+            .iter()
+            .map(|r| r.ratio)
+            .fold(f64::INFINITY, f64::min);
+        let max = records
+            .iter()
+            .map(|r| r.ratio)
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        Some(Self {
+            avg,
+            volatility_pct: variance.sqrt() / avg * 100.0,
+            min,
+            max,
+        })
+    }
+}
+
+/// A pair's week compared with the week before
+#[derive(Debug, Clone)]
+pub struct PairWeekComparison {
+    pub pair_name: String,
+    pub current: WeekStats,
+    pub previous: Option<WeekStats>,
+}
+
+impl PairWeekComparison {
+    /// Whether this week's ratio left the prior week's range: above, below or both
+    pub fn breakout(&self, t: &Texts) -> Option<&'static str> {
+        let previous = self.previous?;
+        match (
+            self.current.max > previous.max,
+            self.current.min < previous.min,
+        ) {
+            (true, true) => Some(t.above_and_below),
+            (true, false) => Some(t.above),
+            (false, true) => Some(t.below),
+            (false, false) => None,
+        }
+    }
+
+    /// Section for the weekly report
+    pub fn lines(&self, t: &Texts) -> Vec<Line> {
+        let mut avg = Line::new()
+            .text(t.avg)
+            .code(format!("{:.8}", self.current.avg));
+        let volatility = Line::new()
+            .text(t.volatility)
+            .code(format!("{:.2}%", self.current.volatility_pct));
+
+        let volatility = match self.previous {
+            Some(previous) => {
+                let avg_change = (self.current.avg - previous.avg) / previous.avg * 100.0;
+                avg = avg.text(fill(
+                    t.week_over_week,
+                    &[("change", &format!("{:+.2}", avg_change))],
+                ));
+                volatility
+                    .text(t.versus)
+                    .code(format!("{:.2}%", previous.volatility_pct))
+                    .text(t.last_week)
+            }
+            None => volatility.text(t.no_prior_week),
+        };
+
+        vec![
+            Line::new().bold(&self.pair_name),
+            avg,
+            volatility,
+            Line::new()
+                .text(t.range)
+                .code(format!("{:.8}", self.current.min))
+                .text(" – ")
+                .code(format!("{:.8}", self.current.max)),
+        ]
+    }
+}
+
+/// Block characters of a sparkline, lowest first
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Compact trend of snapshots ordered oldest first, one character per
+/// bucket of `bucket` length ending at `end` and a space for a bucket with
+/// no data. `None` with fewer than two buckets of data.
+pub fn sparkline(
+    records: &[RatioRecord],
+    end: DateTime<Utc>,
+    buckets: usize,
+    bucket: chrono::Duration,
+) -> Option<String> {
+    let start = end - bucket * buckets as i32;
+    // Last ratio in each bucket
+    let mut values: Vec<Option<f64>> = vec![None; buckets];
+    for record in records {
+        let offset = (record.timestamp - start).num_seconds();
+        if offset < 0 {
+            continue;
+        }
+        let index = (offset / bucket.num_seconds().max(1)) as usize;
+        if let Some(value) = values.get_mut(index.min(buckets - 1)) {
+            *value = Some(record.ratio);
+        }
+    }
+
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    if present.len() < 2 {
+        return None;
+    }
+    let min = present.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = present.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let line = values
+        .iter()
+        .map(|value| match value {
+            Some(_) if max == min => SPARK_LEVELS[SPARK_LEVELS.len() / 2],
+            Some(v) => {
+                let level = ((v - min) / (max - min) * (SPARK_LEVELS.len() - 1) as f64).round();
+                SPARK_LEVELS[level as usize]
+            }
+            None => ' ',
+        })
+        .collect::<String>();
+
+    // Buckets before the first snapshot aren't gaps, just no history yet
+    Some(line.trim_start().to_string())
+}
+
+/// Next time after `after` at local `time` in its timezone, on one of `days` (any day if empty)
+pub fn next_occurrence<Tz: TimeZone>(
+    days: &[Weekday],
+    time: NaiveTime,
+    after: DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+    // A week ahead always contains a matching day; one more covers DST gaps
+    (0..=8).find_map(|offset| {
+        let date = after.date_naive() + chrono::Days::new(offset);
+        if !days.is_empty() && !days.contains(&date.weekday()) {
+            return None;
+        }
+        after
+            .timezone()
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .filter(|at| *at > after)
+    })
+}
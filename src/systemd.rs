@@ -0,0 +1,69 @@
+use std::time::Duration;
+use tokio::time::{Instant, sleep_until};
+
+/// Tell systemd the monitor is up and checking (a no-op outside a `Type=notify` unit)
+pub fn notify_ready() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Ready]);
+}
+
+/// Tell systemd the monitor is shutting down on purpose
+pub fn notify_stopping() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Stopping]);
+}
+
+#[cfg(unix)]
+fn notify(state: &[sd_notify::NotifyState]) {
+    if let Err(e) = sd_notify::notify(false, state) {
+        log::warn!("Failed to notify systemd: {}", e);
+    }
+}
+
+/// Keep-alive pings for systemd's watchdog, which restarts the service when
+/// they stop arriving within `WatchdogSec` (disabled when that isn't set)
+pub struct Watchdog {
+    /// Half the watchdog timeout, as systemd recommends; `None` when disabled
+    interval: Option<Duration>,
+    last_ping: Instant,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        #[cfg(unix)]
+        let interval = {
+            let mut usec = 0;
+            sd_notify::watchdog_enabled(false, &mut usec).then(|| Duration::from_micros(usec) / 2)
+        };
+
+        #[cfg(not(unix))]
+        let interval = None;
+
+        if let Some(interval) = interval {
+            log::info!("systemd watchdog enabled, pinging every {:?}", interval);
+        }
+
+        Self {
+            interval,
+            last_ping: Instant::now(),
+        }
+    }
+
+    pub fn ping(&mut self) {
+        if self.interval.is_none() {
+            return;
+        }
+
+        #[cfg(unix)]
+        notify(&[sd_notify::NotifyState::Watchdog]);
+        self.last_ping = Instant::now();
+    }
+
+    /// Resolves when the next ping is due; never resolves when disabled
+    pub async fn due(&self) {
+        match self.interval {
+            Some(interval) => sleep_until(self.last_ping + interval).await,
+            None => std::future::pending::<()>().await,
+        }
+    }
+}
@@ -1,112 +1,885 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use teloxide::{ApiError, RequestError};
 use teloxide::prelude::*;
-use teloxide::types::ChatId;
+use teloxide::types::{
+    ChatId, InlineKeyboardButton, InlineKeyboardMarkup, InputFile, MessageId, ParseMode, ReplyParameters, ThreadId,
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::{RatioPair, SilentConfig, TelegramRecipient};
+use crate::database::Database;
+use crate::i18n::{self, Language};
+use crate::markdown::{self, Markdown};
+use crate::notifier::{Alert, AlertKind, Line, Message, Notifier, PartialFailure, Span};
+
+/// A chat, and the forum topic within it for supergroups with topics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Destination {
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+}
+
+impl Destination {
+    fn new(chat_id: i64, thread_id: Option<i32>) -> Self {
+        Self {
+            chat_id: ChatId(chat_id),
+            thread_id: thread_id.map(|id| ThreadId(MessageId(id))),
+        }
+    }
+
+    /// Name for the notification queue: `chat_id`, or `chat_id/thread_id`
+    fn key(&self) -> String {
+        match self.thread_id {
+            Some(thread_id) => format!("{}/{}", self.chat_id, thread_id.0),
+            None => self.chat_id.to_string(),
+        }
+    }
+
+    fn from_key(key: &str) -> Result<Self> {
+        let (chat_id, thread_id) = match key.split_once('/') {
+            Some((chat_id, thread_id)) => (chat_id, Some(thread_id)),
+            None => (key, None),
+        };
+        let chat_id = chat_id
+            .parse()
+            .with_context(|| format!("Invalid Telegram destination '{}'", key))?;
+        let thread_id = thread_id
+            .map(|id| id.parse())
+            .transpose()
+            .with_context(|| format!("Invalid Telegram destination '{}'", key))?;
+        Ok(Self::new(chat_id, thread_id))
+    }
+}
+
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.thread_id {
+            Some(thread_id) => write!(f, "{} (topic {})", self.chat_id, thread_id.0),
+            None => write!(f, "{}", self.chat_id),
+        }
+    }
+}
+
+/// Attempts per message before a network error is given up on. Rate limits
+/// don't count: the wait Telegram asks for is always honored.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait after the first network error, doubled for each further attempt
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest text Telegram accepts in one message, in UTF-16 code units
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Room kept for the " (2/3)" part counter added to the title of split messages
+const PART_COUNTER_LENGTH: usize = 16;
+
+/// Snooze durations offered under a new breach, in hours
+const SNOOZE_HOURS: [u64; 3] = [1, 4, 24];
+
+/// A message waiting in the send queue
+struct Outgoing {
+    destination: Destination,
+    content: Content,
+    silent: bool,
+    /// Message in the same chat that a new message replies to
+    reply_to: Option<MessageId>,
+    /// The id of the message sent, edited or pinned
+    sent: oneshot::Sender<Result<MessageId, RequestError>>,
+}
+
+/// How an alert relates to earlier alerts about its pair
+#[derive(Debug, Clone, Copy)]
+enum Thread<'a> {
+    Standalone,
+    /// A new breach: later follow-ups reply to it
+    Opens(&'a str),
+    /// An escalation, replying to the breach
+    Continues(&'a str),
+    /// A recovery, replying to the breach and ending its thread
+    Closes(&'a str),
+}
+
+/// What an outgoing message carries
+enum Content {
+    Text {
+        text: String,
+        markdown: bool,
+        /// Inline buttons under the message
+        keyboard: Option<InlineKeyboardMarkup>,
+    },
+    /// A PNG image, sent as a photo
+    Photo(Vec<u8>),
+    /// New text for a message sent earlier
+    Edit {
+        message_id: MessageId,
+        text: String,
+        markdown: bool,
+    },
+    Pin(MessageId),
+}
+
+impl Content {
+    fn is_markdown(&self) -> bool {
+        match self {
+            Content::Text { markdown, .. } | Content::Edit { markdown, .. } => *markdown,
+            Content::Photo(_) | Content::Pin(_) => false,
+        }
+    }
+}
 
 pub struct TelegramNotifier {
+    /// Messages are sent one at a time, so a rate limit holds back the
+    /// messages behind it instead of failing them too
+    queue: mpsc::UnboundedSender<Outgoing>,
+    recipients: Vec<TelegramRecipient>,
+    /// Chats that receive one pair's alerts instead of the recipients
+    pair_routes: HashMap<String, Destination>,
+    escalation: Option<Destination>,
+    timezone: Tz,
+    language: Language,
+    /// For lookups outside the send queue
     bot: Bot,
-    chat_id: ChatId,
+    /// Keep a pinned live status message instead of sending periodic updates
+    live_status: bool,
+    silent_periodic: bool,
+    silent_reports: bool,
+    /// Render notifications without MarkdownV2
+    plain_text: bool,
+    /// Where chats subscribed to a pair through the bot are looked up, when
+    /// subscribers get its alerts instead of the recipients
+    subscriptions: Option<Database>,
+    status_messages: Mutex<HashMap<Destination, MessageId>>,
+    /// First message of each pair's open breach per chat, replied to by follow-ups
+    threads: Mutex<HashMap<(Destination, String), MessageId>>,
 }
 
 impl TelegramNotifier {
     pub fn new(token: &str, user_id: i64) -> Self {
+        let (queue, pending) = mpsc::unbounded_channel();
+        let bot = Bot::new(token);
+        tokio::spawn(run_queue(bot.clone(), pending));
+
         Self {
-            bot: Bot::new(token),
-            chat_id: ChatId(user_id),
+            queue,
+            recipients: vec![TelegramRecipient {
+                chat_id: user_id,
+                pairs: Vec::new(),
+                severities: Vec::new(),
+                message_thread_id: None,
+                pair_topics: Default::default(),
+            }],
+            pair_routes: HashMap::new(),
+            escalation: None,
+            timezone: Tz::UTC,
+            language: Language::default(),
+            bot,
+            live_status: false,
+            silent_periodic: false,
+            silent_reports: false,
+            plain_text: false,
+            subscriptions: None,
+            status_messages: Mutex::new(HashMap::new()),
+            threads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Notify these chats instead of the user, each with its own filters
+    pub fn with_recipients(mut self, recipients: &[TelegramRecipient]) -> Self {
+        if !recipients.is_empty() {
+            self.recipients = recipients.to_vec();
         }
+        self
+    }
+
+    /// Send the alerts of pairs routed to a chat there instead of to the recipients
+    pub fn with_pair_routes(mut self, pairs: &[RatioPair]) -> Self {
+        self.pair_routes = pairs
+            .iter()
+            .filter_map(|pair| {
+                let route = pair.route.as_ref()?;
+                let chat_id = route.chat_id?;
+                Some((pair.name.clone(), Destination::new(chat_id, route.message_thread_id)))
+            })
+            .collect();
+        self
+    }
+
+    /// Send escalated reminders to a different chat, optionally to one of its topics
+    pub fn with_escalation_chat(mut self, chat_id: Option<i64>, thread_id: Option<i32>) -> Self {
+        self.escalation = chat_id.map(|chat_id| Destination::new(chat_id, thread_id));
+        self
     }
 
-    /// Send a text message to the configured user
+    /// Show message timestamps in this timezone instead of UTC
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Label message timestamps in this language
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Keep one pinned message per recipient with the latest ratios, edited
+    /// in place, and send no periodic updates
+    pub fn with_live_status(mut self, live_status: bool) -> Self {
+        self.live_status = live_status;
+        self
+    }
+
+    /// Send periodic updates and reports with notifications disabled as configured;
+    /// alerts carry their own setting
+    pub fn with_silent(mut self, silent: &SilentConfig) -> Self {
+        self.silent_periodic = silent.periodic;
+        self.silent_reports = silent.reports;
+        self
+    }
+
+    /// Send notifications as plain text instead of MarkdownV2
+    pub fn with_plain_text(mut self, plain_text: bool) -> Self {
+        self.plain_text = plain_text;
+        self
+    }
+
+    /// Send each pair's alerts to the chats subscribed to it instead of the recipients
+    pub fn with_subscriptions(mut self, database: Option<Database>) -> Self {
+        self.subscriptions = database;
+        self
+    }
+
+    /// Send a text message to every recipient
+    #[allow(dead_code)]
     pub async fn send_message(&self, message: &str) -> Result<()> {
-        self.bot
-            .send_message(self.chat_id, message)
-            .await
-            .context("Failed to send Telegram message")?;
+        for destination in self.all_destinations() {
+            self.send(destination, message.to_string(), false, false)
+                .await
+                .context("Failed to send Telegram message")?;
+        }
 
         Ok(())
     }
 
-    /// Send a formatted ratio alert message
-    pub async fn send_ratio_alert(&self, pair_name: &str, ratio: f64, change_pct: f64, time_window: &str) -> Result<()> {
-        let emoji = if change_pct > 0.0 { "📈" } else { "📉" };
-        let time_str = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
-        let message = format!(
-            "{} *Ratio Alert: {}*\n\n\
-            Current Ratio: `{:.8}`\n\
-            Change: `{:+.2}%` in {}\n\
-            Time: {}",
-            emoji,
-            escape_markdown(pair_name),
-            ratio,
-            change_pct,
-            escape_markdown(time_window),
-            escape_markdown(&time_str)
-        );
-
-        self.bot
-            .send_message(self.chat_id, message)
-            .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+    /// Where an alert goes: its escalation or severity tier chat when one is
+    /// set, then its pair's chat, then the pair's subscribers when
+    /// subscriptions are on and anyone subscribed, otherwise every recipient
+    /// whose filters it passes, in the pair's topic if the recipient has one
+    fn alert_destinations(&self, alert: &Alert, subscribers: Option<Vec<i64>>) -> Vec<Destination> {
+        let pair = alert.details.as_ref().map(|d| d.pair.as_str());
+        let routed = match alert.kind {
+            AlertKind::Escalation => self.escalation,
+            AlertKind::Alert => alert
+                .tier
+                .as_ref()
+                .and_then(|t| t.chat_id.map(|chat_id| Destination::new(chat_id, t.message_thread_id))),
+            AlertKind::Notice => None,
+        }
+        .or_else(|| pair.and_then(|p| self.pair_routes.get(p).copied()));
+        if let Some(destination) = routed {
+            return vec![destination];
+        }
+        match subscribers {
+            Some(subscribers) if !subscribers.is_empty() => {
+                return subscribers
+                    .into_iter()
+                    .map(|chat_id| Destination::new(chat_id, None))
+                    .collect();
+            }
+            Some(_) => log::warn!(
+                "No chat is subscribed to {}; sending its alert to the recipients",
+                pair.unwrap_or_default()
+            ),
+            None => {}
+        }
+
+        self.recipients
+            .iter()
+            .filter(|r| accepts(r, alert))
+            .map(|r| {
+                let topic = pair.and_then(|p| r.pair_topics.get(p).copied());
+                Destination::new(r.chat_id, topic.or(r.message_thread_id))
+            })
+            .collect()
+    }
+
+    fn all_destinations(&self) -> Vec<Destination> {
+        self.recipients
+            .iter()
+            .map(|r| Destination::new(r.chat_id, r.message_thread_id))
+            .collect()
+    }
+
+    /// Render a message to MarkdownV2, or plain text if configured: title,
+    /// sections separated by blank lines, then the time in the configured
+    /// timezone. A message too long for Telegram is split into parts between
+    /// sections, or between lines of a long section; each part repeats the
+    /// title with a part counter and the time goes on the last.
+    fn render(&self, message: &Message) -> Vec<String> {
+        let render_line = |line: &Line| {
+            if self.plain_text {
+                line.plain_text()
+            } else {
+                render_line(line)
+            }
+        };
+
+        let title = render_line(&message.title);
+        let footer = message.time.map(|time| {
+            let time_str = time
+                .with_timezone(&self.timezone)
+                .format("%Y-%m-%d %H:%M:%S %Z")
+                .to_string();
+            let label = self.language.texts().time;
+            if self.plain_text {
+                format!("{}: {}", label, time_str)
+            } else {
+                Markdown::new().italic(&format!("{}: {}", label, time_str)).into()
+            }
+        });
+
+        // What's left of a part after the title, counter, footer and blank lines
+        let budget = MAX_MESSAGE_LENGTH
+            .saturating_sub(text_length(&title) + PART_COUNTER_LENGTH)
+            .saturating_sub(footer.as_deref().map_or(0, text_length))
+            .saturating_sub(4);
+
+        let mut parts: Vec<Vec<String>> = vec![Vec::new()];
+        for section in &message.sections {
+            let lines: Vec<String> = section.iter().map(&render_line).collect();
+            for block in split_lines(&lines, budget) {
+                let current = parts.last_mut().expect("parts is never empty");
+                let length: usize = current.iter().map(|p| text_length(p) + 2).sum();
+                if !current.is_empty() && length + text_length(&block) > budget {
+                    parts.push(vec![block]);
+                } else {
+                    current.push(block);
+                }
+            }
+        }
+
+        let count = parts.len();
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(i, body)| {
+                let mut paragraphs = vec![if count > 1 && self.plain_text {
+                    format!("{} ({}/{})", title, i + 1, count)
+                } else if count > 1 {
+                    format!("{} \\({}/{}\\)", title, i + 1, count)
+                } else {
+                    title.clone()
+                }];
+                paragraphs.extend(body);
+                if i + 1 == count {
+                    paragraphs.extend(footer.clone());
+                }
+                paragraphs.join("\n\n")
+            })
+            .collect()
+    }
+
+    /// Queue a text message and wait until it is sent or has failed for good
+    async fn send(&self, destination: Destination, text: String, markdown: bool, silent: bool) -> Result<()> {
+        let content = Content::Text {
+            text,
+            markdown,
+            keyboard: None,
+        };
+        self.enqueue(destination, content, silent, None)
             .await
-            .context("Failed to send ratio alert")?;
+            .map(|_| ())
+    }
 
-        Ok(())
+    async fn enqueue(
+        &self,
+        destination: Destination,
+        content: Content,
+        silent: bool,
+        reply_to: Option<MessageId>,
+    ) -> Result<MessageId> {
+        let (sent, result) = oneshot::channel();
+        self.queue
+            .send(Outgoing {
+                destination,
+                content,
+                silent,
+                reply_to,
+                sent,
+            })
+            .map_err(|_| anyhow::anyhow!("Telegram send queue has stopped"))?;
+
+        result
+            .await
+            .context("Telegram send queue has stopped")?
+            .map_err(anyhow::Error::from)
     }
 
-    /// Send a periodic ratio update
-    pub async fn send_periodic_update(&self, updates: &[String]) -> Result<()> {
-        let time_str = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
-        let message = format!(
-            "📊 *Periodic Ratio Update*\n\n{}\n\n_Time: {}_",
-            updates.join("\n\n"),
-            escape_markdown(&time_str)
-        );
+    /// Send to several chats, followed by the chart if there is one; one
+    /// chat failing doesn't stop the others. Follow-ups reply to the alert
+    /// that opened their pair's thread in the same chat.
+    async fn send_markdown(
+        &self,
+        destinations: &[Destination],
+        message: &Message,
+        chart: Option<&[u8]>,
+        silent: bool,
+        thread: Thread<'_>,
+    ) -> Result<()> {
+        let parts = self.render(message);
+        let results = join_all(destinations.iter().map(|&destination| {
+            let parts = &parts;
+            async move {
+                let mut reply_to = match thread {
+                    Thread::Continues(pair) | Thread::Closes(pair) => {
+                        self.threads.lock().unwrap().get(&(destination, pair.to_string())).copied()
+                    }
+                    Thread::Standalone | Thread::Opens(_) => None,
+                };
+
+                let mut first = None;
+                for (i, part) in parts.iter().enumerate() {
+                    // Buttons go under the last part
+                    let keyboard = match thread {
+                        Thread::Opens(pair) | Thread::Continues(pair) if i + 1 == parts.len() => {
+                            Some(self.alert_keyboard(pair, matches!(thread, Thread::Opens(_))))
+                        }
+                        _ => None,
+                    };
+                    let content = Content::Text {
+                        text: part.clone(),
+                        markdown: !self.plain_text,
+                        keyboard,
+                    };
+                    let message_id = self.enqueue(destination, content, silent, reply_to.take()).await?;
+                    first.get_or_insert(message_id);
+                }
+                if let Some(chart) = chart {
+                    self.enqueue(destination, Content::Photo(chart.to_vec()), silent, None)
+                        .await?;
+                }
+
+                let mut threads = self.threads.lock().unwrap();
+                match (thread, first) {
+                    // Further breaches before recovery keep the first as the root
+                    (Thread::Opens(pair), Some(message_id)) => {
+                        threads.entry((destination, pair.to_string())).or_insert(message_id);
+                    }
+                    (Thread::Closes(pair), _) => {
+                        threads.remove(&(destination, pair.to_string()));
+                    }
+                    _ => {}
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+        }))
+        .await;
+
+        combine_results(destinations, results)
+    }
 
-        self.bot
-            .send_message(self.chat_id, message)
-            .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+    async fn send_alert_to(&self, destinations: &[Destination], alert: &Alert) -> Result<()> {
+        let thread = match (&alert.details, alert.kind, alert.follow_up) {
+            (Some(details), AlertKind::Escalation, true) => Thread::Continues(&details.pair),
+            (Some(details), _, true) => Thread::Closes(&details.pair),
+            (Some(details), AlertKind::Alert, false) => Thread::Opens(&details.pair),
+            _ => Thread::Standalone,
+        };
+        self.send_markdown(destinations, &alert.message, alert.chart.as_deref(), alert.silent, thread)
             .await
-            .context("Failed to send periodic update")?;
+            .context("Failed to send Telegram alert")
+    }
+
+    async fn send_report_to(&self, destinations: &[Destination], message: &Message) -> Result<()> {
+        self.send_markdown(destinations, message, None, self.silent_reports, Thread::Standalone)
+            .await
+            .context("Failed to send report")
+    }
+
+    /// Buttons under a breach alert, handled by the bot: acknowledge, which
+    /// stops escalation, and on the first alert of a breach, snooze the pair
+    fn alert_keyboard(&self, pair: &str, snooze: bool) -> InlineKeyboardMarkup {
+        let t = self.language.texts();
+        let mut rows = vec![vec![InlineKeyboardButton::callback(
+            t.button_ack,
+            format!("ack:{}", pair),
+        )]];
+        if snooze {
+            let buttons = SNOOZE_HOURS.iter().map(|hours| {
+                let duration = format!("{}h", hours);
+                InlineKeyboardButton::callback(
+                    i18n::fill(t.button_snooze, &[("duration", &duration)]),
+                    format!("snooze:{}:{}", hours, pair),
+                )
+            });
+            rows.push(buttons.collect());
+        }
+        InlineKeyboardMarkup::new(rows)
+    }
+
+    /// Edit the destination's live status message, or send and pin a new one
+    /// if there is none or it was deleted
+    async fn update_status(&self, destination: Destination, title: &str, text: &str) -> Result<()> {
+        let known = self.status_messages.lock().unwrap().get(&destination).copied();
+        let message_id = match known {
+            Some(message_id) => Some(message_id),
+            None => self.find_pinned_status(destination, title).await,
+        };
+
+        if let Some(message_id) = message_id {
+            let edit = Content::Edit {
+                message_id,
+                text: text.to_string(),
+                markdown: !self.plain_text,
+            };
+            match self.enqueue(destination, edit, true, None).await {
+                Ok(_) => {
+                    self.status_messages.lock().unwrap().insert(destination, message_id);
+                    return Ok(());
+                }
+                // Telegram refused the edit, e.g. the message was deleted
+                Err(e) if matches!(e.downcast_ref(), Some(RequestError::Api(_))) => {
+                    log::warn!("Live status in chat {} can't be edited, sending a new one: {}", destination, e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let text = Content::Text {
+            text: text.to_string(),
+            markdown: !self.plain_text,
+            keyboard: None,
+        };
+        let message_id = self.enqueue(destination, text, true, None).await?;
+        self.status_messages.lock().unwrap().insert(destination, message_id);
+        if let Err(e) = self.enqueue(destination, Content::Pin(message_id), true, None).await {
+            log::warn!("Failed to pin live status in chat {}: {}", destination, e);
+        }
 
         Ok(())
     }
 
+    /// This bot's pinned live status from before a restart, recognized by its title
+    async fn find_pinned_status(&self, destination: Destination, title: &str) -> Option<MessageId> {
+        let me = self.bot.get_me().await.ok()?;
+        let chat = self.bot.get_chat(destination.chat_id).await.ok()?;
+        let pinned = chat.pinned_message?;
+
+        let ours = pinned.from.as_ref().is_some_and(|user| user.id == me.id);
+        let same_topic = destination.thread_id.is_none() || pinned.thread_id == destination.thread_id;
+        let is_status = pinned.text().is_some_and(|text| text.starts_with(title));
+        (ours && same_topic && is_status).then_some(pinned.id)
+    }
+
     /// Send a slippage analysis message
+    #[allow(dead_code)]
     pub async fn send_slippage_analysis(&self, analysis: &str) -> Result<()> {
-        let message = format!(
-            "🔍 *Slippage Analysis*\n\n```\n{}\n```",
-            analysis
-        );
-
-        self.bot
-            .send_message(self.chat_id, message)
-            .parse_mode(teloxide::types::ParseMode::MarkdownV2)
-            .await
-            .context("Failed to send slippage analysis")?;
+        let message: String = Markdown::new()
+            .text("🔍 ")
+            .bold("Slippage Analysis")
+            .paragraph()
+            .pre(analysis)
+            .into();
+
+        for destination in self.all_destinations() {
+            self.send(destination, message.clone(), true, false)
+                .await
+                .context("Failed to send slippage analysis")?;
+        }
 
         Ok(())
     }
 
-    /// Test the connection by sending a test message
+    /// Test the connection by sending a test message to every recipient
     pub async fn test_connection(&self) -> Result<()> {
         let message = "✅ Ratio-Noti bot is connected and ready!";
 
-        self.bot
-            .send_message(self.chat_id, message)
-            .await
-            .context("Failed to send test message")?;
+        for destination in self.all_destinations() {
+            self.send(destination, message.to_string(), false, false)
+                .await
+                .with_context(|| format!("Failed to send test message to chat {}", destination))?;
+        }
 
         Ok(())
     }
 }
 
-/// Escape special characters for Telegram MarkdownV2
-fn escape_markdown(text: &str) -> String {
-    text.chars()
-        .map(|c| match c {
-            '_' | '*' | '[' | ']' | '(' | ')' | '~' | '`' | '>' | '#' | '+' | '-' | '=' | '|'
-            | '{' | '}' | '.' | '!' => {
-                format!("\\{}", c)
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let subscribers = match (&self.subscriptions, &alert.details) {
+            (Some(database), Some(details)) => Some(database.get_subscribers(&details.pair).await?),
+            _ => None,
+        };
+        let destinations = self.alert_destinations(alert, subscribers);
+        self.send_alert_to(&destinations, alert).await
+    }
+
+    async fn send_periodic(&self, message: &Message) -> Result<()> {
+        // Replaced by the live status
+        if self.live_status {
+            return Ok(());
+        }
+
+        self.send_markdown(
+            &self.all_destinations(),
+            message,
+            None,
+            self.silent_periodic,
+            Thread::Standalone,
+        )
+        .await
+        .context("Failed to send periodic update")
+    }
+
+    async fn send_report(&self, message: &Message) -> Result<()> {
+        self.send_report_to(&self.all_destinations(), message).await
+    }
+
+    async fn send_status(&self, message: &Message) -> Result<()> {
+        if !self.live_status {
+            return Ok(());
+        }
+
+        // A status too long for one message shows its first part
+        let Some(text) = self.render(message).into_iter().next() else {
+            return Ok(());
+        };
+        let title = message.title.plain_text();
+        let destinations = self.all_destinations();
+        let results = join_all(
+            destinations
+                .iter()
+                .map(|&destination| self.update_status(destination, &title, &text)),
+        )
+        .await;
+
+        combine_results(&destinations, results).context("Failed to update live status")
+    }
+
+    async fn resend_alert(&self, alert: &Alert, destination: &str) -> Result<()> {
+        self.send_alert_to(&[Destination::from_key(destination)?], alert).await
+    }
+
+    async fn resend_report(&self, message: &Message, destination: &str) -> Result<()> {
+        self.send_report_to(&[Destination::from_key(destination)?], message).await
+    }
+}
+
+/// Send queued messages in order until every notifier handle is dropped
+async fn run_queue(bot: Bot, mut pending: mpsc::UnboundedReceiver<Outgoing>) {
+    while let Some(outgoing) = pending.recv().await {
+        let result = deliver(&bot, &outgoing).await;
+        // The sender may have stopped waiting; the message was still handled
+        let _ = outgoing.sent.send(result);
+    }
+}
+
+/// Send one message, waiting out rate limits and retrying network errors.
+/// MarkdownV2 that Telegram can't parse is sent again as plain text, so a
+/// formatting mistake doesn't lose the message.
+async fn deliver(bot: &Bot, outgoing: &Outgoing) -> Result<MessageId, RequestError> {
+    let mut attempt = 1;
+    let mut plain = false;
+    loop {
+        let chat_id = outgoing.destination.chat_id;
+        let text = |text: &String| if plain { markdown::strip(text) } else { text.clone() };
+        let result = match &outgoing.content {
+            Content::Text { text: body, markdown, keyboard } => {
+                let mut request = bot
+                    .send_message(chat_id, text(body))
+                    .disable_notification(outgoing.silent);
+                request.message_thread_id = outgoing.destination.thread_id;
+                request.reply_parameters = outgoing.reply_to.map(reply_parameters);
+                request.reply_markup = keyboard.clone().map(Into::into);
+                if *markdown && !plain {
+                    request.parse_mode = Some(ParseMode::MarkdownV2);
+                }
+                request.await.map(|message| message.id)
+            }
+            Content::Photo(png) => {
+                let photo = InputFile::memory(png.clone()).file_name("chart.png");
+                let mut request = bot.send_photo(chat_id, photo).disable_notification(outgoing.silent);
+                request.message_thread_id = outgoing.destination.thread_id;
+                request.reply_parameters = outgoing.reply_to.map(reply_parameters);
+                request.await.map(|message| message.id)
+            }
+            Content::Edit {
+                message_id,
+                text: body,
+                markdown,
+            } => {
+                let mut request = bot.edit_message_text(chat_id, *message_id, text(body));
+                if *markdown && !plain {
+                    request.parse_mode = Some(ParseMode::MarkdownV2);
+                }
+                match request.await {
+                    Err(RequestError::Api(ApiError::MessageNotModified)) | Ok(_) => Ok(*message_id),
+                    Err(e) => Err(e),
+                }
+            }
+            Content::Pin(message_id) => bot
+                .pin_chat_message(chat_id, *message_id)
+                .disable_notification(true)
+                .await
+                .map(|_| *message_id),
+        };
+
+        match result {
+            Ok(message_id) => return Ok(message_id),
+            Err(RequestError::Api(ApiError::CantParseEntities(e))) if outgoing.content.is_markdown() && !plain => {
+                log::warn!(
+                    "Telegram couldn't parse the formatting of a message to {}, sending it as plain text: {}",
+                    outgoing.destination,
+                    e
+                );
+                plain = true;
             }
-            _ => c.to_string(),
+            Err(RequestError::RetryAfter(wait)) => {
+                log::warn!(
+                    "Telegram rate limit hit sending to {}, retrying in {}s",
+                    outgoing.destination,
+                    wait.seconds()
+                );
+                tokio::time::sleep(wait.duration()).await;
+            }
+            Err(e @ (RequestError::Network(_) | RequestError::Io(_))) if attempt < MAX_ATTEMPTS => {
+                let delay = RETRY_DELAY * 2u32.pow(attempt - 1);
+                log::warn!(
+                    "Telegram send to {} failed (attempt {}/{}), retrying in {:?}: {}",
+                    outgoing.destination,
+                    attempt,
+                    MAX_ATTEMPTS,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reply to a message, or send anyway if it has been deleted
+fn reply_parameters(message_id: MessageId) -> ReplyParameters {
+    ReplyParameters::new(message_id).allow_sending_without_reply()
+}
+
+/// Log all but the last failure of a send to several chats, and return that
+/// one; as a `PartialFailure` naming the failed chats if any chat got it
+fn combine_results(destinations: &[Destination], results: Vec<Result<()>>) -> Result<()> {
+    let mut last_error = None;
+    let mut failed = Vec::new();
+    for (destination, result) in destinations.iter().zip(results) {
+        if let Err(e) = result {
+            let e = e.context(format!("Failed to send to chat {}", destination));
+            if let Some(previous) = last_error.replace(e) {
+                log::error!("{:#}", previous);
+            }
+            failed.push(destination.key());
+        }
+    }
+
+    match last_error {
+        Some(error) if failed.len() < destinations.len() => Err(PartialFailure { failed, error }.into()),
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Length as Telegram counts it
+fn text_length(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Group a section's lines into blocks that each fit in `budget`; a section
+/// that fits stays whole, and a single line too long for a block is cut
+fn split_lines(lines: &[String], budget: usize) -> Vec<String> {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for line in lines.iter().flat_map(|line| split_line(line, budget)) {
+        if !current.is_empty() && text_length(&current) + 1 + text_length(&line) > budget {
+            blocks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&line);
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+/// Cut a line longer than `budget` into pieces that fit, between characters
+/// but never between a MarkdownV2 escape and the character it escapes
+fn split_line(line: &str, budget: usize) -> Vec<String> {
+    if text_length(line) <= budget {
+        return vec![line.to_string()];
+    }
+
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut length = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        let mut unit = c.to_string();
+        if c == '\\'
+            && let Some(escaped) = chars.next()
+        {
+            unit.push(escaped);
+        }
+
+        let unit_length = text_length(&unit);
+        if !current.is_empty() && length + unit_length > budget {
+            pieces.push(std::mem::take(&mut current));
+            length = 0;
+        }
+        current.push_str(&unit);
+        length += unit_length;
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// Whether an alert passes a recipient's filters. Alerts not about a single
+/// pair (digests, notices) pass the pair filter, and alerts without a severity
+/// tier pass the severity filter.
+fn accepts(recipient: &TelegramRecipient, alert: &Alert) -> bool {
+    let pair_ok = match &alert.details {
+        Some(details) => recipient.pairs.is_empty() || recipient.pairs.contains(&details.pair),
+        None => true,
+    };
+    let severity_ok = match &alert.tier {
+        Some(tier) => recipient.severities.is_empty() || recipient.severities.contains(&tier.severity),
+        None => true,
+    };
+
+    pair_ok && severity_ok
+}
+
+/// MarkdownV2 for one line
+fn render_line(line: &Line) -> String {
+    line.0
+        .iter()
+        .fold(Markdown::new(), |markdown, span| match span {
+            Span::Text(text) => markdown.text(text),
+            Span::Bold(text) => markdown.bold(text),
+            Span::Code(text) => markdown.code(text),
         })
-        .collect()
+        .into()
 }
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use handlebars::{Handlebars, handlebars_helper, no_escape};
+use serde::Serialize;
+
+use crate::config::{Severity, TemplatesConfig};
+use crate::notifier::{Alert, Line, Message, Span};
+
+const ALERT: &str = "alert";
+const PERIODIC: &str = "periodic";
+const DAILY_SUMMARY: &str = "daily_summary";
+
+handlebars_helper!(fixed: |value: f64, digits: u64| format!("{:.*}", digits as usize, value));
+handlebars_helper!(signed: |value: f64, digits: u64| format!("{:+.*}", digits as usize, value));
+
+/// Variables of the `alert` template
+#[derive(Debug, Serialize)]
+pub struct AlertVars<'a> {
+    pub pair: &'a str,
+    pub ratio: f64,
+    pub symbol_a: &'a str,
+    pub symbol_b: &'a str,
+    pub price_a: f64,
+    pub price_b: f64,
+    /// Change in percent, signed
+    pub change: f64,
+    /// "up" or "down"
+    pub direction: &'static str,
+    /// The change window, e.g. "5m", or "day" for daily open alerts
+    pub window: String,
+    /// Highest threshold crossed, and every threshold crossed, lowest first
+    pub threshold: f64,
+    pub thresholds: &'a [f64],
+    /// "info", "warning" or "critical" when severity tiers are configured
+    pub severity: Option<Severity>,
+    pub severity_emoji: Option<&'static str>,
+}
+
+/// One pair in the `periodic` template's `pairs`
+#[derive(Debug, Serialize)]
+pub struct PeriodicPairVars<'a> {
+    pub pair: &'a str,
+    pub ratio: f64,
+    pub symbol_a: &'a str,
+    pub symbol_b: &'a str,
+    pub price_a: f64,
+    pub price_b: f64,
+    /// Hourly trend of the last 24 hours, e.g. "▁▂▃▅▇", if there is enough history
+    pub sparkline: Option<&'a str>,
+}
+
+/// Variables of the `periodic` template
+#[derive(Debug, Serialize)]
+pub struct PeriodicVars<'a> {
+    pub pairs: Vec<PeriodicPairVars<'a>>,
+    /// Monitoring gaps found at startup, reported once
+    pub gaps: &'a [String],
+}
+
+/// One pair in the `daily_summary` template's `pairs`; all but `pair` are
+/// missing when the pair has no data for the day
+#[derive(Debug, Default, Serialize)]
+pub struct DaySummaryVars<'a> {
+    pub pair: &'a str,
+    pub open: Option<f64>,
+    pub close: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub change: Option<f64>,
+    pub swing: Option<f64>,
+    pub alerts: Option<i64>,
+}
+
+/// Variables of the `daily_summary` template
+#[derive(Debug, Serialize)]
+pub struct DailySummaryVars<'a> {
+    pub pairs: Vec<DaySummaryVars<'a>>,
+}
+
+/// User templates replacing the built-in text of some messages. A template's
+/// output becomes the message: its first line the title, blank lines separate
+/// sections, and `*bold*` and `` `code` `` are emphasized where the channel
+/// supports it.
+pub struct Templates {
+    registry: Handlebars<'static>,
+}
+
+impl Default for Templates {
+    /// No templates: every message keeps its built-in text
+    fn default() -> Self {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(no_escape);
+        registry.register_helper("fixed", Box::new(fixed));
+        registry.register_helper("signed", Box::new(signed));
+
+        Self { registry }
+    }
+}
+
+impl Templates {
+    /// Compile the configured templates, failing on syntax errors
+    pub fn new(config: &TemplatesConfig) -> Result<Self> {
+        let mut templates = Self::default();
+
+        let configured = [
+            (ALERT, &config.alert),
+            (PERIODIC, &config.periodic),
+            (DAILY_SUMMARY, &config.daily_summary),
+        ];
+        for (name, template) in configured {
+            let Some(template) = template else {
+                continue;
+            };
+            templates
+                .registry
+                .register_template_string(name, template)
+                .with_context(|| format!("Invalid {} template", name))?;
+        }
+
+        Ok(templates)
+    }
+
+    pub fn alert(&self, mut alert: Alert, vars: &AlertVars) -> Alert {
+        alert.message = self.apply(ALERT, vars, alert.message);
+        alert
+    }
+
+    pub fn periodic(&self, message: Message, vars: &PeriodicVars) -> Message {
+        self.apply(PERIODIC, vars, message)
+    }
+
+    pub fn daily_summary(&self, message: Message, vars: &DailySummaryVars) -> Message {
+        self.apply(DAILY_SUMMARY, vars, message)
+    }
+
+    /// Replace the message's text with the rendered template, keeping its
+    /// time and data. Without a template, or if rendering fails, the built-in
+    /// text is kept.
+    fn apply(&self, name: &str, vars: &impl Serialize, mut message: Message) -> Message {
+        if !self.registry.has_template(name) {
+            return message;
+        }
+
+        let text = match self.registry.render(name, vars) {
+            Ok(text) => text,
+            Err(e) => {
+                log::error!(
+                    "Failed to render {} template, using the default: {}",
+                    name,
+                    e
+                );
+                return message;
+            }
+        };
+
+        let mut lines = text.lines().map(str::trim_end).skip_while(|l| l.is_empty());
+        message.title = lines.next().map(parse_line).unwrap_or_default();
+        message.sections.clear();
+
+        let mut section = Vec::new();
+        for line in lines {
+            if line.is_empty() {
+                if !section.is_empty() {
+                    message.sections.push(std::mem::take(&mut section));
+                }
+            } else {
+                section.push(parse_line(line));
+            }
+        }
+        if !section.is_empty() {
+            message.sections.push(section);
+        }
+
+        message
+    }
+}
+
+/// Split a template line into spans: `*bold*`, `` `code` `` and plain text.
+/// A marker without its closing pair is kept as text.
+fn parse_line(text: &str) -> Line {
+    let mut line = Line::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['*', '`']) {
+        let marker = rest[start..].chars().next().unwrap_or('*');
+        let Some(len) = rest[start + 1..].find(marker) else {
+            break;
+        };
+
+        if start > 0 {
+            line.0.push(Span::Text(rest[..start].to_string()));
+        }
+        let inner = rest[start + 1..start + 1 + len].to_string();
+        line.0.push(if marker == '*' {
+            Span::Bold(inner)
+        } else {
+            Span::Code(inner)
+        });
+        rest = &rest[start + len + 2..];
+    }
+
+    if !rest.is_empty() {
+        line.0.push(Span::Text(rest.to_string()));
+    }
+    line
+}
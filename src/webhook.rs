@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use std::time::Duration;
+
+use crate::notifier::{Alert, AlertKind, Message, Notifier};
+
+/// Header carrying the hex HMAC-SHA256 of the request body, as "sha256=<hex>"
+const SIGNATURE_HEADER: &str = "X-Ratio-Noti-Signature";
+
+/// How long one delivery attempt may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait before the first retry; doubled for each further one
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// POSTs every notification as JSON to a user-supplied URL
+pub struct WebhookNotifier {
+    client: Client,
+    url: String,
+    secret: Option<String>,
+    max_attempts: u32,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str, secret: Option<&str>, max_attempts: u32) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.to_string(),
+            secret: secret.map(str::to_string),
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// Deliver a payload, retrying with backoff on network errors, 429 and 5xx
+    async fn post(&self, payload: &Value) -> Result<()> {
+        let body = serde_json::to_vec(payload)?;
+        let signature = self.secret.as_deref().map(|secret| sign(secret, &body));
+
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .timeout(REQUEST_TIMEOUT)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let retryable = status.is_server_error() || status.as_u16() == 429;
+                    if !retryable {
+                        anyhow::bail!("Webhook returned {}", status);
+                    }
+                    anyhow::anyhow!("Webhook returned {}", status)
+                }
+                Err(e) => e.into(),
+            };
+
+            if attempt >= self.max_attempts {
+                return Err(error.context(format!("Giving up after {} attempts", attempt)));
+            }
+
+            log::warn!(
+                "Webhook delivery attempt {} failed, retrying in {:?}: {:#}",
+                attempt,
+                backoff,
+                error
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let event = match alert.kind {
+            AlertKind::Alert => "alert",
+            AlertKind::Escalation => "escalation",
+            AlertKind::Notice => "notice",
+        };
+
+        let mut payload = payload(event, &alert.message);
+        payload["severity"] = json!(alert.tier.as_ref().map(|t| t.severity));
+        payload["alert"] = json!(alert.details);
+
+        self.post(&payload)
+            .await
+            .context("Failed to send webhook alert")
+    }
+
+    async fn send_periodic(&self, message: &Message) -> Result<()> {
+        self.post(&payload("periodic", message))
+            .await
+            .context("Failed to send webhook periodic update")
+    }
+
+    async fn send_report(&self, message: &Message) -> Result<()> {
+        self.post(&payload("report", message))
+            .await
+            .context("Failed to send webhook report")
+    }
+}
+
+/// Fields shared by every event: the message as plain text and any ratios it reports
+fn payload(event: &str, message: &Message) -> Value {
+    json!({
+        "event": event,
+        "title": message.title.plain_text(),
+        "text": message.body_text(),
+        "time": message.time.unwrap_or_else(chrono::Utc::now),
+        "ratios": message.ratios,
+    })
+}
+
+/// "sha256=<hex>" HMAC of `body` keyed with `secret`
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}